@@ -5,6 +5,8 @@ use thiserror::Error;
 
 use crate::{error::ParseError, reader};
 
+/// Address in the virtual memory of the process. File offsets and sizes have their own types,
+/// `FileOffset` and `Size`, so they cannot be mixed up with it.
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
 pub struct Addr(pub u64);
 
@@ -35,16 +37,16 @@ impl Sub for Addr {
 }
 
 /// Use for serializing
-impl Into<u64> for Addr {
-    fn into(self) -> u64 {
-        self.0
+impl From<Addr> for u64 {
+    fn from(addr: Addr) -> u64 {
+        addr.0
     }
 }
 
 /// Use for serializing
-impl Into<usize> for Addr {
-    fn into(self) -> usize {
-        self.0 as usize
+impl From<Addr> for usize {
+    fn from(addr: Addr) -> usize {
+        addr.0 as usize
     }
 }
 
@@ -56,12 +58,100 @@ impl From<u64> for Addr {
 }
 
 impl Addr {
+    /// Parses an address whose width is given by the class of the `reader`
     pub fn parse(reader: &mut reader::Reader) -> Result<Self, ParseError> {
-        let value = reader.read_u64()?;
+        let value = reader.read_word()?;
         Ok(Self(value))
     }
 }
 
+/// Adds a size to an address, saturating at the end of the address space, as sizes read from
+/// untrusted headers may overflow
+impl Add<Size> for Addr {
+    type Output = Self;
+    fn add(self, size: Size) -> Self {
+        Self(self.0.saturating_add(size.0))
+    }
+}
+
+/// Offset of a byte from the start of the file
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FileOffset(pub u64);
+
+impl fmt::Debug for FileOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:08x}", self.0)
+    }
+}
+
+impl fmt::Display for FileOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Adds a size to an offset, saturating at the end of the address space
+impl Add<Size> for FileOffset {
+    type Output = Self;
+    fn add(self, size: Size) -> Self {
+        Self(self.0.saturating_add(size.0))
+    }
+}
+
+impl From<FileOffset> for u64 {
+    fn from(offset: FileOffset) -> u64 {
+        offset.0
+    }
+}
+
+impl From<FileOffset> for usize {
+    fn from(offset: FileOffset) -> usize {
+        offset.0 as usize
+    }
+}
+
+impl FileOffset {
+    /// Parses an offset whose width is given by the class of the `reader`
+    pub fn parse(reader: &mut reader::Reader) -> Result<Self, ParseError> {
+        Ok(Self(reader.read_word()?))
+    }
+}
+
+/// Size, in bytes, of something in the file or in memory
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Size(pub u64);
+
+impl fmt::Debug for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:x}", self.0)
+    }
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl From<Size> for u64 {
+    fn from(size: Size) -> u64 {
+        size.0
+    }
+}
+
+impl From<Size> for usize {
+    fn from(size: Size) -> usize {
+        size.0 as usize
+    }
+}
+
+impl Size {
+    /// Parses a size whose width is given by the class of the `reader`
+    pub fn parse(reader: &mut reader::Reader) -> Result<Self, ParseError> {
+        Ok(Self(reader.read_word()?))
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Failed integer conversion {0}")]
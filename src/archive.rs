@@ -0,0 +1,188 @@
+//! Module describing static archives (`.a` files), which bundle object files one after the other,
+//! each behind a textual header.
+use thiserror::Error;
+
+use crate::{Elf64, ElfError, ParseError, Reader};
+
+/// Magic bytes every archive starts with
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+/// Size of the header in front of every member
+const AR_HEADER_SIZE: usize = 60;
+/// Bytes terminating every member header
+const AR_FMAG: &[u8] = b"`\n";
+
+/// A static archive, which lends out its members without copying them
+pub struct Archive<'a> {
+    bytes: &'a [u8],
+    /// Contents of the `//` member, holding the names which do not fit in a member header
+    extended_names: Option<&'a [u8]>,
+}
+
+impl<'a> Archive<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        if !bytes.starts_with(AR_MAGIC) {
+            return Err(Error::BadMagic);
+        }
+
+        let mut archive = Self { bytes, extended_names: None };
+        // The extended names table comes before any member using it, but we look it up upfront to
+        // be able to hand out members in any order
+        for header in archive.raw_members() {
+            let header = header?;
+            if header.name == b"//" {
+                archive.extended_names = Some(header.data);
+                break;
+            }
+        }
+
+        Ok(archive)
+    }
+
+    /// Returns an iterator over the members of the archive, skipping the symbol lookup table and
+    /// the extended names table, which are only used by the linker.
+    pub fn members(&self) -> impl Iterator<Item = Result<Member<'a>, Error>> + '_ {
+        self.raw_members().filter_map(move |header| {
+            let header = match header {
+                Ok(header) => header,
+                Err(err) => return Some(Err(err)),
+            };
+            match header.name {
+                b"/" | b"/SYM64/" | b"//" | b"__.SYMDEF" | b"__.SYMDEF SORTED" => None,
+                _ => Some(self.member(header)),
+            }
+        })
+    }
+
+    /// Returns an iterator over the members of the archive, parsed as Elf files
+    pub fn objects(&self) -> impl Iterator<Item = Result<(String, Elf64<'a>), Error>> + '_ {
+        self.members().map(|member| {
+            let member = member?;
+            let elf = member.parse()?;
+            Ok((member.name, elf))
+        })
+    }
+
+    fn raw_members(&self) -> RawMembers<'a> {
+        let mut reader = Reader::from_bytes(self.bytes);
+        reader.index = AR_MAGIC.len();
+        RawMembers { reader }
+    }
+
+    /// Resolves the name of a member. GNU archives end short names with a `/` and refer to long
+    /// names with `/offset` into the extended names table. BSD archives use `#1/length` and place
+    /// the name at the start of the member data.
+    fn member(&self, header: RawMember<'a>) -> Result<Member<'a>, Error> {
+        let (name, data) = if let Some(offset) = header.name.strip_prefix(b"/") {
+            let offset = parse_decimal(offset).ok_or(Error::BadName)?;
+            let names = self.extended_names.ok_or(Error::MissingExtendedNames)?;
+            let name = names.get(offset..).ok_or(Error::BadName)?;
+            let end = name.iter().position(|&c| c == b'\n').unwrap_or(name.len());
+            (name[..end].strip_suffix(b"/").unwrap_or(&name[..end]), header.data)
+        } else if let Some(len) = header.name.strip_prefix(b"#1/") {
+            let len = parse_decimal(len).ok_or(Error::BadName)?;
+            if len > header.data.len() {
+                return Err(Error::BadName);
+            }
+            let (name, data) = header.data.split_at(len);
+            (name.split(|&c| c == 0).next().unwrap_or(name), data)
+        } else {
+            (header.name.strip_suffix(b"/").unwrap_or(header.name), header.data)
+        };
+
+        Ok(Member {
+            name: String::from_utf8_lossy(name).into(),
+            data,
+        })
+    }
+}
+
+/// A file stored in an archive
+#[derive(Debug)]
+pub struct Member<'a> {
+    /// Name of the member, with the archive format specific decorations removed
+    pub name: String,
+    /// Contents of the member
+    pub data: &'a [u8],
+}
+
+impl<'a> Member<'a> {
+    /// Parses the member as an Elf file
+    pub fn parse(&self) -> Result<Elf64<'a>, ElfError> {
+        Elf64::parse(self.data)
+    }
+}
+
+/// A member header with its name as found in the archive
+struct RawMember<'a> {
+    name: &'a [u8],
+    data: &'a [u8],
+}
+
+/// Iterator over the member headers of an archive
+struct RawMembers<'a> {
+    reader: Reader<'a>,
+}
+
+impl<'a> RawMembers<'a> {
+    fn parse_member(&mut self) -> Result<RawMember<'a>, Error> {
+        let offset = self.reader.index;
+        let header = self.reader.read_slice(AR_HEADER_SIZE)?;
+        if &header[58..60] != AR_FMAG {
+            return Err(Error::BadHeader(offset));
+        }
+
+        let name = trim(&header[0..16]);
+        let size = parse_decimal(&header[48..58]).ok_or(Error::BadHeader(offset))?;
+        let data = self.reader.read_slice(size)?;
+        // Members are aligned to even offsets, but the padding of the last one may be missing
+        if self.reader.align_to(2).is_err() {
+            self.reader.index = self.reader.bytes.len();
+        }
+
+        Ok(RawMember { name, data })
+    }
+}
+
+impl<'a> Iterator for RawMembers<'a> {
+    type Item = Result<RawMember<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.reader.index >= self.reader.bytes.len() {
+            return None;
+        }
+
+        let member = self.parse_member();
+        if member.is_err() {
+            // Nothing after a malformed header can be trusted
+            self.reader.index = self.reader.bytes.len();
+        }
+        Some(member)
+    }
+}
+
+/// Removes the space padding from a header field
+fn trim(field: &[u8]) -> &[u8] {
+    let end = field.iter().rposition(|&c| c != b' ').map_or(0, |pos| pos + 1);
+    &field[..end]
+}
+
+/// Parses a space padded decimal header field
+fn parse_decimal(field: &[u8]) -> Option<usize> {
+    std::str::from_utf8(trim(field)).ok()?.parse().ok()
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Cannot find archive magic")]
+    BadMagic,
+    #[error("Malformed member header at offset {0}")]
+    BadHeader(usize),
+    #[error("Malformed member name")]
+    BadName,
+    #[error("Member refers to the extended names table, but the archive has none")]
+    MissingExtendedNames,
+    #[error("Archive parsing error {0}")]
+    ParseError(#[from] ParseError),
+    #[error("Member is not a valid Elf {0}")]
+    ElfError(#[from] ElfError),
+}
@@ -0,0 +1,142 @@
+//! Module for building minimal executables from scratch
+use std::borrow::Cow;
+
+use crate::{
+    write, Addr, Class, Elf64, ElfHeader, Endianness, FileOffset, FileType, Machine, OsAbi,
+    ProgramHeader, SectionHeader, SegmentContents, SegmentFlags, SegmentType, Size,
+    ELF32_HEADER_SIZE, ELF64_HEADER_SIZE,
+};
+
+/// Alignment of the loadable segments, the size of a page on every machine we know of
+const SEGMENT_ALIGN: u64 = 0x1000;
+
+/// Builds an executable made of loadable segments only, without sections. Segments are laid out
+/// after the header tables, each at a file offset congruent to its address modulo the page size,
+/// as the loader wants.
+pub struct ElfBuilder<'a> {
+    class: Class,
+    endianness: Endianness,
+    os_abi: OsAbi,
+    file_type: FileType,
+    machine: Machine,
+    entry: Addr,
+    segments: Vec<ProgramHeader<'a>>,
+}
+
+impl<'a> ElfBuilder<'a> {
+    /// Starts a 64-bit little endian executable for `machine`
+    pub fn new(machine: Machine) -> Self {
+        Self {
+            class: Class::Elf64,
+            endianness: Endianness::Little,
+            os_abi: OsAbi::SysV,
+            file_type: FileType::EtExec,
+            machine,
+            entry: Addr(0),
+            segments: vec![],
+        }
+    }
+
+    pub fn class(mut self, class: Class) -> Self {
+        self.class = class;
+        self
+    }
+
+    pub fn endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    pub fn os_abi(mut self, os_abi: OsAbi) -> Self {
+        self.os_abi = os_abi;
+        self
+    }
+
+    pub fn file_type(mut self, file_type: FileType) -> Self {
+        self.file_type = file_type;
+        self
+    }
+
+    /// Sets the address the process starts executing from
+    pub fn entry(mut self, entry: Addr) -> Self {
+        self.entry = entry;
+        self
+    }
+
+    /// Adds a segment loading `data` at `vaddr`
+    pub fn add_load_segment(self, vaddr: Addr, flags: SegmentFlags, data: &'a [u8]) -> Self {
+        self.add_load_segment_with_size(vaddr, flags, data, Size(data.len() as u64))
+    }
+
+    /// Adds a segment loading `data` at `vaddr`, followed by zeros up to `memsz` bytes, like
+    /// `.bss` is. A `memsz` smaller than `data` is raised to its length.
+    pub fn add_load_segment_with_size(
+        mut self,
+        vaddr: Addr,
+        flags: SegmentFlags,
+        data: &'a [u8],
+        memsz: Size,
+    ) -> Self {
+        let filesz = Size(data.len() as u64);
+        self.segments.push(ProgramHeader {
+            p_type: SegmentType::PtLoad,
+            p_flags: flags,
+            p_offset: FileOffset(0),
+            p_vaddr: vaddr,
+            p_paddr: vaddr,
+            p_filesz: filesz,
+            p_memsz: memsz.max(filesz),
+            p_align: SEGMENT_ALIGN,
+            data: Cow::Borrowed(data),
+            contents: SegmentContents::Unknown,
+        });
+        self
+    }
+
+    /// Lays out the file and returns it parsed, ready to be inspected or changed further
+    pub fn build_elf(mut self) -> Elf64<'a> {
+        let ehsize = match self.class {
+            Class::Elf32 => ELF32_HEADER_SIZE,
+            Class::Elf64 => ELF64_HEADER_SIZE,
+        } as u64;
+        let phentsize = ProgramHeader::size(self.class) as u64;
+        let mut offset = ehsize + phentsize * self.segments.len() as u64;
+        for ph in self.segments.iter_mut() {
+            offset += ph.p_vaddr.0.wrapping_sub(offset) % SEGMENT_ALIGN;
+            ph.p_offset = FileOffset(offset);
+            offset += ph.p_filesz.0;
+        }
+
+        let elf_header = ElfHeader {
+            e_class: self.class,
+            e_data: self.endianness,
+            e_osabi: self.os_abi,
+            e_abiversion: 0,
+            e_type: self.file_type,
+            e_machine: self.machine,
+            e_entry: self.entry,
+            e_phoff: FileOffset(ehsize),
+            e_shoff: FileOffset(0),
+            e_flags: 0,
+            e_ehsize: ehsize as u16,
+            e_phentsize: phentsize as u16,
+            e_phnum: self.segments.len() as u16,
+            e_shentsize: SectionHeader::size(self.class) as u16,
+            e_shnum: 0,
+            e_shstrndx: 0,
+        };
+        Elf64 {
+            elf_header,
+            ph_table: self.segments,
+            sh_table: vec![],
+            dynamic_section: None,
+            unclaimed: vec![],
+        }
+    }
+
+    /// Lays out the file and writes it to bytes. Fails for 32-bit files with addresses or sizes
+    /// which do not fit in 32 bits.
+    pub fn build(self) -> Result<Vec<u8>, write::Error> {
+        self.build_elf().to_bytes()
+    }
+}
@@ -0,0 +1,37 @@
+//! Module describing the file class, which gives the width of addresses and offsets
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Class {
+    /// 32-bit objects, where addresses, offsets and sizes are 4 bytes wide
+    Elf32 = 0x1,
+    /// 64-bit objects, where addresses, offsets and sizes are 8 bytes wide
+    Elf64 = 0x2,
+}
+
+impl Class {
+    /// Returns the size, in bytes, of an address, offset or size field for this class
+    pub fn word_size(&self) -> usize {
+        match self {
+            Class::Elf32 => 4,
+            Class::Elf64 => 8,
+        }
+    }
+}
+
+impl TryFrom<u8> for Class {
+    type Error = Error;
+    fn try_from(value: u8) -> Result<Class, Self::Error> {
+        match value {
+            0x1 => Ok(Class::Elf32),
+            0x2 => Ok(Class::Elf64),
+            _ => Err(Error::Unknown(value)),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Unknown file class {0}")]
+    Unknown(u8),
+}
@@ -0,0 +1,109 @@
+//! Module describing compressed sections, flagged with `SHF_COMPRESSED`, whose contents start
+//! with a compression header followed by the compressed data.
+//! Decompression is behind the `zlib` and `zstd` features.
+use std::io::{self, Read};
+
+use thiserror::Error;
+
+use crate::{Class, Endianness, ParseError, Reader};
+
+/// Compression type of sections compressed with zlib
+pub const ELFCOMPRESS_ZLIB: u32 = 1;
+/// Compression type of sections compressed with zstd
+pub const ELFCOMPRESS_ZSTD: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompressionType {
+    Zlib,
+    Zstd,
+    /// OS, processor specific or unknown compression
+    Other(u32),
+}
+
+impl From<u32> for CompressionType {
+    fn from(value: u32) -> Self {
+        match value {
+            ELFCOMPRESS_ZLIB => Self::Zlib,
+            ELFCOMPRESS_ZSTD => Self::Zstd,
+            _ => Self::Other(value),
+        }
+    }
+}
+
+/// Header at the start of a compressed section, `Elf32_Chdr` or `Elf64_Chdr`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompressionHeader {
+    pub ch_type: CompressionType,
+    /// Size of the data once decompressed
+    pub ch_size: u64,
+    /// Alignment of the data once decompressed
+    pub ch_addralign: u64,
+}
+
+impl CompressionHeader {
+    /// Returns the size, in bytes, of a compression header for the given `class`
+    pub fn size(class: Class) -> usize {
+        match class {
+            Class::Elf32 => 12,
+            Class::Elf64 => 24,
+        }
+    }
+
+    pub fn parse(reader: &mut Reader) -> Result<Self, ParseError> {
+        let ch_type = CompressionType::from(reader.read_u32()?);
+        if reader.class == Class::Elf64 {
+            // Reserved, keeping the following fields aligned
+            reader.read_u32()?;
+        }
+        let ch_size = reader.read_word()?;
+        let ch_addralign = reader.read_word()?;
+
+        Ok(Self { ch_type, ch_size, ch_addralign })
+    }
+}
+
+/// Decompresses the contents `data` of a compressed section, found in a file of the given class
+/// and byte order
+pub fn decompress(data: &[u8], class: Class, endianness: Endianness) -> Result<Vec<u8>, Error> {
+    let header = CompressionHeader::parse(&mut Reader::new(data, class, endianness))?;
+    let compressed = &data[CompressionHeader::size(class)..];
+
+    // The size comes from the file, so it only bounds how much we read, never what we allocate
+    let mut decompressed = vec![];
+    decoder(header.ch_type, compressed)?
+        .take(header.ch_size)
+        .read_to_end(&mut decompressed)?;
+    if decompressed.len() as u64 != header.ch_size {
+        return Err(Error::SizeMismatch { expected: header.ch_size, actual: decompressed.len() });
+    }
+
+    Ok(decompressed)
+}
+
+/// Returns a reader decompressing `compressed`, if support for `ch_type` is compiled in
+#[cfg_attr(not(any(feature = "zlib", feature = "zstd")), allow(unused_variables))]
+fn decoder<'a>(ch_type: CompressionType, compressed: &'a [u8]) -> Result<Box<dyn Read + 'a>, Error> {
+    match ch_type {
+        #[cfg(feature = "zlib")]
+        CompressionType::Zlib => Ok(Box::new(flate2::read::ZlibDecoder::new(compressed))),
+        #[cfg(feature = "zstd")]
+        CompressionType::Zstd => {
+            let decoder = ruzstd::decoding::StreamingDecoder::new(compressed)
+                .map_err(io::Error::other)?;
+            Ok(Box::new(decoder))
+        }
+        _ => Err(Error::Unsupported(ch_type)),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Decompressing {0:?} sections is not supported, or its feature is disabled")]
+    Unsupported(CompressionType),
+    #[error("Section decompressed to {actual} bytes instead of {expected}")]
+    SizeMismatch { expected: u64, actual: usize },
+    #[error("Cannot decompress section {0}")]
+    Io(#[from] io::Error),
+    #[error("Compression header parsing error {0}")]
+    ParseError(#[from] ParseError),
+}
@@ -0,0 +1,322 @@
+//! Module decoding the notes of core dumps (`ET_CORE` files), which describe the state of the
+//! process when it crashed. Register sets are only decoded for x86_64.
+use thiserror::Error;
+
+use crate::{Addr, Class, Endianness, Machine, Note, ParseError, Reader};
+
+/// Name of the notes written by the Linux kernel in core dumps
+pub const CORE_NAME: &[u8] = b"CORE";
+/// Note holding the status and the registers of a thread
+pub const NT_PRSTATUS: u32 = 1;
+/// Note holding information about the process
+pub const NT_PRPSINFO: u32 = 3;
+/// Note holding the auxiliary vector the process was started with
+pub const NT_AUXV: u32 = 6;
+/// Note holding the files mapped in the address space of the process
+pub const NT_FILE: u32 = 0x4649_4c45;
+
+/// Type of the auxiliary vector entry terminating the vector
+const AT_NULL: u64 = 0;
+
+/// A note found in a core dump, decoded when we know its layout
+#[derive(Debug)]
+pub enum CoreNote<'a> {
+    PrStatus(PrStatus),
+    PrPsInfo(PrPsInfo),
+    Auxv(Vec<AuxvEntry>),
+    File(Vec<FileMapping>),
+    Other(Note<'a>),
+}
+
+impl<'a> CoreNote<'a> {
+    /// Decodes `note`, found in a core dump of the given class, byte order and machine
+    pub fn parse(
+        note: Note<'a>,
+        class: Class,
+        endianness: Endianness,
+        machine: Machine,
+    ) -> Result<Self, Error> {
+        if note.name != CORE_NAME {
+            return Ok(Self::Other(note));
+        }
+
+        let mut reader = Reader::new(note.desc, class, endianness);
+        match note.n_type {
+            NT_PRSTATUS => Ok(Self::PrStatus(PrStatus::parse(&mut reader, machine)?)),
+            NT_PRPSINFO => Ok(Self::PrPsInfo(PrPsInfo::parse(&mut reader, machine)?)),
+            NT_AUXV => Ok(Self::Auxv(AuxvEntry::parse_vector(&mut reader)?)),
+            NT_FILE => Ok(Self::File(FileMapping::parse_table(&mut reader)?)),
+            _ => Ok(Self::Other(note)),
+        }
+    }
+}
+
+/// Status of a thread, as stored in `struct elf_prstatus`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrStatus {
+    /// Signal which stopped the thread
+    pub si_signo: i32,
+    pub si_code: i32,
+    pub si_errno: i32,
+    /// Signal being delivered to the thread
+    pub pr_cursig: u16,
+    /// Set of pending signals
+    pub pr_sigpend: u64,
+    /// Set of blocked signals
+    pub pr_sighold: u64,
+    pub pr_pid: i32,
+    pub pr_ppid: i32,
+    pub pr_pgrp: i32,
+    pub pr_sid: i32,
+    /// General purpose registers of the thread
+    pub pr_reg: X86_64Registers,
+}
+
+impl PrStatus {
+    pub fn parse(reader: &mut Reader, machine: Machine) -> Result<Self, Error> {
+        check_machine(reader, machine)?;
+
+        let si_signo = reader.read_i32()?;
+        let si_code = reader.read_i32()?;
+        let si_errno = reader.read_i32()?;
+        let pr_cursig = reader.read_u16()?;
+        // Padding keeping the signal sets aligned
+        reader.read_u16()?;
+        let pr_sigpend = reader.read_u64()?;
+        let pr_sighold = reader.read_u64()?;
+        let pr_pid = reader.read_i32()?;
+        let pr_ppid = reader.read_i32()?;
+        let pr_pgrp = reader.read_i32()?;
+        let pr_sid = reader.read_i32()?;
+        // User, system and children times, each a `struct timeval`
+        reader.read_slice(4 * 16)?;
+        let pr_reg = X86_64Registers::parse(reader)?;
+
+        Ok(Self {
+            si_signo,
+            si_code,
+            si_errno,
+            pr_cursig,
+            pr_sigpend,
+            pr_sighold,
+            pr_pid,
+            pr_ppid,
+            pr_pgrp,
+            pr_sid,
+            pr_reg,
+        })
+    }
+}
+
+/// General purpose registers of an x86_64 thread, in the order of `struct user_regs_struct`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct X86_64Registers {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub rbp: u64,
+    pub rbx: u64,
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rax: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    /// Number of the system call the thread was in, if any
+    pub orig_rax: u64,
+    pub rip: u64,
+    pub cs: u64,
+    pub eflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+    pub fs_base: u64,
+    pub gs_base: u64,
+    pub ds: u64,
+    pub es: u64,
+    pub fs: u64,
+    pub gs: u64,
+}
+
+impl X86_64Registers {
+    pub fn parse(reader: &mut Reader) -> Result<Self, ParseError> {
+        Ok(Self {
+            r15: reader.read_u64()?,
+            r14: reader.read_u64()?,
+            r13: reader.read_u64()?,
+            r12: reader.read_u64()?,
+            rbp: reader.read_u64()?,
+            rbx: reader.read_u64()?,
+            r11: reader.read_u64()?,
+            r10: reader.read_u64()?,
+            r9: reader.read_u64()?,
+            r8: reader.read_u64()?,
+            rax: reader.read_u64()?,
+            rcx: reader.read_u64()?,
+            rdx: reader.read_u64()?,
+            rsi: reader.read_u64()?,
+            rdi: reader.read_u64()?,
+            orig_rax: reader.read_u64()?,
+            rip: reader.read_u64()?,
+            cs: reader.read_u64()?,
+            eflags: reader.read_u64()?,
+            rsp: reader.read_u64()?,
+            ss: reader.read_u64()?,
+            fs_base: reader.read_u64()?,
+            gs_base: reader.read_u64()?,
+            ds: reader.read_u64()?,
+            es: reader.read_u64()?,
+            fs: reader.read_u64()?,
+            gs: reader.read_u64()?,
+        })
+    }
+}
+
+/// Information about the process, as stored in `struct elf_prpsinfo`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrPsInfo {
+    /// Numeric state of the process
+    pub pr_state: u8,
+    /// State of the process as a letter, like in `ps`
+    pub pr_sname: char,
+    pub pr_zomb: u8,
+    pub pr_nice: i8,
+    pub pr_flag: u64,
+    pub pr_uid: u32,
+    pub pr_gid: u32,
+    pub pr_pid: i32,
+    pub pr_ppid: i32,
+    pub pr_pgrp: i32,
+    pub pr_sid: i32,
+    /// Name of the executable, truncated to 15 bytes
+    pub pr_fname: String,
+    /// Start of the command line, truncated to 79 bytes
+    pub pr_psargs: String,
+}
+
+impl PrPsInfo {
+    pub fn parse(reader: &mut Reader, machine: Machine) -> Result<Self, Error> {
+        check_machine(reader, machine)?;
+
+        let pr_state = reader.read_u8()?;
+        let pr_sname = char::from(reader.read_u8()?);
+        let pr_zomb = reader.read_u8()?;
+        let pr_nice = reader.read_i8()?;
+        // Padding keeping the flags aligned
+        reader.read_u32()?;
+        let pr_flag = reader.read_u64()?;
+        let pr_uid = reader.read_u32()?;
+        let pr_gid = reader.read_u32()?;
+        let pr_pid = reader.read_i32()?;
+        let pr_ppid = reader.read_i32()?;
+        let pr_pgrp = reader.read_i32()?;
+        let pr_sid = reader.read_i32()?;
+        let pr_fname = fixed_string(reader.read_slice(16)?);
+        let pr_psargs = fixed_string(reader.read_slice(80)?);
+
+        Ok(Self {
+            pr_state,
+            pr_sname,
+            pr_zomb,
+            pr_nice,
+            pr_flag,
+            pr_uid,
+            pr_gid,
+            pr_pid,
+            pr_ppid,
+            pr_pgrp,
+            pr_sid,
+            pr_fname,
+            pr_psargs,
+        })
+    }
+}
+
+/// An entry of the auxiliary vector, which the kernel passes to a new process
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AuxvEntry {
+    /// Type of the entry, like `AT_ENTRY` or `AT_PHDR`
+    pub a_type: u64,
+    pub a_val: u64,
+}
+
+impl AuxvEntry {
+    /// Parses entries until the `AT_NULL` one or the end of the note
+    pub fn parse_vector(reader: &mut Reader) -> Result<Vec<Self>, ParseError> {
+        let mut entries = vec![];
+        while reader.index < reader.bytes.len() {
+            let a_type = reader.read_word()?;
+            let a_val = reader.read_word()?;
+            if a_type == AT_NULL {
+                break;
+            }
+            entries.push(Self { a_type, a_val });
+        }
+        Ok(entries)
+    }
+}
+
+/// A file mapped in the address space of the process
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileMapping {
+    pub start: Addr,
+    pub end: Addr,
+    /// Offset in the file where the mapping starts
+    pub file_offset: u64,
+    pub path: String,
+}
+
+impl FileMapping {
+    /// Parses the `NT_FILE` note: the number of mappings and the page size, the ranges of every
+    /// mapping with their offset in pages, then all the paths one after the other.
+    pub fn parse_table(reader: &mut Reader) -> Result<Vec<Self>, ParseError> {
+        let count = reader.read_word()?;
+        let page_size = reader.read_word()?;
+
+        let mut ranges = vec![];
+        for _ in 0..count {
+            let start = Addr(reader.read_word()?);
+            let end = Addr(reader.read_word()?);
+            let file_offset = reader.read_word()?.wrapping_mul(page_size);
+            ranges.push((start, end, file_offset));
+        }
+
+        ranges
+            .into_iter()
+            .map(|(start, end, file_offset)| {
+                let path = reader.read_cstr()?;
+                Ok(Self {
+                    start,
+                    end,
+                    file_offset,
+                    path: String::from_utf8_lossy(path).into(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Only the x86_64 layouts of the process structures are known
+fn check_machine(reader: &Reader, machine: Machine) -> Result<(), Error> {
+    if reader.class != Class::Elf64 || machine != Machine::AmdX86_64 {
+        return Err(Error::UnsupportedMachine(machine));
+    }
+    Ok(())
+}
+
+/// Reads a null padded string of a fixed size array
+fn fixed_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into()
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Process structures are not known for {0:?}")]
+    UnsupportedMachine(Machine),
+    #[error("Core note parsing error {0}")]
+    ParseError(#[from] ParseError),
+}
@@ -0,0 +1,69 @@
+//! Module for finding the separate debug information of a stripped file, either through the
+//! `.gnu_debuglink` section or through the GNU build ID.
+use std::path::{Path, PathBuf};
+
+use crate::{ParseError, Reader};
+
+/// Directory distributions install separate debug files under
+pub const DEBUG_ROOT: &str = "/usr/lib/debug";
+
+/// Contents of the `.gnu_debuglink` section
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugLink {
+    /// Name of the file holding the debug information, without any directory
+    pub filename: String,
+    /// CRC32 of the whole debug file, used to check it matches this file
+    pub crc: u32,
+}
+
+impl DebugLink {
+    /// Parses the section contents `reader` points to: a null terminated file name, padded to a
+    /// 4-byte boundary, followed by the CRC.
+    pub fn parse(reader: &mut Reader) -> Result<Self, ParseError> {
+        let filename = String::from_utf8_lossy(reader.read_cstr()?).into();
+        // Skip the padding after the terminator
+        reader.align_to(4)?;
+        let crc = reader.read_u32()?;
+
+        Ok(Self { filename, crc })
+    }
+
+    /// Returns the paths where the debug file is looked for, in the same order as GDB, for the
+    /// file at `path`: next to it, in a `.debug` directory next to it and under `debug_root`.
+    pub fn candidates(&self, path: &Path, debug_root: &Path) -> Vec<PathBuf> {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut candidates = vec![dir.join(&self.filename), dir.join(".debug").join(&self.filename)];
+        if let Ok(dir) = dir.canonicalize() {
+            // `join` would replace the root with `dir` since it is absolute
+            let relative = dir.strip_prefix("/").unwrap_or(&dir);
+            candidates.push(debug_root.join(relative).join(&self.filename));
+        }
+        candidates
+    }
+}
+
+/// Returns the path of the debug file for the build ID `build_id` under `debug_root`, following
+/// the `.build-id/xx/yyyyyy.debug` layout, where `xx` is the first byte in hex.
+pub fn build_id_path(build_id: &[u8], debug_root: &Path) -> Option<PathBuf> {
+    let (first, rest) = build_id.split_first()?;
+    let rest: String = rest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    Some(
+        debug_root
+            .join(".build-id")
+            .join(format!("{:02x}", first))
+            .join(format!("{}.debug", rest)),
+    )
+}
+
+/// Computes the CRC32 used by `.gnu_debuglink`, which is the common IEEE 802.3 one
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
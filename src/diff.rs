@@ -0,0 +1,228 @@
+//! Module comparing two files by what they hold rather than by their bytes: header fields,
+//! sections, symbols and dynamic entries. Diffing a file with its patched copy shows whether the
+//! patch changed exactly what it meant to.
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+};
+
+use thiserror::Error;
+
+use crate::{
+    DynamicTag, DynamicValue, Elf64, SegmentError, Size, StringError, SymbolEntry, SymbolError,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Symbol table error {0}")]
+    Symbols(#[from] SymbolError),
+    #[error("Dynamic symbol table error {0}")]
+    DynamicSymbols(#[from] SegmentError),
+    #[error("Dynamic table strings {0}")]
+    Strings(#[from] StringError),
+}
+
+/// Differences between two files, empty when they hold the same things
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ElfDiff {
+    pub header: Vec<FieldChange>,
+    pub sections: Vec<SectionChange>,
+    /// Changes of the symbols of `.symtab`
+    pub symbols: Vec<SymbolChange>,
+    pub dynamic_symbols: Vec<SymbolChange>,
+    pub dynamic: Vec<DynamicChange>,
+}
+
+impl ElfDiff {
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty()
+            && self.sections.is_empty()
+            && self.symbols.is_empty()
+            && self.dynamic_symbols.is_empty()
+            && self.dynamic.is_empty()
+    }
+}
+
+/// A field of the ELF header which changed, with its old and new values as `Debug` shows them
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// A change of a section, which sections are matched by name for
+#[derive(Debug, Clone, PartialEq)]
+pub enum SectionChange {
+    Added(String),
+    Removed(String),
+    Resized { name: String, old: Size, new: Size },
+    /// The size is the same, but the contents are not
+    Modified(String),
+}
+
+/// A symbol of the table which changed, matched by name: added when `old` is `None`, removed when
+/// `new` is
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolChange {
+    pub name: String,
+    pub old: Option<SymbolEntry>,
+    pub new: Option<SymbolEntry>,
+}
+
+/// The values of the dynamic entries with `tag` changed. Entries added or removed have no old
+/// or no new values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicChange {
+    pub tag: DynamicTag,
+    pub old: Vec<DynamicValue>,
+    pub new: Vec<DynamicValue>,
+}
+
+/// Compares `old` with `new`
+pub fn diff(old: &Elf64, new: &Elf64) -> Result<ElfDiff, Error> {
+    Ok(ElfDiff {
+        header: header_changes(old, new),
+        sections: section_changes(old, new),
+        symbols: symbol_changes(symbols(old)?, symbols(new)?),
+        dynamic_symbols: symbol_changes(dynamic_symbols(old)?, dynamic_symbols(new)?),
+        dynamic: dynamic_changes(old.dynamic_values()?, new.dynamic_values()?),
+    })
+}
+
+fn header_changes(old: &Elf64, new: &Elf64) -> Vec<FieldChange> {
+    let (old, new) = (&old.elf_header, &new.elf_header);
+    let mut changes = vec![];
+    let mut compare = |field, old: &dyn Debug, new: &dyn Debug| {
+        let (old, new) = (format!("{old:?}"), format!("{new:?}"));
+        if old != new {
+            changes.push(FieldChange { field, old, new });
+        }
+    };
+    compare("e_class", &old.e_class, &new.e_class);
+    compare("e_data", &old.e_data, &new.e_data);
+    compare("e_osabi", &old.e_osabi, &new.e_osabi);
+    compare("e_abiversion", &old.e_abiversion, &new.e_abiversion);
+    compare("e_type", &old.e_type, &new.e_type);
+    compare("e_machine", &old.e_machine, &new.e_machine);
+    compare("e_entry", &old.e_entry, &new.e_entry);
+    compare("e_phoff", &old.e_phoff, &new.e_phoff);
+    compare("e_shoff", &old.e_shoff, &new.e_shoff);
+    compare("e_flags", &old.e_flags, &new.e_flags);
+    compare("e_ehsize", &old.e_ehsize, &new.e_ehsize);
+    compare("e_phentsize", &old.e_phentsize, &new.e_phentsize);
+    compare("e_phnum", &old.e_phnum, &new.e_phnum);
+    compare("e_shentsize", &old.e_shentsize, &new.e_shentsize);
+    compare("e_shnum", &old.e_shnum, &new.e_shnum);
+    compare("e_shstrndx", &old.e_shstrndx, &new.e_shstrndx);
+    changes
+}
+
+/// Matches the sections by name. Sections sharing a name are matched in the order they come in.
+fn section_changes(old: &Elf64, new: &Elf64) -> Vec<SectionChange> {
+    let names = |elf: &Elf64| -> Vec<String> {
+        elf.sh_table.iter().map(|sh| elf.section_name(sh).unwrap_or_default()).collect()
+    };
+    let (old_names, new_names) = (names(old), names(new));
+    let mut new_indexes: HashMap<&str, VecDeque<usize>> = HashMap::new();
+    for (index, name) in new_names.iter().enumerate() {
+        new_indexes.entry(name).or_default().push_back(index);
+    }
+
+    let mut changes = vec![];
+    for (sh, name) in old.sh_table.iter().zip(&old_names) {
+        let Some(index) = new_indexes.get_mut(&name[..]).and_then(VecDeque::pop_front) else {
+            changes.push(SectionChange::Removed(name.clone()));
+            continue;
+        };
+        let other = &new.sh_table[index];
+        if sh.sh_size() != other.sh_size() {
+            let (old, new) = (sh.sh_size(), other.sh_size());
+            changes.push(SectionChange::Resized { name: name.clone(), old, new });
+        } else if sh.data != other.data {
+            changes.push(SectionChange::Modified(name.clone()));
+        }
+    }
+    let mut added: Vec<usize> = new_indexes.into_values().flatten().collect();
+    added.sort();
+    changes.extend(added.into_iter().map(|index| SectionChange::Added(new_names[index].clone())));
+    changes
+}
+
+fn symbols(elf: &Elf64) -> Result<Vec<(String, SymbolEntry)>, Error> {
+    let symbols = elf.symbols()?.into_iter().map(|sym| (sym.name, sym.entry));
+    Ok(symbols.collect())
+}
+
+fn dynamic_symbols(elf: &Elf64) -> Result<Vec<(String, SymbolEntry)>, Error> {
+    if elf.dynamic_entry(DynamicTag::SymTab).is_none() {
+        return Ok(vec![]);
+    }
+    let symbols = elf.named_dynamic_symbols()?.map(|(name, sym)| (name.into_owned(), sym));
+    Ok(symbols.collect())
+}
+
+/// Matches the symbols by name. Names several symbols share, like those of local symbols of
+/// different source files, are only changed when they have one symbol on both sides.
+fn symbol_changes(
+    old: Vec<(String, SymbolEntry)>,
+    new: Vec<(String, SymbolEntry)>,
+) -> Vec<SymbolChange> {
+    // Symbols of each name, old and new, with the names in the order they first appear
+    let mut names = vec![];
+    let mut by_name: HashMap<&str, (Vec<&SymbolEntry>, Vec<&SymbolEntry>)> = HashMap::new();
+    for (side, symbols) in [&old, &new].into_iter().enumerate() {
+        for (name, sym) in symbols.iter().filter(|(name, _)| !name.is_empty()) {
+            let entries = by_name.entry(name).or_insert_with(|| {
+                names.push(name);
+                (vec![], vec![])
+            });
+            match side {
+                0 => entries.0.push(sym),
+                _ => entries.1.push(sym),
+            }
+        }
+    }
+
+    let mut changes = vec![];
+    for name in names {
+        let (old, new) = &by_name[&name[..]];
+        let change = |old: Option<&SymbolEntry>, new: Option<&SymbolEntry>| SymbolChange {
+            name: name.clone(),
+            old: old.cloned(),
+            new: new.cloned(),
+        };
+        if let ([old], [new]) = (&old[..], &new[..]) {
+            if old != new {
+                changes.push(change(Some(old), Some(new)));
+            }
+            continue;
+        }
+        let removed = old.iter().filter(|sym| !new.contains(sym));
+        changes.extend(removed.map(|sym| change(Some(sym), None)));
+        let added = new.iter().filter(|sym| !old.contains(sym));
+        changes.extend(added.map(|sym| change(None, Some(sym))));
+    }
+    changes
+}
+
+/// Compares the values of the entries of every tag, in the order the tags first appear
+fn dynamic_changes(
+    old: Vec<(DynamicTag, DynamicValue)>,
+    new: Vec<(DynamicTag, DynamicValue)>,
+) -> Vec<DynamicChange> {
+    let mut tags: Vec<DynamicTag> = vec![];
+    for (tag, _) in old.iter().chain(&new) {
+        if !tags.contains(tag) {
+            tags.push(*tag);
+        }
+    }
+    let values = |entries: &[(DynamicTag, DynamicValue)], tag| -> Vec<DynamicValue> {
+        let tagged = entries.iter().filter(|(other, _)| *other == tag);
+        tagged.map(|(_, value)| value.clone()).collect()
+    };
+    tags.into_iter()
+        .map(|tag| DynamicChange { tag, old: values(&old, tag), new: values(&new, tag) })
+        .filter(|change| change.old != change.new)
+        .collect()
+}
@@ -0,0 +1,27 @@
+//! Module describing the byte order used by the multi-byte fields of a file
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Endianness {
+    /// Two's complement, least significant byte first
+    Little = 0x1,
+    /// Two's complement, most significant byte first
+    Big = 0x2,
+}
+
+impl TryFrom<u8> for Endianness {
+    type Error = Error;
+    fn try_from(value: u8) -> Result<Endianness, Self::Error> {
+        match value {
+            0x1 => Ok(Endianness::Little),
+            0x2 => Ok(Endianness::Big),
+            _ => Err(Error::Unknown(value)),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Unknown data encoding {0}")]
+    Unknown(u8),
+}
@@ -0,0 +1,57 @@
+//! Module measuring the Shannon entropy of contents, in bits per byte. Code and data stay well
+//! below the 8 bits of random bytes, which packed, compressed or encrypted contents come close to.
+
+/// Returns the Shannon entropy of `data`, from 0 for empty or constant data up to 8
+pub fn entropy(data: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for byte in data {
+        counts[usize::from(*byte)] += 1;
+    }
+    entropy_of_counts(&counts, data.len())
+}
+
+/// Returns the entropy of every `window` bytes of `data`, starting every `step` bytes, which
+/// finds a packed blob inside otherwise plain contents. Windows past the end of `data` are left
+/// out, so data shorter than `window` has none.
+pub fn window_entropy(data: &[u8], window: usize, step: usize) -> Vec<f64> {
+    if window == 0 || step == 0 || data.len() < window {
+        return vec![];
+    }
+    let mut counts = [0usize; 256];
+    for byte in &data[..window] {
+        counts[usize::from(*byte)] += 1;
+    }
+    let mut entropies = vec![entropy_of_counts(&counts, window)];
+    let mut start = 0;
+    while start + step + window <= data.len() {
+        // Slides the window, counting only the bytes which go out of it and come in
+        let next = start + step;
+        let (gone, new) = if step < window {
+            (start..next, start + window..next + window)
+        } else {
+            counts = [0; 256];
+            (0..0, next..next + window)
+        };
+        for byte in &data[gone] {
+            counts[usize::from(*byte)] -= 1;
+        }
+        for byte in &data[new] {
+            counts[usize::from(*byte)] += 1;
+        }
+        entropies.push(entropy_of_counts(&counts, window));
+        start = next;
+    }
+    entropies
+}
+
+fn entropy_of_counts(counts: &[usize; 256], len: usize) -> f64 {
+    counts
+        .iter()
+        .filter(|count| **count != 0)
+        .map(|count| {
+            let probability = *count as f64 / len as f64;
+            probability * (1.0 / probability).log2()
+        })
+        // Summing no values gives -0
+        .fold(0.0, |sum, value| sum + value)
+}
@@ -3,11 +3,13 @@ use thiserror::Error;
 
 use crate::{
     section::SectionError,
+    class,
+    endian,
     file_type,
-    machine,
     DynamicTag,
     addr,
     reloc::Error as RelocError,
+    sym::SymbolError,
 };
 
 #[derive(Debug, Error)]
@@ -20,26 +22,28 @@ pub enum ElfError {
     ParseError(#[from] ParseError),
     #[error("Section header error {0}")]
     SectionError(#[from] SectionError),
+    #[error("I/O error {0}")]
+    Io(#[from] std::io::Error),
 }
 
 #[derive(Debug, Error)]
 pub enum ElfHeaderError {
     #[error("Cannot find elf magic, found: {0}")]
     BadMagic(String),
-    #[error("Elf is not 64-bit")]
-    Not64Bit,
-    #[error("Elf is not Littel Endian")]
-    BadEndianness,
+    #[error("Unknown class: {0}")]
+    ClassError(#[from] class::Error),
+    #[error("Unknown endianness: {0}")]
+    EndiannessError(#[from] endian::Error),
     #[error("Elf has bad version(not 1)")]
     BadVersion,
-    #[error("Unknown OS ABI")]
-    BadOsAbi,
     #[error("Unknown object file type {0}")]
     FileTypeError(#[from] file_type::Error),
-    #[error("Unknown machine: {0}")]
-    MachineError(#[from] machine::Error),
     #[error("Not original version")]
     NotOriginalVersion,
+    #[error("Program header entry size {0} is smaller than a program header")]
+    BadPhEntSize(u16),
+    #[error("Section header entry size {0} is smaller than a section header")]
+    BadShEntSize(u16),
     #[error("Parsing error {0}")]
     ParseError(#[from] ParseError)
 }
@@ -50,6 +54,12 @@ pub enum ParseError {
     OutOfBounds,
     #[error("Trying to access bad range {0:?} from slice")]
     BadRange(Range<usize>),
+    #[error("Reading {size} bytes at offset {offset} goes past the end")]
+    ReadOutOfBounds { offset: usize, size: usize },
+    #[error("LEB128 value does not fit in 64 bits")]
+    BadLeb128,
+    #[error("String at offset {0} has no null terminator")]
+    Unterminated(usize),
 }
 
 #[derive(Debug, Error)]
@@ -58,6 +68,10 @@ pub enum DynamicError {
     TagNotFound(DynamicTag),
     #[error("Dynamic Entry unknow {0}")]
     EntryUnknown(u64),
+    #[error("Dynamic table ends after {0} entries, without a null entry")]
+    Truncated(usize),
+    #[error("DT_PLTREL names neither DT_REL nor DT_RELA, but {0}")]
+    BadPltRel(u64),
 }
 
 #[derive(Debug, Error)]
@@ -78,6 +92,8 @@ pub enum SegmentError {
     SegmentFlagsParseFailed(u32),
     #[error("Address not found in any PtLoad segment {0}")]
     BadPtLoadAddr(addr::Addr),
+    #[error("Contents of the segment at {0} were not loaded")]
+    NotLoaded(addr::Addr),
     #[error("Dynamic segment error {0}")]
     DynamicError(#[from] DynamicError),
     #[error("Address Error {0}")]
@@ -86,6 +102,8 @@ pub enum SegmentError {
     RelocError(#[from] RelocError),
     #[error("String table error: {0}")]
     StrTabError(#[from] StringError),
+    #[error("Symbol table error: {0}")]
+    SymbolError(#[from] SymbolError),
 }
 
 #[derive(Debug, Error)]
@@ -94,7 +112,13 @@ pub enum StringError {
     StrTabNotFound,
     #[error("String Table Segment not found")]
     StrTabSegmentNotFound,
+    #[error("Contents of the segment at {0} holding the String Table were not loaded")]
+    NotLoaded(addr::Addr),
     #[error("String from string Table not found")]
-    StringNotFound
+    StringNotFound,
+    #[error("String offset {0} is outside the string table")]
+    OffsetOutOfBounds(usize),
+    #[error("String at offset {0} is not null terminated")]
+    Unterminated(usize),
 }
 
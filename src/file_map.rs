@@ -0,0 +1,86 @@
+//! Module mapping the bytes of a file to the headers, segments and sections claiming them. What
+//! nothing claims is a gap, where padding lives, but also data appended to or hidden in a binary.
+use std::ops::Range;
+
+use crate::{Addr, FileOffset, SegmentFlags};
+
+/// Part of the file a range of bytes belongs to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileRegion {
+    ElfHeader,
+    ProgramHeaderTable,
+    SectionHeaderTable,
+    /// Contents of the segment at this index of the program header table
+    Segment(usize),
+    /// Contents of the section at this index of the section header table
+    Section(usize),
+    /// Bytes no header refers to
+    Gap,
+}
+
+/// A range of the file and the region it belongs to
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileMapEntry {
+    pub range: Range<FileOffset>,
+    pub region: FileRegion,
+}
+
+/// Padding inside a loadable segment, which no section claims, where code can be injected
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeCave {
+    /// Index of the segment in the program header table
+    pub segment: usize,
+    pub file_range: Range<FileOffset>,
+    /// Where the padding is loaded
+    pub mem_range: Range<Addr>,
+    /// Permissions of the segment, which the injected code runs with
+    pub flags: SegmentFlags,
+}
+
+/// Sorts the non-empty `claimed` ranges by offset, the ones containing others first, and adds the
+/// gaps between them up to `file_size`
+pub(crate) fn with_gaps(claimed: Vec<FileMapEntry>, file_size: u64) -> Vec<FileMapEntry> {
+    let mut claimed: Vec<_> = claimed.into_iter().filter(|entry| !entry.range.is_empty()).collect();
+    claimed.sort_by_key(|entry| (entry.range.start, std::cmp::Reverse(entry.range.end)));
+
+    let mut map = Vec::with_capacity(claimed.len());
+    let mut covered = FileOffset(0);
+    let gap = |range| FileMapEntry { range, region: FileRegion::Gap };
+    for entry in claimed {
+        if covered < entry.range.start {
+            map.push(gap(covered..entry.range.start));
+        }
+        covered = covered.max(entry.range.end);
+        map.push(entry);
+    }
+    if covered.0 < file_size {
+        map.push(gap(covered..FileOffset(file_size)));
+    }
+    map
+}
+
+/// Returns the parts of `range` that nothing of the sorted `map` claims but the segment at index
+/// `segment`
+pub(crate) fn unclaimed(
+    map: &[FileMapEntry],
+    range: Range<FileOffset>,
+    segment: usize,
+) -> Vec<Range<FileOffset>> {
+    let mut parts = vec![];
+    let mut covered = range.start;
+    let claimed = map
+        .iter()
+        .filter(|entry| entry.region != FileRegion::Segment(segment))
+        .filter(|entry| entry.region != FileRegion::Gap)
+        .filter(|entry| entry.range.start < range.end && range.start < entry.range.end);
+    for entry in claimed {
+        if covered < entry.range.start {
+            parts.push(covered..entry.range.start);
+        }
+        covered = covered.max(entry.range.end);
+    }
+    if covered < range.end {
+        parts.push(covered..range.end);
+    }
+    parts
+}
@@ -1,12 +1,23 @@
 use thiserror::Error;
 
-#[derive(Debug, PartialEq)]
+// Reserved inclusive range. Operating system specific.
+const LOOS: u16 = 0xFE00;
+const HIOS: u16 = 0xFEFF;
+// Reserved inclusive range. Processor specific.
+const LOPROC: u16 = 0xFF00;
+const HIPROC: u16 = 0xFFFF;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FileType {
-    EtNone = 0x0,
-    EtRel = 0x1,
-    EtExec = 0x2,
-    EtDyn = 0x3,
-    EtCore = 0x4,
+    EtNone,
+    EtRel,
+    EtExec,
+    EtDyn,
+    EtCore,
+    /// Value for specific OS
+    OsSpecific(u16),
+    /// Value for specific processor
+    ProcSpecific(u16),
 }
 
 impl TryFrom<u16> for FileType {
@@ -18,11 +29,45 @@ impl TryFrom<u16> for FileType {
             0x2 => Ok(FileType::EtExec),
             0x3 => Ok(FileType::EtDyn),
             0x4 => Ok(FileType::EtCore),
+            LOOS..=HIOS => Ok(FileType::OsSpecific(value)),
+            LOPROC..=HIPROC => Ok(FileType::ProcSpecific(value)),
             _ => Err(Error::Unsupported),
         }
     }
 }
 
+impl From<FileType> for u16 {
+    fn from(value: FileType) -> u16 {
+        match value {
+            FileType::EtNone => 0x0,
+            FileType::EtRel => 0x1,
+            FileType::EtExec => 0x2,
+            FileType::EtDyn => 0x3,
+            FileType::EtCore => 0x4,
+            FileType::OsSpecific(value) | FileType::ProcSpecific(value) => value,
+        }
+    }
+}
+
+/// What an object is for, telling apart the kinds of executables which `e_type` alone does not
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectKind {
+    /// Object file, waiting to be linked
+    Relocatable,
+    /// Executable loaded at a fixed address, without a dynamic loader
+    StaticExecutable,
+    /// Executable loaded at a fixed address by the dynamic loader
+    DynamicExecutable,
+    /// Position independent executable, loaded by the dynamic loader
+    Pie,
+    /// Position independent executable which relocates itself, without a dynamic loader
+    StaticPie,
+    SharedLibrary,
+    Core,
+    /// Unknown, OS or processor specific object
+    Other,
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Unsupported file type")]
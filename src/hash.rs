@@ -0,0 +1,172 @@
+//! Module describing the symbol hash tables the dynamic linker uses to look up symbols by name
+use thiserror::Error;
+
+use crate::{Addr, Class, Endianness, ParseError, Reader};
+
+/// GNU style hash table, `.gnu.hash`. Only the symbols from `symoffset` on are hashed, sorted by
+/// bucket, and a bloom filter lets lookups of missing names bail out early.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GnuHashTable {
+    /// Index of the first dynamic symbol in the table
+    pub symoffset: u32,
+    /// Shift giving the second bit of a name in the bloom filter
+    pub bloom_shift: u32,
+    /// Bloom filter words, each as wide as the class of the file
+    pub bloom: Vec<u64>,
+    /// Class of the file, giving the width of the bloom filter words
+    pub class: Class,
+    /// Index of the first symbol of every bucket, or 0 for empty buckets
+    pub buckets: Vec<u32>,
+    /// Hash of every symbol from `symoffset` on, with the lowest bit set on the last symbol of a
+    /// bucket
+    pub chains: Vec<u32>,
+}
+
+impl GnuHashTable {
+    /// Parses the table `bytes` starts with. The size of the chain array is not recorded, so it
+    /// is found by walking the chain of the last bucket until its end.
+    pub fn parse(bytes: &[u8], class: Class, endianness: Endianness) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes, class, endianness);
+        let nbuckets = reader.read_u32()?;
+        let symoffset = reader.read_u32()?;
+        let bloom_size = reader.read_u32()?;
+        let bloom_shift = reader.read_u32()?;
+        if bloom_shift >= u32::BITS {
+            return Err(Error::BadBloomShift(bloom_shift));
+        }
+
+        let bloom = (0..bloom_size).map(|_| reader.read_word()).collect::<Result<Vec<_>, _>>()?;
+        let buckets = (0..nbuckets).map(|_| reader.read_u32()).collect::<Result<Vec<_>, _>>()?;
+
+        let mut chains = vec![];
+        if let Some(&last) = buckets.iter().max().filter(|&&last| last != 0) {
+            let start = last.checked_sub(symoffset).ok_or(Error::BadBucket(last))?;
+            // Every chain before the last bucket is fully read along the way
+            for _ in 0..=start {
+                chains.push(reader.read_u32()?);
+            }
+            while chains.last().is_some_and(|hash| hash & 1 == 0) {
+                chains.push(reader.read_u32()?);
+            }
+        }
+
+        Ok(Self { symoffset, bloom_shift, bloom, class, buckets, chains })
+    }
+
+    /// Returns the number of dynamic symbols, hashed or not
+    pub fn symbol_count(&self) -> usize {
+        self.symoffset as usize + self.chains.len()
+    }
+
+    /// Returns the index of the symbol called `name`, checking the candidates with `is_named`,
+    /// which tells whether the symbol at an index is called `name`
+    pub fn lookup(&self, name: &str, is_named: impl Fn(u32) -> bool) -> Option<u32> {
+        if self.bloom.is_empty() || self.buckets.is_empty() {
+            return None;
+        }
+        let hash = gnu_hash(name.as_bytes());
+
+        // Both bits the name sets in the bloom filter must be set for the name to be in the table
+        let word_bits = self.class.word_size() * 8;
+        let word = self.bloom[(hash as usize / word_bits) % self.bloom.len()];
+        let shifted = hash.checked_shr(self.bloom_shift)?;
+        let mask = (1u64 << (hash as usize % word_bits))
+            | (1u64 << (shifted as usize % word_bits));
+        if word & mask != mask {
+            return None;
+        }
+
+        let mut index = self.buckets[hash as usize % self.buckets.len()];
+        if index < self.symoffset {
+            return None;
+        }
+        // Chains hold the hashes of the symbols, so names are only compared on hash matches
+        while let Some(&chain) = self.chains.get((index - self.symoffset) as usize) {
+            if chain | 1 == hash | 1 && is_named(index) {
+                return Some(index);
+            }
+            if chain & 1 != 0 {
+                break;
+            }
+            index += 1;
+        }
+        None
+    }
+}
+
+/// Classic System V hash table, `.hash`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SysvHashTable {
+    /// Index of the first symbol of every bucket, or 0 for empty buckets
+    pub buckets: Vec<u32>,
+    /// Index of the next symbol in the same bucket, for every dynamic symbol, or 0 at the end
+    pub chains: Vec<u32>,
+}
+
+impl SysvHashTable {
+    /// Parses the table `bytes` starts with
+    pub fn parse(bytes: &[u8], class: Class, endianness: Endianness) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes, class, endianness);
+        let nbucket = reader.read_u32()?;
+        let nchain = reader.read_u32()?;
+        let buckets = (0..nbucket).map(|_| reader.read_u32()).collect::<Result<Vec<_>, _>>()?;
+        let chains = (0..nchain).map(|_| reader.read_u32()).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { buckets, chains })
+    }
+
+    /// Returns the number of dynamic symbols, which all have a chain entry
+    pub fn symbol_count(&self) -> usize {
+        self.chains.len()
+    }
+
+    /// Returns the index of the symbol called `name`, checking the candidates of its bucket with
+    /// `is_named`, which tells whether the symbol at an index is called `name`
+    pub fn lookup(&self, name: &str, is_named: impl Fn(u32) -> bool) -> Option<u32> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let hash = elf_hash(name.as_bytes());
+        let mut index = self.buckets[hash as usize % self.buckets.len()];
+        // Bounding the walk by the number of symbols keeps cyclic chains from looping forever
+        for _ in 0..self.chains.len() {
+            if index == 0 {
+                break;
+            }
+            if is_named(index) {
+                return Some(index);
+            }
+            index = *self.chains.get(index as usize)?;
+        }
+        None
+    }
+}
+
+/// Hash function of the System V hash table
+pub fn elf_hash(name: &[u8]) -> u32 {
+    name.iter().fold(0u32, |hash, &c| {
+        let hash = (hash << 4).wrapping_add(u32::from(c));
+        let high = hash & 0xF000_0000;
+        (hash ^ (high >> 24)) & !high
+    })
+}
+
+/// Hash function of the GNU hash table, from Daniel J. Bernstein
+pub fn gnu_hash(name: &[u8]) -> u32 {
+    name.iter()
+        .fold(5381u32, |hash, &c| hash.wrapping_mul(33).wrapping_add(u32::from(c)))
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Hash table address {0} is not in any loadable segment")]
+    BadAddress(Addr),
+    #[error("Contents of the segment at {0} holding the hash table were not loaded")]
+    NotLoaded(Addr),
+    #[error("Bucket starts at symbol {0}, before the first hashed symbol")]
+    BadBucket(u32),
+    #[error("Bloom filter shift {0} is wider than the hashes")]
+    BadBloomShift(u32),
+    #[error("Hash table parsing error {0}")]
+    ParseError(#[from] ParseError),
+}
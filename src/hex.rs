@@ -0,0 +1,160 @@
+//! Module writing the loadable segments as Intel HEX or Motorola S-records, the text formats
+//! flash programmers and bootloaders of embedded targets read. Contents go at the physical address
+//! of their segment, where they live in flash, rather than at the virtual address they run from.
+use std::fmt::Write;
+
+use thiserror::Error;
+
+use crate::{Addr, Elf64, SegmentType};
+
+/// Number of data bytes in each record, as `objcopy` writes them
+pub const RECORD_LEN: usize = 16;
+
+/// Intel HEX record holding data
+const IHEX_DATA: u8 = 0x00;
+/// Intel HEX record ending the file
+const IHEX_EOF: u8 = 0x01;
+/// Intel HEX record giving the upper 16 bits of the addresses of the data records following it
+const IHEX_EXTENDED_LINEAR_ADDRESS: u8 = 0x04;
+/// Intel HEX record giving the 32-bit entry point
+const IHEX_START_LINEAR_ADDRESS: u8 = 0x05;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Address {0} does not fit in 32 bits")]
+    AddressTooLarge(Addr),
+    #[error("Contents of the segment at physical address {0} were not loaded")]
+    NotLoaded(Addr),
+}
+
+/// Writes the file backed part of the loadable segments as Intel HEX, with 32-bit addresses, and
+/// the entry point, when there is one, as the start address
+pub fn intel_hex(elf: &Elf64) -> Result<String, Error> {
+    let mut out = String::new();
+    let mut upper = 0;
+    for (addr, data) in load_images(elf)? {
+        let mut addr = addr;
+        for chunk in chunks(addr, data) {
+            if addr >> 16 != upper {
+                upper = addr >> 16;
+                let upper = (upper as u16).to_be_bytes();
+                ihex_record(&mut out, 0, IHEX_EXTENDED_LINEAR_ADDRESS, &upper);
+            }
+            ihex_record(&mut out, addr as u16, IHEX_DATA, chunk);
+            // Segments may end at the last address, past which nothing is written
+            addr = addr.wrapping_add(chunk.len() as u32);
+        }
+    }
+    let entry = entry(elf)?;
+    if entry != 0 {
+        ihex_record(&mut out, 0, IHEX_START_LINEAR_ADDRESS, &entry.to_be_bytes());
+    }
+    ihex_record(&mut out, 0, IHEX_EOF, &[]);
+    Ok(out)
+}
+
+/// Writes the file backed part of the loadable segments as Motorola S-records, with the entry
+/// point in the termination record. Addresses are as narrow as the highest one allows, picking
+/// `S1`, `S2` or `S3` data records.
+pub fn srec(elf: &Elf64) -> Result<String, Error> {
+    let images = load_images(elf)?;
+    let entry = entry(elf)?;
+    let highest = images
+        .iter()
+        .map(|(addr, data)| addr + data.len().saturating_sub(1) as u32)
+        .fold(entry, u32::max);
+    // Data records and the termination record matching them, by address width
+    let (data_type, end_type, width) = match highest {
+        0..=0xffff => (b'1', b'9', 2),
+        0x1_0000..=0xff_ffff => (b'2', b'8', 3),
+        _ => (b'3', b'7', 4),
+    };
+
+    let mut out = String::new();
+    srec_record(&mut out, b'0', &[0; 2], &[]);
+    for (addr, data) in images {
+        let mut addr = addr;
+        for chunk in data.chunks(RECORD_LEN) {
+            srec_record(&mut out, data_type, &addr.to_be_bytes()[4 - width..], chunk);
+            addr = addr.wrapping_add(chunk.len() as u32);
+        }
+    }
+    srec_record(&mut out, end_type, &entry.to_be_bytes()[4 - width..], &[]);
+    Ok(out)
+}
+
+/// Returns the physical address and file contents of the loadable segments with file contents,
+/// sorted by address
+fn load_images<'e>(elf: &'e Elf64) -> Result<Vec<(u32, &'e [u8])>, Error> {
+    let mut images = vec![];
+    for ph in elf.ph_table.iter().filter(|ph| ph.p_type() == SegmentType::PtLoad) {
+        let (addr, size) = (ph.p_addr(), ph.p_filesz().0);
+        if size == 0 {
+            continue;
+        }
+        if ph.data.len() as u64 != size {
+            return Err(Error::NotLoaded(addr));
+        }
+        let end = addr.0.checked_add(size - 1).filter(|end| *end <= u64::from(u32::MAX));
+        if end.is_none() {
+            return Err(Error::AddressTooLarge(addr));
+        }
+        images.push((addr.0 as u32, &ph.data[..]));
+    }
+    images.sort_by_key(|(addr, _)| *addr);
+    Ok(images)
+}
+
+fn entry(elf: &Elf64) -> Result<u32, Error> {
+    let entry = elf.elf_header.e_entry;
+    u32::try_from(entry.0).map_err(|_| Error::AddressTooLarge(entry))
+}
+
+/// Splits `data`, starting at `addr`, in records which do not cross 64KiB boundaries, as the
+/// addresses of Intel HEX data records only hold the lower 16 bits
+fn chunks(addr: u32, data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut addr = addr as usize;
+    let mut rest = data;
+    std::iter::from_fn(move || {
+        let room = 0x1_0000 - (addr & 0xffff);
+        let len = rest.len().min(RECORD_LEN).min(room);
+        let (chunk, tail) = rest.split_at(len);
+        rest = tail;
+        addr += len;
+        (!chunk.is_empty()).then_some(chunk)
+    })
+}
+
+/// Writes an Intel HEX record, whose checksum makes all of its bytes add up to zero
+fn ihex_record(out: &mut String, addr: u16, record_type: u8, data: &[u8]) {
+    let mut bytes = vec![data.len() as u8];
+    bytes.extend_from_slice(&addr.to_be_bytes());
+    bytes.push(record_type);
+    bytes.extend_from_slice(data);
+    let checksum = bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)).wrapping_neg();
+    out.push(':');
+    push_hex(out, &bytes);
+    push_hex(out, &[checksum]);
+    out.push_str("\r\n");
+}
+
+/// Writes an S-record of type `record_type`, whose checksum is the ones' complement of the sum of
+/// the count, address and data bytes
+fn srec_record(out: &mut String, record_type: u8, addr: &[u8], data: &[u8]) {
+    let mut bytes = vec![(addr.len() + data.len() + 1) as u8];
+    bytes.extend_from_slice(addr);
+    bytes.extend_from_slice(data);
+    let checksum = !bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    out.push('S');
+    out.push(record_type as char);
+    push_hex(out, &bytes);
+    push_hex(out, &[checksum]);
+    out.push_str("\r\n");
+}
+
+fn push_hex(out: &mut String, bytes: &[u8]) {
+    for byte in bytes {
+        // Writing to a `String` cannot fail
+        let _ = write!(out, "{byte:02X}");
+    }
+}
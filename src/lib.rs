@@ -1,19 +1,61 @@
-use std::{fmt, ops::Range};
+use std::{
+    borrow::Cow,
+    fmt,
+    fs,
+    hash::Hasher,
+    io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 pub mod addr;
+pub mod archive;
+pub mod builder;
+pub mod class;
+pub mod compress;
+pub mod core_dump;
+pub mod debug;
+pub mod diff;
+pub mod endian;
+pub mod entropy;
 pub mod error;
+pub mod file_map;
 pub mod file_type;
+pub mod hash;
+#[cfg(feature = "hex")]
+pub mod hex;
+#[cfg(all(feature = "loader", unix))]
+pub mod loader;
 pub mod machine;
+pub mod note;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod os_abi;
+pub mod packer;
 pub mod segment;
 pub mod section;
 pub mod reader;
 pub mod reloc;
+pub mod resolver;
+pub mod source;
+pub mod strtab;
+pub mod sym;
+pub mod tls;
+pub mod version;
+pub mod write;
 
 use segment::DynamicEntry;
 pub use segment::{SegmentContents, DynamicTable};
+use source::{Fetch, FromSource};
 
 pub use crate::{
-    addr::Addr,
+    addr::{Addr, FileOffset, Size},
+    builder::ElfBuilder,
+    class::Class,
+    core_dump::CoreNote,
+    debug::DebugLink,
+    diff::{DynamicChange, ElfDiff, FieldChange, SectionChange, SymbolChange},
+    endian::Endianness,
     error::{
         ElfError,
         ElfHeaderError,
@@ -23,452 +65,5264 @@ pub use crate::{
         DynamicError,
         StringError,
     },
-    file_type::FileType,
+    file_map::{CodeCave, FileMapEntry, FileRegion},
+    file_type::{FileType, ObjectKind},
+    hash::{GnuHashTable, SysvHashTable},
     machine::Machine,
-    segment::{SegmentType, SegmentFlags, DynamicTag},
-    reloc::{Rela, RelType},
-    reader::Reader,
-    section::{SectionHeader},
+    note::{AbiTag, AbiTagOs, BuildId, GnuProperty, Note, Notes, X86Feature1, X86Isa1},
+    os_abi::OsAbi,
+    packer::{PackerAnomaly, PackerInfo},
+    segment::{
+        SegmentType, SegmentFlags, DynamicFlags, DynamicFlags1, DynamicTag, DynamicValue,
+        RelroLevel, SegmentIssue, SegmentDiagnostic, PT_GNU_EH_FRAME, PT_GNU_PROPERTY, PT_GNU_RELRO,
+        PT_GNU_SFRAME, PT_GNU_STACK,
+    },
+    reloc::{
+        AArch64RelType, ArmRelType, I386RelType, IndirectFunction, NamedRelocation,
+        PackedRelocation, PltRelocations, Rel, Rela, RelType, RelativeRelocation, Relocation,
+        RelocationSection, RelocationTable, RiscVRelType, X64RelType,
+    },
+    resolver::{Dependency, DependencyGraph, SearchPaths},
+    reader::{Checkpoint, Reader},
+    source::ReadAt,
+    strtab::StringTable,
+    section::{Section, SectionHeader, SectionFlags, SectionType, SHN_XINDEX},
+    sym::{
+        Export, Import, Symbol, SymbolDiagnostic, SymbolEntry, SymbolIssue, SymbolInfo,
+        SymbolType, SymbolBinding, SymbolVisibility, SymbolError,
+    },
+    tls::{StaticTlsLayout, TlsTemplate, TlsVariant},
+    version::{
+        RequiredVersion, SymbolVersion, VersionDefinition, VersionNeeded, VersionRequirement,
+    },
+    write::Writer,
 };
 
-/// Structure that represents an Elf 64-bit file
-/// We are only parsing x86 ISA little endian Elfs
-pub struct Elf64 {
+/// Structure that represents an Elf file
+/// Both 32-bit and 64-bit files are parsed, with 32-bit addresses and offsets widened to 64-bit,
+/// in either byte order.
+/// The contents of segments and sections are borrowed from the parsed bytes, not copied.
+pub struct Elf64<'a> {
     pub elf_header: ElfHeader,
     /// `ProgramHeader` table
-    pub ph_table: Vec<ProgramHeader>,
+    pub ph_table: Vec<ProgramHeader<'a>>,
     /// `SectionHeader` table
-    pub sh_table: Vec<SectionHeader>,
+    pub sh_table: Vec<SectionHeader<'a>>,
+    /// Dynamic table parsed from the `SHT_DYNAMIC` section, for files without a `PT_DYNAMIC`
+    /// segment, like relocatable files
+    dynamic_section: Option<DynamicTable>,
+    /// Bytes of the file no header, segment or section claims, like padding or data appended to
+    /// the file, at their offsets, kept to write them back as they were
+    unclaimed: Vec<(FileOffset, Cow<'a, [u8]>)>,
 }
 
-impl Elf64 {
-    pub fn parse(bytes: &[u8]) -> Result<Self, ElfError> {
-        let mut reader = Reader::from_bytes(bytes);
-        let elf_header = ElfHeader::parse(&mut reader)?;
+/// Controls how much of the file `Elf64::parse_with` reads
+#[derive(Debug, Clone, Copy)]
+pub struct ParseOptions {
+    /// Borrow the contents of every segment and section and decode the ones we know about, like the
+    /// dynamic table. When false, only the headers and the dynamic table are parsed and the other
+    /// contents can be loaded later with `Elf64::load_data`.
+    pub load_data: bool,
+    /// Keep the bytes no header, segment or section claims, like padding or data appended to the
+    /// file, so that `Elf64::to_bytes` writes them back. They are borrowed when parsing a slice,
+    /// but `Elf64::parse_source` has to read them, however large, so it is off by default.
+    pub keep_unclaimed: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self { load_data: true, keep_unclaimed: false }
+    }
+}
+
+/// Controls what `Elf64::strip` removes
+#[derive(Debug, Clone, Copy)]
+pub struct StripOptions {
+    /// Keep the section headers of the dynamic symbol table and of the string, hash and version
+    /// tables that go with it. Their contents are needed at run time, so they stay in their
+    /// segments either way.
+    pub keep_dynamic_symbols: bool,
+}
+
+impl Default for StripOptions {
+    fn default() -> Self {
+        Self { keep_dynamic_symbols: true }
+    }
+}
+
+impl Elf64<'static> {
+    /// Parses a file from `source`, reading only the parts that are needed. Unlike parsing from a
+    /// slice, the contents of segments and sections are read into owned buffers.
+    pub fn parse_source<S: ReadAt + ?Sized>(
+        source: &S,
+        options: ParseOptions,
+    ) -> Result<Self, ElfError> {
+        Self::parse_from(&FromSource(source), options)
+    }
+}
+
+impl<'a> Elf64<'a> {
+    /// Parses the file `bytes` holds, borrowing all of its contents, including the bytes nothing
+    /// claims
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, ElfError> {
+        Self::parse_with(bytes, ParseOptions { keep_unclaimed: true, ..Default::default() })
+    }
 
-        // Allocate a new vector to hold the Program header table
-        let mut ph_table = Vec::with_capacity(elf_header.e_phnum().into());
+    /// Parses only the Elf header, the program header table and the section header table, leaving
+    /// the contents of segments and sections empty until `load_data` is called, apart from the
+    /// dynamic table, which most lookups start from. Lookups through addresses fail with a
+    /// `NotLoaded` error until then.
+    pub fn parse_headers(bytes: &'a [u8]) -> Result<Self, ElfError> {
+        Self::parse_with(bytes, ParseOptions { load_data: false, ..Default::default() })
+    }
+
+    pub fn parse_with(bytes: &'a [u8], options: ParseOptions) -> Result<Self, ElfError> {
+        Self::parse_from(&bytes, options)
+    }
 
-        // Move the read cursor to the program header table beginning
-        reader.seek(elf_header.e_phoff().into())?;
+    /// Parses the file, fetching the Elf header, the header tables and, depending on `options`,
+    /// the contents of segments and sections through `fetch`
+    fn parse_from<F: Fetch<'a>>(fetch: &F, options: ParseOptions) -> Result<Self, ElfError> {
+        // The class decides how large the Elf header is
+        let ident = fetch.fetch(0, EI_NIDENT)?;
+        let header_size = match Class::try_from(ident[EI_CLASS]) {
+            Ok(Class::Elf32) => ELF32_HEADER_SIZE,
+            _ => ELF64_HEADER_SIZE,
+        };
+        let elf_header = ElfHeader::parse(&mut Reader::from_bytes(&fetch.fetch(0, header_size)?))?;
 
-        for _ in 0..elf_header.e_phnum() {
-            ph_table.push(ProgramHeader::parse(&mut reader)?);
+        // Entries may be larger than the structures we know about, but never smaller
+        let class = elf_header.e_class();
+        let phentsize = usize::from(elf_header.e_phentsize());
+        let shentsize = usize::from(elf_header.e_shentsize());
+        if elf_header.e_phnum() != 0 && phentsize < ProgramHeader::size(class) {
+            return Err(ElfHeaderError::BadPhEntSize(elf_header.e_phentsize()).into());
+        }
+        if elf_header.e_shoff() != FileOffset(0) && shentsize < SectionHeader::size(class) {
+            return Err(ElfHeaderError::BadShEntSize(elf_header.e_shentsize()).into());
         }
 
-        // Allocate a new vector to hold the SectionHeader table
-        let mut sh_table = Vec::with_capacity(elf_header.e_shnum().into());
-        // Move the read cursor to the section header table beginning
-        reader.seek(elf_header.e_shoff().into())?;
+        // Files with more entries than the Elf header can count keep the real counts in the
+        // first entry of the section header table
+        let first_section = if elf_header.e_shoff() != FileOffset(0) {
+            let bytes = fetch.fetch(elf_header.e_shoff().into(), shentsize)?;
+            Some(SectionHeader::parse_header(&mut elf_header.reader(&bytes))?)
+        } else {
+            None
+        };
+        let phnum: usize = match (elf_header.e_phnum(), &first_section) {
+            (PN_XNUM, Some(sh)) => sh.sh_info() as usize,
+            (phnum, _) => phnum.into(),
+        };
+        let shnum: usize = match (elf_header.e_shnum(), &first_section) {
+            (0, Some(sh)) => sh.sh_size().into(),
+            (shnum, _) => shnum.into(),
+        };
 
-        for _ in 0..elf_header.e_shnum() {
-            sh_table.push(SectionHeader::parse(&mut reader)?);
+        // Allocate a new vector to hold the Program header table. Extended counts come straight
+        // from the file, so they do not get to decide how much we allocate upfront.
+        let mut ph_table = Vec::with_capacity(phnum.min(PN_XNUM.into()));
+        let bytes = fetch_table(fetch, elf_header.e_phoff(), phnum, phentsize)?;
+        let mut reader = elf_header.reader(&bytes);
+        for index in 0..phnum {
+            // Move the read cursor to the beginning of the entry
+            reader.seek(index * phentsize)?;
+            ph_table.push(ProgramHeader::parse_header(&mut reader)?);
         }
 
-        Ok(Self {
+        // Allocate a new vector to hold the SectionHeader table
+        let mut sh_table = Vec::with_capacity(shnum.min(PN_XNUM.into()));
+        let bytes = fetch_table(fetch, elf_header.e_shoff(), shnum, shentsize)?;
+        let mut reader = elf_header.reader(&bytes);
+        for index in 0..shnum {
+            // Move the read cursor to the beginning of the entry
+            reader.seek(index * shentsize)?;
+            sh_table.push(SectionHeader::parse_header(&mut reader)?);
+        }
+
+        let mut elf = Self {
             elf_header,
             ph_table,
             sh_table,
-        })
-    }
+            dynamic_section: None,
+            unclaimed: vec![],
+        };
+        if options.load_data {
+            elf.load_data_from(fetch, options.keep_unclaimed)?;
+        } else {
+            elf.load_dynamic_from(fetch)?;
+        }
 
-    /// Returns the `ProgramHeader` of the segment that contains the `addr`
-    pub fn segment_at(&self, addr: Addr) -> Option<&ProgramHeader> {
-        self.ph_table
-            .iter()
-            .filter(|ph| ph.p_type == SegmentType::PtLoad)
-            .find(|ph| ph.mem_range().contains(&addr))
+        Ok(elf)
     }
 
-    /// Returns a slice from the the Load segment containing `mem_addr` address.
-    /// The slice spans from `mem_addr` until the end of the segment.
-    pub fn slice_at(&self, mem_addr: Addr) -> Option<&[u8]> {
-        self.segment_at(mem_addr)
-            .map(|seg| &seg.data[(mem_addr - seg.mem_range().start).into()..])
+    /// Loads the contents of every segment and section from `bytes`, which must be the same bytes
+    /// the headers were parsed from. Used to finish an `Elf64` parsed with `parse_headers`. The
+    /// bytes nothing claims are kept, like `parse` does.
+    pub fn load_data(&mut self, bytes: &'a [u8]) -> Result<(), ElfError> {
+        self.load_data_from(&bytes, true)
     }
 
-    /// Returns a string from the string table located at `offset`.
-    pub fn get_string(&self, offset: Addr) -> Result<String, StringError> {
-        let addr = self.dynamic_entry(DynamicTag::StrTab).ok_or(StringError::StringNotFound)?;
-        let slice = self
-            .slice_at(addr + offset)
-            .ok_or(StringError::StrTabSegmentNotFound)?;
-        // String are null terminated. So we split the slice into slices separated by '\0'
-        let string_slice = slice.split(|&c| c == 0).next().ok_or(StringError::StringNotFound)?;
-        Ok(String::from_utf8_lossy(string_slice).into())
-    }
+    /// Loads the contents of every segment and section through `fetch`, and the bytes nothing
+    /// claims if `keep_unclaimed` is set
+    fn load_data_from<F: Fetch<'a>>(
+        &mut self,
+        fetch: &F,
+        keep_unclaimed: bool,
+    ) -> Result<(), ElfError> {
+        let (class, endianness) = (self.elf_header.e_class(), self.elf_header.e_data());
+        for ph in self.ph_table.iter_mut() {
+            let data = fetch.fetch(ph.p_offset.into(), ph.p_filesz.into())?;
+            ph.set_data(data, class, endianness)?;
+        }
+        for sh in self.sh_table.iter_mut() {
+            if let Some(range) = sh.file_range() {
+                sh.data = fetch.fetch(range.start, range.len())?;
+            }
+        }
 
-    /// Returns the first segment of type `p_type`.
-    pub fn segment_of_type(&self, p_type: SegmentType) -> Option<&ProgramHeader> {
-        self.ph_table
-            .iter()
-            .find(|ph| ph.p_type() == p_type)
-    }
+        let names: Vec<_> = self.sh_table.iter().map(|sh| self.section_name(sh)).collect();
+        for (sh, name) in self.sh_table.iter_mut().zip(names) {
+            sh.name = name;
+        }
+        self.parse_dynamic_section()?;
+        if !keep_unclaimed {
+            return Ok(());
+        }
 
-    /// Return an entry from the Dynamic table with the given `tag` or None if `tag` does not exist
-    /// in the table
-    pub fn dynamic_entry(&self, tag: DynamicTag) -> Option<Addr> {
-        self.dynamic_entries(tag).next()
+        let gaps = self.file_map(fetch.size()?).into_iter();
+        self.unclaimed = gaps
+            .filter(|entry| entry.region == FileRegion::Gap)
+            .map(|entry| {
+                let len = entry.range.end.0 - entry.range.start.0;
+                Ok((entry.range.start, fetch.fetch(entry.range.start.into(), len as usize)?))
+            })
+            .collect::<Result<_, ElfError>>()?;
+        Ok(())
     }
 
-    pub fn dynamic_table(&self) -> Option<&[DynamicEntry]> {
-        match self.segment_of_type(SegmentType::PtDynamic) {
-            Some(ProgramHeader {
-                contents: SegmentContents::Dynamic(table),
-                ..
-            }) => Some(table.entries()),
-            _ => None,
+    /// Loads the dynamic table alone, from the `PT_DYNAMIC` segment or else from the `.dynamic`
+    /// section, as most lookups start from it, even when the other contents are left for later
+    fn load_dynamic_from<F: Fetch<'a>>(&mut self, fetch: &F) -> Result<(), ElfError> {
+        let (class, endianness) = (self.elf_header.e_class(), self.elf_header.e_data());
+        let segment = self.ph_table.iter_mut().find(|ph| ph.p_type == SegmentType::PtDynamic);
+        if let Some(ph) = segment {
+            let data = fetch.fetch(ph.p_offset.into(), ph.p_filesz.into())?;
+            ph.set_data(data, class, endianness)?;
+            return Ok(());
         }
+        let section = self.sh_table.iter_mut().find(|sh| sh.sh_type() == SectionType::ShtDynamic);
+        if let Some(sh) = section {
+            if let Some(range) = sh.file_range() {
+                sh.data = fetch.fetch(range.start, range.len())?;
+            }
+        }
+        self.parse_dynamic_section()
     }
 
-    /// Returns an `Interator` over addresses contained in the entries of the dynamic table which
-    /// have `tag`
-    pub fn dynamic_entries(&self, tag: DynamicTag) -> impl Iterator<Item = Addr> + '_{
-        self.dynamic_table()
-            .unwrap_or_default()
-            .iter()
-            .filter(move |e| e.d_tag == tag)
-            .map(|e| e.d_un)
+    /// Parses the dynamic table from the `.dynamic` section, for files without a `PT_DYNAMIC`
+    /// segment
+    fn parse_dynamic_section(&mut self) -> Result<(), ElfError> {
+        if self.segment_of_type(SegmentType::PtDynamic).is_none() {
+            let dynamic = self.sh_table.iter().find(|sh| sh.sh_type() == SectionType::ShtDynamic);
+            if let Some(sh) = dynamic {
+                let mut reader = self.elf_header.reader(&sh.data);
+                let table = DynamicTable::parse(&mut reader).map_err(section::SectionError::from)?;
+                self.dynamic_section = Some(table);
+            }
+        }
+        Ok(())
     }
 
-    pub fn dynamic_entry_strings(&self, tag: DynamicTag) -> impl Iterator<Item = String> + '_ {
-        self.dynamic_entries(tag)
-            .filter_map(move |addr| self.get_string(addr).ok())
+    /// Compares the file with `other`, a patched copy of it for instance: the fields of their
+    /// headers, their sections, symbols and dynamic symbols, matched by name, and the values of
+    /// their dynamic entries
+    pub fn diff(&self, other: &Elf64) -> Result<ElfDiff, diff::Error> {
+        diff::diff(self, other)
     }
 
-    /// Reads and returns the vector of `Rela` entries from the file
-    pub fn read_rela_entries(&self) -> Result<Vec<Rela>, SegmentError> {
-        use DynamicTag;
-        use DynamicError;
+    /// Writes the file back to bytes: the bytes of the parsed file which nothing claimed, then the
+    /// contents of every segment, then those of every section, then the header tables and last
+    /// the Elf header, each at the offset its header gives, so headers win over the copies
+    /// segments hold of them. Other bytes outside all of these are zero.
+    /// A file parsed and written back untouched gives the bytes it was parsed from, including
+    /// padding and data appended to it if they were kept, see `ParseOptions::keep_unclaimed`.
+    /// Headers are written as they are, so after changing the tables the caller keeps the counts
+    /// and offsets in the Elf header in sync. Contents must have been loaded, and match the size
+    /// their header gives.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, write::Error> {
+        let header = &self.elf_header;
+        let mut writer = Writer::new(header.e_class, header.e_data);
+        for (offset, bytes) in self.unclaimed.iter() {
+            writer.set_position((*offset).into());
+            writer.write_slice(bytes);
+        }
+        for ph in self.ph_table.iter() {
+            write_contents(&mut writer, ph.p_offset, ph.p_filesz, &ph.data)?;
+        }
+        for sh in self.sh_table.iter().filter(|sh| sh.file_range().is_some()) {
+            write_contents(&mut writer, sh.sh_offset(), sh.sh_size(), &sh.data)?;
+        }
 
-        // Get address for the Rela entries
-        let rela_addr = self
-            .dynamic_entry(DynamicTag::RelA)
-            .ok_or(DynamicError::TagNotFound(DynamicTag::RelA))?;
+        let phentsize = usize::from(header.e_phentsize);
+        for (index, ph) in self.ph_table.iter().enumerate() {
+            writer.set_position(usize::from(header.e_phoff) + index * phentsize);
+            ph.write_header(&mut writer)?;
+            writer.write_zeros(phentsize.saturating_sub(ProgramHeader::size(header.e_class)));
+        }
+        let shentsize = usize::from(header.e_shentsize);
+        for (index, sh) in self.sh_table.iter().enumerate() {
+            writer.set_position(usize::from(header.e_shoff) + index * shentsize);
+            sh.write_header(&mut writer)?;
+            writer.write_zeros(shentsize.saturating_sub(SectionHeader::size(header.e_class)));
+        }
 
-        // Get total length, in bytes, for the Rela entries
-        let rela_len = self
-            .dynamic_entry(DynamicTag::RelASz)
-            .ok_or(DynamicError::TagNotFound(DynamicTag::RelASz))?;
+        writer.set_position(0);
+        header.write(&mut writer)?;
+        Ok(writer.into_bytes())
+    }
 
-        // Get the segment where the Rela entries are store
-        let seg = self.segment_at(rela_addr).ok_or(SegmentError::BadPtLoadAddr(rela_addr))?;
+    /// Writes the raw contents of the section named `name` to `out`, as they are in the file, like
+    /// `objcopy -O binary -j <name>` does. Sections taking no space in the file write nothing.
+    pub fn dump_section(&self, name: &str, out: &mut impl io::Write) -> Result<(), write::Error> {
+        let sh = self.section_by_name(name).ok_or_else(|| write::Error::NoSection(name.into()))?;
+        out.write_all(&sh.data)?;
+        Ok(())
+    }
 
-        // Prepare a range to fetch bytes
-        let rela_range: Range<usize> = Range { 
-            start: (rela_addr - seg.mem_range().start).into(),
-            end: ((rela_addr + rela_len) - seg.mem_range().start).into(),
+    /// Lays out the file backed part of the loadable segments by virtual address, starting from
+    /// the lowest one, like `objcopy -O binary` does for kernels and firmware. Gaps between
+    /// segments are zero filled, while the zeros past the end of the last segment's file data,
+    /// like `.bss`, are left out. Contents must have been loaded.
+    pub fn flat_binary(&self) -> Result<Vec<u8>, write::Error> {
+        let segments: Vec<_> = self
+            .ph_table
+            .iter()
+            .filter(|ph| ph.p_type == SegmentType::PtLoad && ph.p_filesz.0 != 0)
+            .collect();
+        let Some(base) = segments.iter().map(|ph| ph.p_vaddr.0).min() else {
+            return Ok(vec![]);
         };
+        let mut writer = Writer::new(self.elf_header.e_class, self.elf_header.e_data);
+        for ph in segments {
+            let offset = FileOffset(ph.p_vaddr.0 - base);
+            write_contents(&mut writer, offset, ph.p_filesz, &ph.data)?;
+        }
+        Ok(writer.into_bytes())
+    }
 
-        // Fetch the slice to parse the rela from
-        let rela_slice = seg.data.get(rela_range.clone()).ok_or(ParseError::BadRange(rela_range))?;
+    /// Feeds the address and file contents of the executable loadable segments to `hasher` and
+    /// returns its result, to compare the code of two files while ignoring everything else, like
+    /// debug info. See `loadable_hash`.
+    pub fn text_hash<H: Hasher>(&self, hasher: &mut H) -> u64 {
+        self.hash_segments(hasher, |ph| ph.p_flags.contains(SegmentFlags::EXEC))
+    }
 
-        // Construct a reader
-        let mut reader = Reader::from_bytes(rela_slice);
+    /// Feeds the address and file contents of every loadable segment to `hasher` and returns its
+    /// result, to compare what two files map in memory while ignoring the rest of them, like debug
+    /// info. The section header table fields of a mapped Elf header are hashed as zero, as
+    /// stripping changes them.
+    /// Any `Hasher` fits, including wrappers around cryptographic hashes, which the caller can
+    /// finish themselves.
+    pub fn loadable_hash<H: Hasher>(&self, hasher: &mut H) -> u64 {
+        self.hash_segments(hasher, |_| true)
+    }
 
-        // Initialise a `Vec` to hold Rela entries
-        let mut rela_entries: Vec<Rela> = vec![];
-        // Parse the Rela entries
-        while reader.index < rela_len.into() {
-            let rela = Rela::parse(&mut reader)?;
-            rela_entries.push(rela);
+    fn hash_segments<H: Hasher>(
+        &self,
+        hasher: &mut H,
+        filter: impl Fn(&ProgramHeader) -> bool,
+    ) -> u64 {
+        let class = self.elf_header.e_class;
+        let header_size = match class {
+            Class::Elf32 => ELF32_HEADER_SIZE,
+            Class::Elf64 => ELF64_HEADER_SIZE,
+        };
+        let loads = self.ph_table.iter().filter(|ph| ph.p_type == SegmentType::PtLoad);
+        for ph in loads.filter(|ph| filter(ph)) {
+            hasher.write(&ph.p_vaddr.0.to_le_bytes());
+            if ph.p_offset.0 != 0 || ph.data.len() < header_size {
+                hasher.write(&ph.data);
+                continue;
+            }
+            let mut header = ph.data[..header_size].to_vec();
+            // `e_shoff`, which follows `e_entry` and `e_phoff`
+            let shoff = E_ENTRY_OFFSET + 2 * class.word_size();
+            header[shoff..shoff + class.word_size()].fill(0);
+            // `e_shentsize`, `e_shnum` and `e_shstrndx`, which end the header
+            header[header_size - 6..].fill(0);
+            hasher.write(&header);
+            hasher.write(&ph.data[header_size..]);
         }
-
-        Ok(rela_entries)
-        
+        hasher.finish()
     }
 
-    /// Returns the section header that start at EXACTLY this virtual address `addr`,
-    /// or `None` if we can't find one.
-    pub fn section_starting_at(&self, addr: Addr) -> Option<&SectionHeader> {
-        self.sh_table.iter().find(|&sh| sh.sh_addr() == addr)
+    /// Writes the file backed part of the loadable segments at their physical addresses as Intel
+    /// HEX, for flash programmers. Contents must have been loaded.
+    #[cfg(feature = "hex")]
+    pub fn to_intel_hex(&self) -> Result<String, hex::Error> {
+        hex::intel_hex(self)
     }
-}
 
-impl fmt::Debug for Elf64 {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{:?}\n{}\n",
-            self.elf_header,
-            self.ph_table.iter().map(|ph_entry| format!("{:?}", ph_entry))
-            .collect::<Vec<_>>().join("\n"))
+    /// Writes the file backed part of the loadable segments at their physical addresses as
+    /// Motorola S-records, for flash programmers. Contents must have been loaded.
+    #[cfg(feature = "hex")]
+    pub fn to_srec(&self) -> Result<String, hex::Error> {
+        hex::srec(self)
     }
-}
 
-/// Tell the system how to create a process image. It is found at file offset
-/// `e_phoff` and consists of `e_phnum` entries, each with size `e_phentsize`.
-pub struct ProgramHeader {
-    /// Identifies the type of the segment
-    p_type: SegmentType,
-    /// BitMask for segment-dependent flags
-    p_flags: SegmentFlags,
-    /// Offset of the segment in the file image,
-    p_offset: Addr,
-    /// Virtual Address of the segment in memory,
-    p_vaddr: Addr,
-    /// On systems where physical address is relevant, reserved for segment's
-    /// physical address
-    p_paddr: Addr,
-    /// Size in bytes of the segment in the file image. May be 0.
-    p_filesz: Addr,
-    /// Size in bytes of the segment in memory
-    p_memsz: Addr,
-    /// 0 and 1 specify no alignment. Otherwise should be a positive, integral
-    /// power of 2 with p_vaddr = p_offset % p_align
-    p_align: Addr,
-    /// A vector storing the contents of the segment
-    pub data: Vec<u8>,
-    /// Contents of the current segment based on `SegmentType`
-    pub contents: SegmentContents,
-}
+    /// Sets the address the process starts executing from, for `to_bytes` or `patch_entry` to
+    /// write out
+    pub fn set_entry(&mut self, entry: Addr) {
+        self.elf_header.e_entry = entry;
+    }
 
-impl ProgramHeader {
-    pub fn parse(reader: &mut Reader) -> Result<Self, ProgramHeaderError> {
-        let p_type = SegmentType::parse(reader)?;
-        let p_flags = SegmentFlags::parse(reader)?;
-        let p_offset = Addr::parse(reader)?;
-        let p_vaddr = Addr::parse(reader)?;
-        let p_paddr = Addr::parse(reader)?;
-        let p_filesz = Addr::parse(reader)?;
-        let p_memsz = Addr::parse(reader)?;
-        let p_align = Addr::parse(reader)?;
+    /// Writes the entry point into `bytes`, the file this was parsed from, without touching any
+    /// other byte of it
+    pub fn patch_entry(&self, bytes: &mut [u8]) -> Result<(), write::Error> {
+        let mut writer = Writer::new(self.elf_header.e_class, self.elf_header.e_data);
+        writer.write_word(self.elf_header.e_entry.into())?;
+        write::patch(bytes, E_ENTRY_OFFSET, &writer.into_bytes())
+    }
 
-        let segment_start: usize = p_offset.into();
-        let segment_end: usize = Into::<usize>::into(p_offset) +
-            Into::<usize>::into(p_filesz);
+    /// Sets the permissions of the segment at `index` in the program header table, for `to_bytes`
+    /// or `patch_segment_flags` to write out, like clearing `EXEC` on `PtGnuStack`. Segments
+    /// lying inside a loadable segment, like `PtDynamic` or `PtGnuRelro`, cannot have permissions
+    /// the loadable segment does not give, so such changes fail and leave the flags alone.
+    pub fn set_segment_flags(
+        &mut self,
+        index: usize,
+        flags: SegmentFlags,
+    ) -> Result<(), write::Error> {
+        let ph = self.ph_table.get(index).ok_or(write::Error::NoSegment(index))?;
+        let range = ph.mem_range();
+        let is_load = ph.p_type == SegmentType::PtLoad;
+        for (other, ph) in self.ph_table.iter().enumerate().filter(|(other, _)| *other != index) {
+            let (inner, outer, inner_flags, outer_flags) = match (is_load, ph.p_type) {
+                (true, SegmentType::PtLoad) => continue,
+                (true, _) if contains_range(&range, &ph.mem_range()) => {
+                    (other, index, ph.p_flags, flags)
+                }
+                (false, SegmentType::PtLoad) if contains_range(&ph.mem_range(), &range) => {
+                    (index, other, flags, ph.p_flags)
+                }
+                _ => continue,
+            };
+            if !outer_flags.contains(inner_flags) {
+                return Err(write::Error::SegmentFlagsConflict { inner, outer });
+            }
+        }
+        self.ph_table[index].p_flags = flags;
+        Ok(())
+    }
 
-        let segment_data_range = Range {
-            start: segment_start,
-            end: segment_end
+    /// Writes the flags of the segment at `index` into `bytes`, the file this was parsed from,
+    /// without touching any other byte of it
+    pub fn patch_segment_flags(&self, index: usize, bytes: &mut [u8]) -> Result<(), write::Error> {
+        let ph = self.ph_table.get(index).ok_or(write::Error::NoSegment(index))?;
+        let header = &self.elf_header;
+        let mut writer = Writer::new(header.e_class, header.e_data);
+        writer.write_u32(ph.p_flags.bits());
+        // 64-bit headers keep the flags right after the type, to align the words after them
+        let field = match header.e_class {
+            Class::Elf32 => 24,
+            Class::Elf64 => 4,
         };
+        let offset = usize::from(header.e_phoff) + index * usize::from(header.e_phentsize) + field;
+        write::patch(bytes, offset, &writer.into_bytes())
+    }
 
-        let data = reader.read_slice_from(segment_data_range)?.to_vec();
+    /// Appends a `PtLoad` segment at `vaddr` to the end of the file, holding the program header
+    /// table followed by `data`, and returns the address `data` is loaded at.
+    /// The table cannot grow where it is without moving what follows it, so it moves into the
+    /// new segment, which keeps it mapped in memory, readable, for the dynamic loader. `e_phoff`,
+    /// `e_phnum` and the `PtPhdr` segment, if any, are updated to match. `vaddr` has to be past
+    /// the pages of the other loadable segments, as those are sorted by address.
+    pub fn add_load_segment(
+        &mut self,
+        vaddr: Addr,
+        flags: SegmentFlags,
+        data: &[u8],
+    ) -> Result<Addr, write::Error> {
+        if self.next_load_addr().is_none_or(|next| vaddr < next) {
+            return Err(write::Error::BadSegmentAddr(vaddr));
+        }
+        let phnum = self.ph_table.len() + 1;
+        if phnum >= PN_XNUM.into() {
+            return Err(write::Error::TooManySegments(phnum));
+        }
 
-        let contents = match p_type {
-            SegmentType::PtDynamic => {
-                // Parse the dynamic table
-                SegmentContents::Dynamic(DynamicTable::parse(&data)?)
-            },
-            _ => SegmentContents::Unknown,
-        };
+        let align = self.load_align();
+        let header = &mut self.elf_header;
+        if header.e_phentsize == 0 {
+            header.e_phentsize = ProgramHeader::size(header.e_class) as u16;
+        }
+        let table_size = Size((phnum * usize::from(header.e_phentsize)) as u64);
+        let end = self.file_size();
+        let offset = FileOffset(end + vaddr.0.wrapping_sub(end) % align);
+        let size = Size(table_size.0 + data.len() as u64);
+        self.ph_table.push(ProgramHeader {
+            p_type: SegmentType::PtLoad,
+            p_flags: flags | SegmentFlags::READ,
+            p_offset: offset,
+            p_vaddr: vaddr,
+            p_paddr: vaddr,
+            p_filesz: size,
+            p_memsz: size,
+            p_align: align,
+            data: Cow::Borrowed(&[]),
+            contents: SegmentContents::Unknown,
+        });
+        for ph in self.ph_table.iter_mut().filter(|ph| ph.p_type == SegmentType::PtPhdr) {
+            (ph.p_offset, ph.p_vaddr, ph.p_paddr) = (offset, vaddr, vaddr);
+            (ph.p_filesz, ph.p_memsz) = (table_size, table_size);
+        }
+        self.elf_header.e_phoff = offset;
+        self.elf_header.e_phnum = phnum as u16;
 
-        Ok(Self {
-            p_type,
-            p_flags,
-            p_offset,
-            p_vaddr,
-            p_paddr,
-            p_filesz,
-            p_memsz,
-            p_align,
-            data,
-            contents,
-        })
+        // The segments hold a copy of the table as it is now, which `to_bytes` writes over with
+        // the table as it is then
+        let mut writer = Writer::new(self.elf_header.e_class, self.elf_header.e_data);
+        let phentsize = usize::from(self.elf_header.e_phentsize);
+        for (index, ph) in self.ph_table.iter().enumerate() {
+            writer.set_position(index * phentsize);
+            ph.write_header(&mut writer)?;
+        }
+        writer.set_position(table_size.into());
+        let table = writer.into_bytes();
+        for ph in self.ph_table.iter_mut().filter(|ph| ph.p_type == SegmentType::PtPhdr) {
+            ph.data = Cow::Owned(table.clone());
+        }
+        if let Some(ph) = self.ph_table.last_mut() {
+            ph.data = Cow::Owned([&table[..], data].concat());
+        }
+        Ok(vaddr + table_size)
     }
 
-    /// Returns a range where the segment is stored in the file
-    pub fn file_range(&self) -> Range<Addr> {
-        self.p_offset..self.p_offset + self.p_filesz
+    /// Returns the lowest address a new loadable segment can be added at, the start of the page
+    /// after those of the other loadable segments
+    pub fn next_load_addr(&self) -> Option<Addr> {
+        let loads = self.ph_table.iter().filter(|ph| ph.p_type == SegmentType::PtLoad);
+        let end = loads.map(|ph| ph.mem_range().end.0).max().unwrap_or(0);
+        end.checked_next_multiple_of(self.load_align()).map(Addr)
     }
 
-    /// Returns a range where the segment should be stored in memory
-    pub fn mem_range(&self) -> Range<Addr> {
-        self.p_vaddr..self.p_vaddr + self.p_memsz
+    /// Adds a loadable segment holding `data` at `next_load_addr`, like `add_load_segment` does,
+    /// and returns the address `data` is loaded at
+    fn append_load_segment(
+        &mut self,
+        flags: SegmentFlags,
+        data: &[u8],
+    ) -> Result<Addr, write::Error> {
+        let vaddr = self.next_load_addr().ok_or(write::Error::BadSegmentAddr(Addr(0)))?;
+        self.add_load_segment(vaddr, flags, data)
     }
 
-    pub fn p_vaddr(&self) -> Addr {
-        self.p_vaddr
+    /// Returns the alignment of the loadable segments, at least that of a page
+    fn load_align(&self) -> u64 {
+        let loads = self.ph_table.iter().filter(|ph| ph.p_type == SegmentType::PtLoad);
+        loads.map(|ph| ph.p_align).max().unwrap_or(0).max(0x1000)
     }
 
-    pub fn p_memsz(&self) -> Addr {
-        self.p_memsz
+    /// Sets the path of the program interpreter, the dynamic loader named by `PT_INTERP`. The
+    /// path takes the place of the old one when it fits there. Otherwise it moves to a new
+    /// loadable segment, as the dynamic loader reads its own name from memory.
+    pub fn set_interpreter(&mut self, path: &str) -> Result<(), write::Error> {
+        let index = self
+            .ph_table
+            .iter()
+            .position(|ph| ph.p_type == SegmentType::PtInterp)
+            .ok_or(write::Error::NoInterpreter)?;
+        let mut string = [path.as_bytes(), &[0]].concat();
+        let size = usize::from(self.ph_table[index].p_filesz);
+        if string.len() <= size {
+            string.resize(size, 0);
+            return self.write_at(self.ph_table[index].p_vaddr, &string);
+        }
+
+        let addr = self.append_load_segment(SegmentFlags::READ, &string)?;
+        let offset = self.segment_at(addr).and_then(|load| load.addr_to_offset(addr));
+        let old = self.ph_table[index].p_vaddr;
+        let ph = &mut self.ph_table[index];
+        (ph.p_offset, ph.p_vaddr, ph.p_paddr) = (offset.unwrap_or(FileOffset(0)), addr, addr);
+        (ph.p_filesz, ph.p_memsz) = (Size(string.len() as u64), Size(string.len() as u64));
+        ph.set_data(Cow::Owned(string.clone()), self.elf_header.e_class, self.elf_header.e_data)?;
+        self.move_section(old, addr, &string);
+        Ok(())
     }
 
-    pub fn p_flags(&self) -> SegmentFlags {
-        self.p_flags
+    /// Sets the library search path of the object to `path`, in a `DT_RUNPATH` entry. A
+    /// `DT_RPATH` entry becomes that entry, as `DT_RPATH` is ignored next to `DT_RUNPATH`.
+    /// `path` takes the place of the old path in the dynamic string table when it fits there, or
+    /// reuses a string which is already in the table. Otherwise the table moves to a new
+    /// loadable segment with `path` appended, and so does the dynamic table when it has no spare
+    /// null entry left for a new `DT_RUNPATH`.
+    pub fn set_runpath(&mut self, path: &str) -> Result<(), write::Error> {
+        let mut entries = self.dynamic_table().ok_or(write::Error::NoDynamicTable)?.to_vec();
+        let slot = entries
+            .iter()
+            .position(|entry| entry.d_tag == DynamicTag::RunPath)
+            .or_else(|| entries.iter().position(|entry| entry.d_tag == DynamicTag::RPath));
+
+        let (mut offset, mut strtab) = self.dynamic_string_offset(path)?;
+        if let (Some(_), Some(index)) = (&strtab, slot) {
+            let old = usize::from(entries[index].d_un);
+            let len = self
+                .dynamic_string_table()
+                .map_err(|_| write::Error::NoDynamicStringTable)?
+                .get(old)
+                .map_or(0, |old| old.len());
+            if len >= path.len() {
+                let mut string = path.as_bytes().to_vec();
+                string.resize(len + 1, 0);
+                let addr = self.dynamic_entry(DynamicTag::StrTab).unwrap_or(Addr(0));
+                self.write_at(addr + Size(old as u64), &string)?;
+                (offset, strtab) = (old, None);
+            }
+        }
+
+        let runpath = DynamicEntry { d_tag: DynamicTag::RunPath, d_un: Addr(offset as u64) };
+        match slot {
+            Some(index) => entries[index] = runpath,
+            None => entries.insert(entries.len() - 1, runpath),
+        }
+        self.write_dynamic(entries, strtab)
     }
 
-    pub fn p_type(&self) -> SegmentType {
-        self.p_type
+    /// Adds a `DT_NEEDED` entry for the library `name`, after those already there, so it is
+    /// searched for symbols last. Libraries which are already needed are left alone. The name
+    /// and the entry are added to the tables the way `set_runpath` adds the path.
+    pub fn add_needed(&mut self, name: &str) -> Result<(), write::Error> {
+        if self.needed_libraries().is_ok_and(|needed| needed.iter().any(|lib| lib == name)) {
+            return Ok(());
+        }
+        let mut entries = self.dynamic_table().ok_or(write::Error::NoDynamicTable)?.to_vec();
+        let (offset, strtab) = self.dynamic_string_offset(name)?;
+        let index = entries
+            .iter()
+            .rposition(|entry| entry.d_tag == DynamicTag::Needed)
+            .map_or(0, |index| index + 1);
+        let needed = DynamicEntry { d_tag: DynamicTag::Needed, d_un: Addr(offset as u64) };
+        entries.insert(index, needed);
+        self.write_dynamic(entries, strtab)
     }
 
-    pub fn p_align(&self) -> Addr {
-        self.p_align
+    /// Removes the `DT_NEEDED` entries for the library `name`. The name stays in the dynamic
+    /// string table, which other entries may share.
+    pub fn remove_needed(&mut self, name: &str) -> Result<(), write::Error> {
+        let table = self.dynamic_string_table().map_err(|_| write::Error::NoDynamicStringTable)?;
+        let mut entries = self.dynamic_table().ok_or(write::Error::NoDynamicTable)?.to_vec();
+        entries.retain(|entry| {
+            entry.d_tag != DynamicTag::Needed
+                || table.get(entry.d_un.into()).map_or(true, |lib| lib != name)
+        });
+        self.write_dynamic(entries, None)
     }
 
-    pub fn p_addr(&self) -> Addr {
-        self.p_paddr
+    /// Returns the offset of `string` in the dynamic string table. Strings which are not there
+    /// yet are added to the end of a copy of the table, which is returned with the offset.
+    fn dynamic_string_offset(
+        &self,
+        string: &str,
+    ) -> Result<(usize, Option<Vec<u8>>), write::Error> {
+        let addr =
+            self.dynamic_entry(DynamicTag::StrTab).ok_or(write::Error::NoDynamicStringTable)?;
+        let size =
+            self.dynamic_entry(DynamicTag::StrSz).ok_or(write::Error::NoDynamicStringTable)?;
+        let strtab = self.bytes_at(addr, size.into()).map_err(|_| write::Error::NotInFile(addr))?;
+        let string = [string.as_bytes(), &[0]].concat();
+        match strtab.windows(string.len()).position(|s| s == string) {
+            Some(offset) => Ok((offset, None)),
+            None => Ok((strtab.len(), Some([strtab, &string].concat()))),
+        }
     }
-}
 
-impl fmt::Debug for ProgramHeader {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            " Type: {:?}\n Flags: {:?}\n Contents: {:?}\n",
-            self.p_type,
-            self.p_flags,
-            self.contents,
-        )
+    /// Removes the library search paths of the object, the `DT_RPATH` and `DT_RUNPATH` entries.
+    /// Their strings stay in the dynamic string table, unused.
+    pub fn remove_rpath(&mut self) -> Result<(), write::Error> {
+        let mut entries = self.dynamic_table().ok_or(write::Error::NoDynamicTable)?.to_vec();
+        entries.retain(|entry| !matches!(entry.d_tag, DynamicTag::RPath | DynamicTag::RunPath));
+        self.write_dynamic(entries, None)
     }
-}
 
-const ELF_MAGIC_SIZE: usize = 4;
-const ELF_MAGIC: &[u8] = &[0x7F, 0x45, 0x4C, 0x46];
+    /// Writes `entries` over the dynamic table, filling the rest of it with null entries, and
+    /// `strtab` over the dynamic string table, if given. Tables which do not fit where they are
+    /// move together to a new loadable segment.
+    fn write_dynamic(
+        &mut self,
+        mut entries: Vec<DynamicEntry>,
+        strtab: Option<Vec<u8>>,
+    ) -> Result<(), write::Error> {
+        let dynamic = self
+            .ph_table
+            .iter()
+            .position(|ph| ph.p_type == SegmentType::PtDynamic)
+            .ok_or(write::Error::NoDynamicTable)?;
+        let entry_size = 2 * self.elf_header.e_class.word_size();
+        let capacity = usize::from(self.ph_table[dynamic].p_filesz) / entry_size;
+        let moves = entries.len() > capacity;
 
-#[derive(Debug)]
-pub struct ElfHeader {
-    pub e_type: FileType,
-    pub e_machine: Machine,
-    /// Memory address of the entry point from where the process starts
-    /// executing
-    pub e_entry: Addr,
-    /// Points to the start of the program header table.
-    pub e_phoff: Addr,
-    /// Points to the start of the section header table.
-    pub e_shoff: Addr,
-    /// Contains the size of a program header table entry.
-    pub e_phentsize: u16,
-    /// Contains the number of entries in the program header table.
-    pub e_phnum: u16,
-    /// Contains the size of a section header table entry.
-    pub e_shentsize: u16,
-    /// Contains the number of entries in the section header table.
-    pub e_shnum: u16,
-    /// Contains index of the section header table entry that contains the section names.
-    pub e_shstrndx: u16,
-}
+        // Moved tables are laid out in a new segment, the string table first
+        let strtab_size = strtab.as_ref().map_or(0, |strtab| strtab.len().next_multiple_of(8));
+        let size = strtab_size + if moves { entries.len() * entry_size } else { 0 };
+        let at = if size > 0 {
+            self.append_load_segment(SegmentFlags::READ | SegmentFlags::WRITE, &vec![0; size])?
+        } else {
+            Addr(0)
+        };
 
-impl ElfHeader {
-    pub fn parse(reader: &mut Reader) -> Result<Self, ElfHeaderError> {
-        // Read the magic
-        let e_magic = reader.read_slice(ELF_MAGIC_SIZE)?;
-        // Check if we have an Elf files
-        if e_magic != ELF_MAGIC {
-            return Err(ElfHeaderError::BadMagic(format!("{:?}", e_magic)))
+        if let Some(strtab) = strtab {
+            let old = self.dynamic_entry(DynamicTag::StrTab).unwrap_or(Addr(0));
+            for entry in entries.iter_mut() {
+                match entry.d_tag {
+                    DynamicTag::StrTab => entry.d_un = at,
+                    DynamicTag::StrSz => entry.d_un = Addr(strtab.len() as u64),
+                    _ => {}
+                }
+            }
+            self.move_section(old, at, &strtab);
+            self.write_at(at, &strtab)?;
         }
 
-        // Read the class
-        let e_class = reader.read_u8()?;
-        // Check the class is 64-bit
-        if e_class != 2 {
-            return Err(ElfHeaderError::Not64Bit)
+        let mut writer = Writer::new(self.elf_header.e_class, self.elf_header.e_data);
+        for entry in entries.iter() {
+            entry.write(&mut writer)?;
         }
-
-        // Read the endianness
-        let e_data = reader.read_u8()?;
-        // Check that is little endian
-        if e_data != 1 {
-            return Err(ElfHeaderError::BadEndianness)
+        if moves {
+            let table = writer.into_bytes();
+            let addr = at + Size(strtab_size as u64);
+            let old = self.ph_table[dynamic].p_vaddr;
+            let offset = self.segment_at(addr).and_then(|load| load.addr_to_offset(addr));
+            let ph = &mut self.ph_table[dynamic];
+            (ph.p_offset, ph.p_vaddr, ph.p_paddr) = (offset.unwrap_or(FileOffset(0)), addr, addr);
+            (ph.p_filesz, ph.p_memsz) = (Size(table.len() as u64), Size(table.len() as u64));
+            ph.data = Cow::Owned(table.clone());
+            self.move_section(old, addr, &table);
+            self.write_at(addr, &table)
+        } else {
+            writer.write_zeros(capacity * entry_size - writer.position());
+            let addr = self.ph_table[dynamic].p_vaddr;
+            self.write_at(addr, &writer.into_bytes())
         }
+    }
 
-        // Read the version
-        let e_version = reader.read_u8()?;
-        // Should be 1 for the original and current version of Elf
-        if e_version != 1 {
-            return Err(ElfHeaderError::BadVersion)
+    /// Moves the allocated section at `old` to `addr`, with `data` as its contents. The
+    /// contents are written to the segment holding `addr` separately.
+    fn move_section(&mut self, old: Addr, addr: Addr, data: &[u8]) {
+        let offset = self.segment_at(addr).and_then(|ph| ph.addr_to_offset(addr));
+        let section = self.sh_table.iter_mut().find(|sh| {
+            sh.sh_flags().contains(SectionFlags::ALLOC) && sh.sh_addr() == old && sh.sh_size().0 > 0
+        });
+        if let (Some(sh), Some(offset)) = (section, offset) {
+            sh.set_sh_addr(addr);
+            sh.set_contents(offset, Cow::Owned(data.to_vec()));
         }
+    }
 
-        // Read the target operating system ABI
-        let e_osabi = reader.read_u8()?;
-        // Check the OS Abi is System V or Linux
-        if e_osabi != 0 && e_osabi != 3 {
-            return Err(ElfHeaderError::BadOsAbi)
+    /// Writes `bytes` at `addr`, in every segment and section holding a copy of the file there,
+    /// and decodes the dynamic table again
+    fn write_at(&mut self, addr: Addr, bytes: &[u8]) -> Result<(), write::Error> {
+        let last = addr + Size(bytes.len().saturating_sub(1) as u64);
+        let range = self
+            .segment_at(addr)
+            .and_then(|ph| Some(ph.addr_to_offset(addr)?.0..ph.addr_to_offset(last)?.0 + 1))
+            .ok_or(write::Error::NotInFile(addr))?;
+
+        // Copies the part of `bytes` which overlaps the file range of `data`, starting at `start`
+        let copy = |data: &mut Cow<[u8]>, start: u64| {
+            let end = start + data.len() as u64;
+            let (from, to) = (range.start.max(start), range.end.min(end));
+            if from < to {
+                let bytes = &bytes[(from - range.start) as usize..(to - range.start) as usize];
+                data.to_mut()[(from - start) as usize..(to - start) as usize]
+                    .copy_from_slice(bytes);
+            }
+        };
+        let (class, endianness) = (self.elf_header.e_class, self.elf_header.e_data);
+        for ph in self.ph_table.iter_mut() {
+            copy(&mut ph.data, ph.p_offset.0);
+            if matches!(ph.p_type, SegmentType::PtDynamic | SegmentType::PtInterp) {
+                let data = std::mem::take(&mut ph.data);
+                ph.set_data(data, class, endianness)?;
+            }
+        }
+        for sh in self.sh_table.iter_mut().filter(|sh| sh.file_range().is_some()) {
+            let start = sh.sh_offset().0;
+            copy(&mut sh.data, start);
         }
+        Ok(())
+    }
 
-        // Skip the remaining padding
-        let _ = reader.read_slice(8)?;
+    /// Appends a section called `name`, of `sh_type` and holding `data`, which is not loaded in
+    /// memory, and returns its index. The section, the section name string table grown with
+    /// `name` and the section header table all move to the end of the file, as none of them
+    /// can grow where they are. Files without sections get the null section and a name table.
+    pub fn add_section(
+        &mut self,
+        name: &str,
+        sh_type: SectionType,
+        data: &[u8],
+    ) -> Result<usize, write::Error> {
+        let end = self.file_size();
+        let class = self.elf_header.e_class;
+        let extended = self.elf_header.e_shnum == 0 && !self.sh_table.is_empty();
+        if self.sh_table.is_empty() {
+            self.elf_header.e_shentsize = SectionHeader::size(class) as u16;
+            let null =
+                SectionHeader::new(0, SectionType::ShtNull, FileOffset(0), Cow::Borrowed(&[]));
+            self.sh_table.push(null);
+        }
 
-        // Read the object file_type
-        let e_type: FileType = reader.read_u16()?.try_into()?;
+        let mut shstrndx = self.shstrndx();
+        let has_names = shstrndx != 0
+            && self.sh_table.get(shstrndx).is_some_and(|sh| sh.sh_type() == SectionType::ShtStrtab);
+        let mut names = if has_names {
+            self.sh_table[shstrndx].data.to_vec()
+        } else {
+            shstrndx = self.sh_table.len();
+            if shstrndx >= usize::from(sym::SHN_LORESERVE) {
+                return Err(write::Error::TooManySections(shstrndx));
+            }
+            let mut shstrtab =
+                SectionHeader::new(1, SectionType::ShtStrtab, FileOffset(0), Cow::Borrowed(&[]));
+            shstrtab.name = Some(".shstrtab".into());
+            self.sh_table.push(shstrtab);
+            self.elf_header.e_shstrndx = shstrndx as u16;
+            b"\0.shstrtab\0".to_vec()
+        };
+        let sh_name = names.len() as u32;
+        names.extend_from_slice(name.as_bytes());
+        names.push(0);
 
-        // Read the object machine
-        let e_machine: Machine = reader.read_u16()?.try_into()?;
+        let data_offset = end + names.len() as u64;
+        let shoff = data_offset + data.len() as u64;
+        self.sh_table[shstrndx].set_contents(FileOffset(end), Cow::Owned(names));
+        let data = Cow::Owned(data.to_vec());
+        let mut sh = SectionHeader::new(sh_name, sh_type, FileOffset(data_offset), data);
+        sh.name = Some(name.into());
+        self.sh_table.push(sh);
 
-        // Read yet another version
-        let e_version = reader.read_u32()?;
+        self.elf_header.e_shoff = FileOffset(shoff.next_multiple_of(class.word_size() as u64));
+        let shnum = self.sh_table.len();
+        if extended || shnum >= usize::from(sym::SHN_LORESERVE) {
+            self.elf_header.e_shnum = 0;
+            self.sh_table[0].set_sh_size(Size(shnum as u64));
+        } else {
+            self.elf_header.e_shnum = shnum as u16;
+        }
+        Ok(shnum - 1)
+    }
 
-        // Check if version has the only value possible
+    /// Removes the symbol table, its string table and the debug sections, along with the
+    /// relocations that apply to them, like `strip` does. The section header table is rewritten
+    /// without them, links between sections and the section indexes of the dynamic symbols
+    /// follow the sections which moved, and the sections outside of segments are packed again
+    /// after the segments, so the file gets smaller. Bytes no header, segment or section claims,
+    /// like data appended to the file, go as well.
+    pub fn strip(&mut self, options: StripOptions) -> Result<(), write::Error> {
+        if self.elf_header.e_type == FileType::EtRel {
+            return Err(write::Error::StripRelocatable);
+        }
+        if self.sh_table.is_empty() {
+            return Ok(());
+        }
+        let shstrndx = self.shstrndx();
+        let mut removed: Vec<bool> = self
+            .sh_table
+            .iter()
+            .map(|sh| {
+                let name = sh.name().unwrap_or_default();
+                let dynamic = matches!(
+                    sh.sh_type(),
+                    SectionType::ShtDynsym
+                        | SectionType::ShtHash
+                        | SectionType::ShtGnuHash
+                        | SectionType::ShtGnuVersym
+                        | SectionType::ShtGnuVerdef
+                        | SectionType::ShtGnuVerneed
+                );
+                // Debug sections loaded at run time, like `.debug_gdb_scripts`, are kept
+                let debug = (name.starts_with(".debug") || name.starts_with(".zdebug"))
+                    && !sh.sh_flags().contains(SectionFlags::ALLOC);
+                matches!(sh.sh_type(), SectionType::ShtSymtab | SectionType::ShtSymtabShndx)
+                    || debug
+                    || (dynamic && !options.keep_dynamic_symbols)
+            })
+            .collect();
+        // String tables of removed symbol tables and relocations for removed sections go too
+        loop {
+            let mut changed = false;
+            for (index, sh) in self.sh_table.iter().enumerate() {
+                let link = sh.sh_link() as usize;
+                let (symbols, relocations) = match sh.sh_type() {
+                    SectionType::ShtSymtab | SectionType::ShtDynsym => (removed[index], false),
+                    SectionType::ShtRel | SectionType::ShtRela => (false, !removed[index]),
+                    _ => (false, false),
+                };
+                if symbols && link != shstrndx && removed.get(link) == Some(&false) {
+                    removed[link] = true;
+                    changed = true;
+                }
+                let target = sh.sh_info() as usize;
+                if relocations && target != 0 && removed.get(target) == Some(&true) {
+                    removed[index] = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        // String tables which kept sections still use stay, like `.dynstr` for `.dynamic`
+        for (index, sh) in self.sh_table.iter().enumerate() {
+            let link = sh.sh_link() as usize;
+            let strtab = self.sh_table.get(link).map(|sh| sh.sh_type());
+            if !removed[index] && strtab == Some(SectionType::ShtStrtab) {
+                removed[link] = false;
+            }
+        }
+        removed[0] = false;
+        if let Some(shstrtab) = removed.get_mut(shstrndx) {
+            *shstrtab = false;
+        }
+
+        let mut new_index = vec![None; removed.len()];
+        let mut count = 0;
+        for (index, removed) in removed.iter().enumerate() {
+            if !removed {
+                new_index[index] = Some(count as u32);
+                count += 1;
+            }
+        }
+        let remap = |index: u32| new_index.get(index as usize).copied().flatten().unwrap_or(0);
+
+        // The dynamic symbols name sections by index. Their contents stay where they are, so
+        // they are fixed in place.
+        if let (Ok(symbols), Ok((symtab, syment))) =
+            (self.dynamic_symbols(), self.dynamic_symbol_table())
+        {
+            let shndx_offset = match self.elf_header.e_class {
+                Class::Elf32 => 14,
+                Class::Elf64 => 6,
+            };
+            for (index, symbol) in symbols.iter().enumerate() {
+                let shndx = symbol.st_shndx();
+                if shndx == 0 || shndx >= sym::SHN_LORESERVE || remap(shndx.into()) == 0 {
+                    continue;
+                }
+                let mut writer = Writer::new(self.elf_header.e_class, self.elf_header.e_data);
+                writer.write_u16(remap(shndx.into()) as u16);
+                let addr = symtab + Size((index * syment + shndx_offset) as u64);
+                self.write_at(addr, &writer.into_bytes())?;
+            }
+        }
+
+        let sh_table = std::mem::take(&mut self.sh_table);
+        self.sh_table = sh_table
+            .into_iter()
+            .zip(removed)
+            .filter_map(|(sh, removed)| (!removed).then_some(sh))
+            .collect();
+        for sh in self.sh_table.iter_mut().skip(1) {
+            sh.set_sh_link(remap(sh.sh_link()));
+            let info_link = sh.sh_flags().contains(SectionFlags::INFO_LINK)
+                || matches!(sh.sh_type(), SectionType::ShtRel | SectionType::ShtRela);
+            if info_link {
+                sh.set_sh_info(remap(sh.sh_info()));
+            }
+        }
+        let shstrndx = remap(shstrndx as u32);
+        let (extended_shnum, extended_shstrndx) = (
+            count >= usize::from(sym::SHN_LORESERVE),
+            shstrndx >= u32::from(sym::SHN_LORESERVE),
+        );
+        self.elf_header.e_shnum = if extended_shnum { 0 } else { count as u16 };
+        self.elf_header.e_shstrndx = if extended_shstrndx { SHN_XINDEX } else { shstrndx as u16 };
+        if let Some(null) = self.sh_table.first_mut() {
+            null.set_sh_size(Size(if extended_shnum { count as u64 } else { 0 }));
+            null.set_sh_link(if extended_shstrndx { shstrndx } else { 0 });
+        }
+
+        // Sections outside of segments are packed after them, with a name table holding only
+        // the names which are left, and nothing else follows them
+        self.unclaimed.clear();
+        let mut names = vec![0];
+        for sh in self.sh_table.iter_mut().skip(1) {
+            sh.set_sh_name(names.len() as u32);
+            names.extend_from_slice(sh.name().unwrap_or_default().as_bytes());
+            names.push(0);
+        }
+        let header = &self.elf_header;
+        let phentsize = u64::from(header.e_phentsize);
+        let phdrs = header.e_phoff.0.saturating_add(phentsize * self.ph_table.len() as u64);
+        let segments = self.ph_table.iter().map(|ph| ph.file_range());
+        let mut offset = segments.clone().map(|range| range.end.0).fold(phdrs, u64::max);
+        offset = offset.max(header.e_ehsize.into());
+        for (index, sh) in self.sh_table.iter_mut().enumerate() {
+            let Some(range) = sh.file_range() else { continue };
+            let (start, end) = (range.start as u64, range.end as u64);
+            let inside = |segment: &Range<FileOffset>| {
+                segment.start.0 <= start && end <= segment.end.0 && segment.start < segment.end
+            };
+            if index != shstrndx as usize && segments.clone().any(|segment| inside(&segment)) {
+                continue;
+            }
+            offset = offset.next_multiple_of(sh.sh_addralign().max(1));
+            let data = if index == shstrndx as usize {
+                Cow::Owned(std::mem::take(&mut names))
+            } else {
+                std::mem::take(&mut sh.data)
+            };
+            let len = data.len() as u64;
+            sh.set_contents(FileOffset(offset), data);
+            offset += len;
+        }
+        let word_size = self.elf_header.e_class.word_size() as u64;
+        self.elf_header.e_shoff = FileOffset(offset.next_multiple_of(word_size));
+        Ok(())
+    }
+
+    /// Maps the whole file, `file_size` bytes long, to what claims each range of it: the headers,
+    /// the header tables, the contents of every segment and section, and the gaps nothing claims.
+    /// Entries are sorted by offset and overlap, as segments hold sections.
+    pub fn file_map(&self, file_size: u64) -> Vec<FileMapEntry> {
+        let header = &self.elf_header;
+        let table = |start: FileOffset, entsize: u16, count: usize| {
+            start..start + Size(u64::from(entsize).saturating_mul(count as u64))
+        };
+        let mut claimed = vec![
+            FileMapEntry {
+                range: FileOffset(0)..FileOffset(header.e_ehsize.into()),
+                region: FileRegion::ElfHeader,
+            },
+            FileMapEntry {
+                range: table(header.e_phoff, header.e_phentsize, self.ph_table.len()),
+                region: FileRegion::ProgramHeaderTable,
+            },
+            FileMapEntry {
+                range: table(header.e_shoff, header.e_shentsize, self.sh_table.len()),
+                region: FileRegion::SectionHeaderTable,
+            },
+        ];
+        let segments = self.ph_table.iter().enumerate().map(|(index, ph)| FileMapEntry {
+            range: ph.file_range(),
+            region: FileRegion::Segment(index),
+        });
+        let sections = self.sh_table.iter().enumerate().filter_map(|(index, sh)| {
+            let range = sh.file_range()?;
+            Some(FileMapEntry {
+                range: FileOffset(range.start as u64)..FileOffset(range.end as u64),
+                region: FileRegion::Section(index),
+            })
+        });
+        claimed.extend(segments.chain(sections));
+        file_map::with_gaps(claimed, file_size)
+    }
+
+    /// Returns the padding of at least `min_size` bytes of the executable loadable segments: the
+    /// parts of their file contents which neither a section nor a header table claims, and the
+    /// unclaimed bytes following them up to the end of their last 4KiB page, which is loaded
+    /// whole. Files without section headers cannot tell padding apart, so they have no caves.
+    pub fn find_caves(&self, min_size: u64) -> Vec<CodeCave> {
+        if self.sh_table.is_empty() {
+            return vec![];
+        }
+        let file_size = self.file_size();
+        let map = self.file_map(file_size);
+        let executable = self.ph_table.iter().enumerate().filter(|(_, ph)| {
+            ph.p_type == SegmentType::PtLoad && ph.p_flags.contains(SegmentFlags::EXEC)
+        });
+        let mut caves = vec![];
+        for (segment, ph) in executable {
+            let mut range = ph.file_range();
+            // Past the file contents of the last page, memory is zeroed instead of loaded
+            if ph.p_memsz == ph.p_filesz {
+                range.end = FileOffset(range.end.0.next_multiple_of(0x1000).min(file_size));
+            }
+            for file_range in file_map::unclaimed(&map, range, segment) {
+                if file_range.end.0 - file_range.start.0 < min_size {
+                    continue;
+                }
+                let start = ph.p_vaddr + Size(file_range.start.0 - ph.p_offset.0);
+                let mem_range = start..start + Size(file_range.end.0 - file_range.start.0);
+                caves.push(CodeCave { segment, file_range, mem_range, flags: ph.p_flags });
+            }
+        }
+        caves
+    }
+
+    /// Tells whether the file, whose bytes are `bytes`, is packed, by UPX or another packer known
+    /// by its signature, or going by the anomalies packers leave: no section headers, room to
+    /// unpack into in the last loadable segment, writable code, or code as random as compressed
+    /// data
+    pub fn detect_packer(&self, bytes: &[u8]) -> PackerInfo {
+        packer::detect(self, bytes)
+    }
+
+    /// Returns the size of the file, up to the end of the last thing any header points to
+    fn file_size(&self) -> u64 {
+        let header = &self.elf_header;
+        let phentsize = u64::from(header.e_phentsize);
+        let shentsize = u64::from(header.e_shentsize);
+        let tables = [
+            u64::from(header.e_ehsize),
+            header.e_phoff.0.saturating_add(phentsize * self.ph_table.len() as u64),
+            header.e_shoff.0.saturating_add(shentsize * self.sh_table.len() as u64),
+        ];
+        let segments = self.ph_table.iter().map(|ph| ph.file_range().end.0);
+        let sections = self.sh_table.iter().filter_map(|sh| sh.file_range()).map(|r| r.end as u64);
+        let unclaimed = self.unclaimed.iter().map(|(offset, bytes)| offset.0 + bytes.len() as u64);
+        let ends = tables.into_iter().chain(segments).chain(sections).chain(unclaimed);
+        ends.max().unwrap_or(0)
+    }
+
+    /// Returns the `ProgramHeader` of the segment that contains the `addr`
+    pub fn segment_at(&self, addr: Addr) -> Option<&ProgramHeader<'a>> {
+        self.ph_table
+            .iter()
+            .filter(|ph| ph.p_type == SegmentType::PtLoad)
+            .find(|ph| ph.mem_range().contains(&addr))
+    }
+
+    /// Returns a slice from the the Load segment containing `mem_addr` address.
+    /// The slice spans from `mem_addr` until the end of the segment's file contents, so addresses
+    /// in the zeroed tail past them, or in contents which were not loaded, give `None`.
+    pub fn slice_at(&self, mem_addr: Addr) -> Option<&[u8]> {
+        let seg = self.segment_at(mem_addr)?;
+        seg.data.get(usize::from(mem_addr - seg.mem_range().start)..)
+    }
+
+    /// Returns the dynamic string table, `.dynstr`, located through the dynamic table
+    pub fn dynamic_string_table(&self) -> Result<StringTable<'_>, StringError> {
+        let addr = self.dynamic_entry(DynamicTag::StrTab).ok_or(StringError::StrTabNotFound)?;
+        let size = self.dynamic_entry(DynamicTag::StrSz).ok_or(StringError::StrTabNotFound)?;
+        let bytes = self.bytes_at(addr, size.into()).map_err(|err| match err {
+            SegmentError::NotLoaded(vaddr) => StringError::NotLoaded(vaddr),
+            _ => StringError::StrTabSegmentNotFound,
+        })?;
+        Ok(StringTable::new(bytes))
+    }
+
+    /// Returns a string from the dynamic string table located at `offset`.
+    pub fn get_string(&self, offset: Addr) -> Result<String, StringError> {
+        Ok(self.dynamic_string_table()?.get(offset.into())?.into())
+    }
+
+    /// Returns the first segment of type `p_type`.
+    pub fn segment_of_type(&self, p_type: SegmentType) -> Option<&ProgramHeader<'a>> {
+        self.ph_table
+            .iter()
+            .find(|ph| ph.p_type() == p_type)
+    }
+
+    /// Returns the path of the program interpreter, the dynamic loader named by `PT_INTERP`.
+    /// Files without the segment, like static executables and libraries, have none.
+    pub fn interpreter(&self) -> Option<Result<String, ParseError>> {
+        let interp = self.segment_of_type(SegmentType::PtInterp)?;
+        if let SegmentContents::Interp(path) = &interp.contents {
+            return Some(Ok(path.clone()));
+        }
+        let path = Reader::from_bytes(&interp.data).read_cstr();
+        Some(path.map(|path| String::from_utf8_lossy(path).into()))
+    }
+
+    /// Returns the permissions of the stack the `PtGnuStack` segment asks for. Without one, the
+    /// stack of most machines is executable.
+    pub fn gnu_stack(&self) -> Option<SegmentFlags> {
+        self.segment_of_type(SegmentType::PtGnuStack).map(|ph| ph.p_flags)
+    }
+
+    /// Tells how much RELRO protects: not at all without `PtGnuRelro`, fully when binding is not
+    /// lazy, through `DT_BIND_NOW`, `DF_BIND_NOW` or `DF_1_NOW`, and partially otherwise
+    pub fn relro_level(&self) -> RelroLevel {
+        if self.segment_of_type(SegmentType::PtGnuRelro).is_none() {
+            return RelroLevel::None;
+        }
+        let bind_now = self.dynamic_entry(DynamicTag::BindNow).is_some()
+            || self.dynamic_flags().contains(DynamicFlags::BIND_NOW)
+            || self.dynamic_flags_1().contains(DynamicFlags1::NOW);
+        match bind_now {
+            true => RelroLevel::Full,
+            false => RelroLevel::Partial,
+        }
+    }
+
+    /// Returns whether the program runs with an executable stack. Without a `PtGnuStack` segment
+    /// the kernel of most machines makes it executable, so files with program headers but none
+    /// of those count as having one.
+    pub fn has_executable_stack(&self) -> bool {
+        !self.ph_table.is_empty()
+            && self.gnu_stack().is_none_or(|flags| flags.contains(SegmentFlags::EXEC))
+    }
+
+    /// Returns the segments which are both writable and executable, breaking W^X, for reporting
+    pub fn wx_segments(&self) -> Vec<&ProgramHeader<'a>> {
+        let wx = SegmentFlags::WRITE | SegmentFlags::EXEC;
+        self.ph_table.iter().filter(|ph| ph.p_flags.contains(wx)).collect()
+    }
+
+    /// Checks the layout of the segments instead of trusting it, the way malformed or evasive
+    /// files break it: loadable segments must not overlap in memory nor be misaligned, and no
+    /// segment may reach past the end of the file, `file_size` bytes long.
+    pub fn validate_segments(&self, file_size: u64) -> Vec<SegmentDiagnostic> {
+        let mut diagnostics = vec![];
+        let is_load = |ph: &&ProgramHeader| ph.p_type == SegmentType::PtLoad;
+        for (index, ph) in self.ph_table.iter().enumerate() {
+            let mut report = |issue| diagnostics.push(SegmentDiagnostic { index, issue });
+
+            let end = ph.file_range().end;
+            if ph.p_filesz.0 != 0 && end.0 > file_size {
+                report(SegmentIssue::PastEndOfFile { end });
+            }
+            if !is_load(&ph) {
+                continue;
+            }
+            let align = ph.p_align;
+            if align > 1 && ph.p_vaddr.0 % align != ph.p_offset.0 % align {
+                let (vaddr, offset) = (ph.p_vaddr, ph.p_offset);
+                report(SegmentIssue::Misaligned { vaddr, offset, align });
+            }
+            let range = ph.mem_range();
+            let overlapping = self.ph_table[..index].iter().enumerate().filter(|(_, other)| {
+                let other_range = other.mem_range();
+                is_load(other)
+                    && range.start.0 < other_range.end.0
+                    && other_range.start.0 < range.end.0
+            });
+            for (other, _) in overlapping {
+                report(SegmentIssue::OverlappingLoad { other });
+            }
+        }
+        diagnostics
+    }
+
+    /// Returns the memory range the `PtGnuRelro` segment makes read-only after relocation
+    pub fn gnu_relro_range(&self) -> Option<Range<Addr>> {
+        self.segment_of_type(SegmentType::PtGnuRelro).map(|ph| ph.mem_range())
+    }
+
+    /// Returns the template thread local storage blocks are initialized from, described by the
+    /// `PtTls` segment
+    pub fn tls_template(&self) -> Option<TlsTemplate<'_>> {
+        let tls = self.segment_of_type(SegmentType::PtTls)?;
+        Some(TlsTemplate {
+            image: &tls.data,
+            vaddr: tls.p_vaddr,
+            file_size: tls.p_filesz.0,
+            mem_size: tls.p_memsz.0,
+            align: tls.p_align.max(1),
+        })
+    }
+
+    /// Return an entry from the Dynamic table with the given `tag` or None if `tag` does not exist
+    /// in the table
+    pub fn dynamic_entry(&self, tag: DynamicTag) -> Option<Addr> {
+        self.dynamic_entries(tag).next()
+    }
+
+    /// Returns the entries of the dynamic table, from the `PT_DYNAMIC` segment or else from the
+    /// `.dynamic` section
+    pub fn dynamic_table(&self) -> Option<&[DynamicEntry]> {
+        match self.segment_of_type(SegmentType::PtDynamic) {
+            Some(ProgramHeader {
+                contents: SegmentContents::Dynamic(table),
+                ..
+            }) => Some(table.entries()),
+            _ => self.dynamic_section.as_ref().map(|table| &table.entries()[..]),
+        }
+    }
+
+    /// Returns an `Interator` over addresses contained in the entries of the dynamic table which
+    /// have `tag`
+    pub fn dynamic_entries(&self, tag: DynamicTag) -> impl Iterator<Item = Addr> + '_{
+        self.dynamic_table()
+            .unwrap_or_default()
+            .iter()
+            .filter(move |e| e.d_tag == tag)
+            .map(|e| e.d_un)
+    }
+
+    /// Returns the flags of the `DT_FLAGS` entry, empty when there is none. Unknown flags are
+    /// dropped.
+    pub fn dynamic_flags(&self) -> DynamicFlags {
+        let flags = self.dynamic_entry(DynamicTag::Flags).unwrap_or(Addr(0));
+        DynamicFlags::from_bits_truncate(flags.0)
+    }
+
+    /// Returns the flags of the `DT_FLAGS_1` entry, empty when there is none. Unknown flags are
+    /// dropped.
+    pub fn dynamic_flags_1(&self) -> DynamicFlags1 {
+        let flags = self.dynamic_entry(DynamicTag::Flags1).unwrap_or(Addr(0));
+        DynamicFlags1::from_bits_truncate(flags.0)
+    }
+
+    /// Returns what the object is for. Executables linked with `-pie` are shared objects, told
+    /// apart from libraries by the `DF_1_PIE` flag or, for older linkers which do not set it, by
+    /// asking for an interpreter without naming themselves with a `DT_SONAME`, which runnable
+    /// libraries like `libc.so.6` do.
+    pub fn object_kind(&self) -> ObjectKind {
+        let interpreted = self.segment_of_type(SegmentType::PtInterp).is_some();
+        let pie_flag = self.dynamic_flags_1().contains(DynamicFlags1::PIE);
+        let named = self.dynamic_entry(DynamicTag::SoName).is_some();
+        match self.elf_header.e_type {
+            FileType::EtRel => ObjectKind::Relocatable,
+            FileType::EtExec if interpreted || self.dynamic_table().is_some() => {
+                ObjectKind::DynamicExecutable
+            }
+            FileType::EtExec => ObjectKind::StaticExecutable,
+            FileType::EtDyn if interpreted && (pie_flag || !named) => ObjectKind::Pie,
+            FileType::EtDyn if pie_flag => ObjectKind::StaticPie,
+            FileType::EtDyn => ObjectKind::SharedLibrary,
+            FileType::EtCore => ObjectKind::Core,
+            _ => ObjectKind::Other,
+        }
+    }
+
+    /// Returns whether the object is a position independent executable
+    pub fn is_pie(&self) -> bool {
+        matches!(self.object_kind(), ObjectKind::Pie | ObjectKind::StaticPie)
+    }
+
+    /// Returns the entries of the dynamic table with their values interpreted according to their
+    /// tags, resolving string offsets through the dynamic string table
+    pub fn dynamic_values(&self) -> Result<Vec<(DynamicTag, DynamicValue)>, StringError> {
+        let Some(table) = self.dynamic_table() else {
+            return Ok(vec![]);
+        };
+        // Only entries holding strings need the string table
+        let strtab = self.dynamic_string_table().unwrap_or(StringTable::new(&[]));
+        table
+            .iter()
+            .map(|entry| Ok((entry.d_tag, DynamicValue::new(entry.d_tag, entry.d_un, &strtab)?)))
+            .collect()
+    }
+
+    /// Returns the names of the libraries the object needs, from the `DT_NEEDED` entries, in the
+    /// order the loader searches them
+    pub fn needed_libraries(&self) -> Result<Vec<String>, StringError> {
+        self.dynamic_entries(DynamicTag::Needed)
+            .map(|offset| self.get_string(offset))
+            .collect()
+    }
+
+    /// Returns the name the object is known by to the objects linked against it, from the
+    /// `DT_SONAME` entry
+    pub fn soname(&self) -> Result<Option<String>, StringError> {
+        self.dynamic_entry(DynamicTag::SoName)
+            .map(|offset| self.get_string(offset))
+            .transpose()
+    }
+
+    pub fn dynamic_entry_strings(&self, tag: DynamicTag) -> impl Iterator<Item = String> + '_ {
+        self.dynamic_entries(tag)
+            .filter_map(move |addr| self.get_string(addr).ok())
+    }
+
+    /// Reads and returns the vector of `Rela` entries from the file
+    pub fn read_rela_entries(&self) -> Result<Vec<Rela>, SegmentError> {
+        self.rela_table(DynamicTag::RelA, DynamicTag::RelASz)
+    }
+
+    /// Reads and returns the `Rela` entries of the procedure linkage table, which `DT_JMPREL`
+    /// points to
+    pub fn read_plt_rela_entries(&self) -> Result<Vec<Rela>, SegmentError> {
+        self.rela_table(DynamicTag::JmpRel, DynamicTag::PltRelSz)
+    }
+
+    /// Reads the relocations of the procedure linkage table, `DT_JMPREL`, as `Rel` or `Rela`
+    /// entries depending on `DT_PLTREL`. Files without the table, like static executables, have
+    /// no entries.
+    pub fn read_plt_relocations(&self) -> Result<PltRelocations, SegmentError> {
+        if self.dynamic_entry(DynamicTag::JmpRel).is_none() {
+            return Ok(PltRelocations::Rela(vec![]));
+        }
+        let pltrel = self
+            .dynamic_entry(DynamicTag::PltRel)
+            .ok_or(DynamicError::TagNotFound(DynamicTag::PltRel))?;
+        match DynamicTag::try_from(pltrel.0) {
+            Ok(DynamicTag::RelA) => Ok(PltRelocations::Rela(self.read_plt_rela_entries()?)),
+            Ok(DynamicTag::Rel) => Ok(PltRelocations::Rel(self.read_plt_rel_entries()?)),
+            _ => Err(DynamicError::BadPltRel(pltrel.0).into()),
+        }
+    }
+
+    /// Returns the relocations of all the dynamic relocation tables, `DT_RELA`, `DT_REL`,
+    /// `DT_RELR` and `DT_JMPREL`, in this order. Missing tables are skipped.
+    pub fn relocations(&self) -> Result<impl Iterator<Item = Relocation>, SegmentError> {
+        let mut relocations = vec![];
+        if self.dynamic_entry(DynamicTag::RelA).is_some() {
+            let relas = self.read_rela_entries()?;
+            relocations.extend(
+                relas.iter().map(|rela| Relocation::from_rela(rela, RelocationTable::Rela)),
+            );
+        }
+        if self.dynamic_entry(DynamicTag::Rel).is_some() {
+            let rels = self.read_rel_entries()?;
+            relocations.extend(
+                rels.iter().map(|rel| Relocation::from_rel(rel, RelocationTable::Rel)),
+            );
+        }
+        // Machines without a relocation table here have no relative type to give them
+        let relative = RelType::relative(self.elf_header.e_machine).unwrap_or(RelType::Relr);
+        relocations.extend(self.relr_offsets()?.into_iter().map(|r_offset| Relocation {
+            r_offset,
+            r_type: relative,
+            r_sym: 0,
+            r_addend: None,
+            table: RelocationTable::Relr,
+        }));
+        match self.read_plt_relocations()? {
+            PltRelocations::Rela(relas) => relocations.extend(
+                relas.iter().map(|rela| Relocation::from_rela(rela, RelocationTable::JmpRel)),
+            ),
+            PltRelocations::Rel(rels) => relocations.extend(
+                rels.iter().map(|rel| Relocation::from_rel(rel, RelocationTable::JmpRel)),
+            ),
+        }
+        Ok(relocations.into_iter())
+    }
+
+    /// Returns the number of relative relocations at the start of the `DT_RELA` table, from
+    /// `DT_RELACOUNT`, or else of the `DT_REL` table, from `DT_RELCOUNT`
+    pub fn relative_count(&self) -> Option<usize> {
+        self.dynamic_entry(DynamicTag::RelaCount)
+            .or_else(|| self.dynamic_entry(DynamicTag::RelCount))
+            .map(Into::into)
+    }
+
+    /// Returns the relative relocations of the dynamic relocation tables, `DT_RELR` ones last.
+    /// When `DT_RELACOUNT` or `DT_RELCOUNT` gives their number, only the offsets and addends of
+    /// the leading entries are read, without decoding their types.
+    pub fn relative_relocations(
+        &self,
+    ) -> Result<impl Iterator<Item = RelativeRelocation>, SegmentError> {
+        let class = self.elf_header.e_class();
+        let counted = [
+            (DynamicTag::RelaCount, (DynamicTag::RelA, DynamicTag::RelASz, DynamicTag::RelAEnt)),
+            (DynamicTag::RelCount, (DynamicTag::Rel, DynamicTag::RelSz, DynamicTag::RelEnt)),
+        ];
+        let mut relatives = vec![];
+        if let Some((count, (tag, size_tag, ent_tag))) = counted
+            .into_iter()
+            .find_map(|(count_tag, tags)| Some((self.dynamic_entry(count_tag)?, tags)))
+        {
+            let is_rela = tag == DynamicTag::RelA;
+            let addr = self.dynamic_entry(tag).ok_or(DynamicError::TagNotFound(tag))?;
+            let len: usize =
+                self.dynamic_entry(size_tag).ok_or(DynamicError::TagNotFound(size_tag))?.into();
+            let min_size = if is_rela { Rela::size(class) } else { Rel::size(class) };
+            let entsize = self.dynamic_entry(ent_tag).map_or(min_size, Into::into).max(min_size);
+            // The count cannot reach past the table it describes
+            let count = usize::from(count).min(len / entsize);
+
+            let mut reader = self.reader_at(addr, count * entsize)?;
+            for index in 0..count {
+                reader.seek(index * entsize)?;
+                let r_offset = Addr::parse(&mut reader)?;
+                // `r_info` is known to hold the relative type, without a symbol
+                let _r_info = reader.read_word()?;
+                let r_addend = if is_rela { Some(reader.read_sword()?) } else { None };
+                relatives.push(RelativeRelocation { r_offset, r_addend });
+            }
+        } else {
+            let relocations = self
+                .relocations()?
+                .filter(|rel| rel.table != RelocationTable::Relr && rel.r_type.is_relative());
+            relatives.extend(relocations.map(|Relocation { r_offset, r_addend, .. }| {
+                RelativeRelocation { r_offset, r_addend }
+            }));
+        }
+        relatives.extend(
+            self.relr_offsets()?
+                .into_iter()
+                .map(|r_offset| RelativeRelocation { r_offset, r_addend: None }),
+        );
+        Ok(relatives.into_iter())
+    }
+
+    /// Returns the relocations of `relocations`, each with the dynamic symbol `r_sym` refers to
+    /// resolved, name included
+    pub fn named_relocations(&self) -> Result<Vec<NamedRelocation>, SegmentError> {
+        let relocations = self.relocations()?.collect::<Vec<_>>();
+        // Objects relocated only relative to their base may have no dynamic symbols at all
+        if relocations.iter().all(|relocation| relocation.r_sym == 0) {
+            let unnamed = |relocation| NamedRelocation { relocation, symbol: None };
+            return Ok(relocations.into_iter().map(unnamed).collect());
+        }
+        let symbols = self.dynamic_symbols()?;
+        let strtab = self.dynamic_string_table()?;
+        relocations
+            .into_iter()
+            .map(|relocation| {
+                if relocation.r_sym == 0 {
+                    return Ok(NamedRelocation { relocation, symbol: None });
+                }
+                let entry = symbols
+                    .get(relocation.r_sym as usize)
+                    .ok_or(SymbolError::BadSymbolIndex(relocation.r_sym))?
+                    .clone();
+                let name = strtab.get(entry.st_name() as usize)?.into_owned();
+                let symbol = Symbol { name, shndx: entry.st_shndx().into(), entry };
+                Ok(NamedRelocation { relocation, symbol: Some(symbol) })
+            })
+            .collect()
+    }
+
+    /// Returns the relocations of every `SHT_RELA` and `SHT_REL` section, with the symbols they
+    /// refer to resolved through the symbol table linked to the section. This is how relocatable
+    /// objects and kernel modules store their relocations, which have no dynamic table.
+    pub fn relocation_sections(&self) -> Result<Vec<RelocationSection>, SegmentError> {
+        let class = self.elf_header.e_class();
+        let machine = self.elf_header.e_machine;
+        // Relocation sections usually all share the same symbol table
+        let mut symbol_tables: Vec<(u32, Vec<Symbol>)> = vec![];
+        let mut sections = vec![];
+        for (index, sh) in self.sh_table.iter().enumerate() {
+            let (is_rela, min_size) = match sh.sh_type() {
+                SectionType::ShtRela => (true, Rela::size(class)),
+                SectionType::ShtRel => (false, Rel::size(class)),
+                _ => continue,
+            };
+            let table = RelocationTable::Section(index as u32);
+            let entsize = (sh.sh_entsize() as usize).max(min_size);
+            let mut reader = self.elf_header.reader(&sh.data);
+            let relocations = (0..sh.data.len() / entsize)
+                .map(|entry| {
+                    reader.seek(entry * entsize)?;
+                    Ok(if is_rela {
+                        Relocation::from_rela(&Rela::parse(&mut reader, machine)?, table)
+                    } else {
+                        Relocation::from_rel(&Rel::parse(&mut reader, machine)?, table)
+                    })
+                })
+                .collect::<Result<Vec<_>, SegmentError>>()?;
+
+            let symtab = sh.sh_link();
+            if symtab != 0 && !symbol_tables.iter().any(|(cached, _)| *cached == symtab) {
+                symbol_tables.push((symtab, self.symbol_table(symtab as usize)?));
+            }
+            let symbols = symbol_tables
+                .iter()
+                .find(|(cached, _)| *cached == symtab)
+                .map_or(&[][..], |(_, symbols)| &symbols[..]);
+            let relocations = relocations
+                .into_iter()
+                .map(|relocation| {
+                    let symbol = match relocation.r_sym {
+                        0 => None,
+                        r_sym => Some(
+                            symbols
+                                .get(r_sym as usize)
+                                .ok_or(SymbolError::BadSymbolIndex(r_sym))?
+                                .clone(),
+                        ),
+                    };
+                    Ok(NamedRelocation { relocation, symbol })
+                })
+                .collect::<Result<Vec<_>, SymbolError>>()?;
+
+            sections.push(RelocationSection { index, symtab, target: sh.sh_info(), relocations });
+        }
+        Ok(sections)
+    }
+
+    /// Reads the `Rela` table whose address and total length, in bytes, are given by the dynamic
+    /// entries `tag` and `size_tag`
+    fn rela_table(&self, tag: DynamicTag, size_tag: DynamicTag) -> Result<Vec<Rela>, SegmentError> {
+        // Get address for the Rela entries
+        let rela_addr = self.dynamic_entry(tag).ok_or(DynamicError::TagNotFound(tag))?;
+
+        // Get total length, in bytes, for the Rela entries
+        let rela_len = self.dynamic_entry(size_tag).ok_or(DynamicError::TagNotFound(size_tag))?;
+
+        // Parse the rela through a reader which cannot go past the table
+        self.parse_relas(&mut self.reader_at(rela_addr, rela_len.into())?)
+    }
+
+    /// Returns the offsets relocated by the packed relative relocations `DT_RELR` points to, which
+    /// get the load base added like `R_*_RELATIVE` relocations. Files without the table have none.
+    pub fn relr_offsets(&self) -> Result<Vec<Addr>, SegmentError> {
+        let Some(relr_addr) = self.dynamic_entry(DynamicTag::Relr) else {
+            return Ok(vec![]);
+        };
+        let relr_len = self
+            .dynamic_entry(DynamicTag::RelrSz)
+            .ok_or(DynamicError::TagNotFound(DynamicTag::RelrSz))?;
+        let bytes = self.bytes_at(relr_addr, relr_len.into())?;
+        let (class, endianness) = (self.elf_header.e_class(), self.elf_header.e_data());
+        Ok(reloc::decode_relr(bytes, class, endianness)?)
+    }
+
+    /// Returns the relocations of the Android packed table, `APS2`, which `DT_ANDROID_RELA` or
+    /// `DT_ANDROID_REL` points to. Files without the table have none. Every relocation patches a
+    /// word of a loadable segment, so tables claiming more relocations than there are words are
+    /// rejected.
+    pub fn android_packed_relocations(&self) -> Result<Vec<PackedRelocation>, SegmentError> {
+        let tables = [
+            (DynamicTag::AndroidRela, DynamicTag::AndroidRelaSz, true),
+            (DynamicTag::AndroidRel, DynamicTag::AndroidRelSz, false),
+        ];
+        let Some((addr, size_tag, is_rela)) = tables
+            .into_iter()
+            .find_map(|(tag, size_tag, rela)| Some((self.dynamic_entry(tag)?, size_tag, rela)))
+        else {
+            return Ok(vec![]);
+        };
+        let len = self.dynamic_entry(size_tag).ok_or(DynamicError::TagNotFound(size_tag))?;
+        let bytes = self.bytes_at(addr, len.into())?;
+        let (class, endianness) = (self.elf_header.e_class(), self.elf_header.e_data());
+        let machine = self.elf_header.e_machine;
+        let loads = self.ph_table.iter().filter(|ph| ph.p_type == SegmentType::PtLoad);
+        let mem_size = loads.fold(0u64, |size, ph| size.saturating_add(ph.p_memsz.0));
+        let max_count = (mem_size / class.word_size() as u64) as usize;
+        Ok(reloc::decode_android_packed(bytes, class, endianness, machine, is_rela, max_count)?)
+    }
+
+    /// Reads and returns the `Rel` entries, without addends, of the table `DT_REL` points to
+    pub fn read_rel_entries(&self) -> Result<Vec<Rel>, SegmentError> {
+        self.rel_table(DynamicTag::Rel, DynamicTag::RelSz)
+    }
+
+    /// Reads and returns the `Rel` entries of the procedure linkage table, for files whose
+    /// `DT_PLTREL` is `DT_REL`
+    pub fn read_plt_rel_entries(&self) -> Result<Vec<Rel>, SegmentError> {
+        self.rel_table(DynamicTag::JmpRel, DynamicTag::PltRelSz)
+    }
+
+    /// Reads the `Rel` table whose address and total length, in bytes, are given by the dynamic
+    /// entries `tag` and `size_tag`, stepping by `DT_RELENT` bytes
+    fn rel_table(&self, tag: DynamicTag, size_tag: DynamicTag) -> Result<Vec<Rel>, SegmentError> {
+        let rel_addr = self.dynamic_entry(tag).ok_or(DynamicError::TagNotFound(tag))?;
+        let rel_len: usize =
+            self.dynamic_entry(size_tag).ok_or(DynamicError::TagNotFound(size_tag))?.into();
+        let relent = self
+            .dynamic_entry(DynamicTag::RelEnt)
+            .map_or(Rel::size(self.elf_header.e_class()), Into::into)
+            .max(1);
+
+        let mut reader = self.reader_at(rel_addr, rel_len)?;
+        (0..rel_len / relent)
+            .map(|index| {
+                reader.seek(index * relent)?;
+                Rel::parse(&mut reader, self.elf_header.e_machine)
+            })
+            .collect()
+    }
+
+    /// Parses `Rela` entries until the end of the table `reader` points to
+    fn parse_relas(&self, reader: &mut Reader) -> Result<Vec<Rela>, SegmentError> {
+        let mut rela_entries: Vec<Rela> = vec![];
+        while reader.index < reader.bytes.len() {
+            rela_entries.push(Rela::parse(reader, self.elf_header.e_machine)?);
+        }
+        Ok(rela_entries)
+    }
+
+    /// Returns the indirect functions the loader resolves through `IRELATIVE` relocations. They are
+    /// found in the dynamic relocation tables or, in static executables, in the allocated
+    /// relocation sections, like `.rela.iplt`, which the startup code applies.
+    pub fn indirect_functions(&self) -> Result<Vec<IndirectFunction>, SegmentError> {
+        let mut relas = vec![];
+        if self.dynamic_table().is_some() {
+            if self.dynamic_entry(DynamicTag::RelA).is_some() {
+                relas.extend(self.read_rela_entries()?);
+            }
+            if self.dynamic_entry(DynamicTag::JmpRel).is_some() {
+                relas.extend(self.read_plt_rela_entries()?);
+            }
+        } else {
+            let rela_sections = self.sh_table.iter().filter(|sh| {
+                sh.sh_type() == SectionType::ShtRela && sh.sh_flags().contains(SectionFlags::ALLOC)
+            });
+            for sh in rela_sections {
+                relas.extend(self.parse_relas(&mut self.elf_header.reader(&sh.data))?);
+            }
+        }
+
+        let mut functions: Vec<_> = relas
+            .iter()
+            .filter(|rela| rela.r_type.is_irelative())
+            .map(|rela| IndirectFunction {
+                resolver: Addr(rela.r_addend as u64),
+                target: rela.r_offset,
+            })
+            .collect();
+        // Some linkers let the dynamic relocation table overlap the PLT one
+        functions.sort_by_key(|function| function.target.0);
+        functions.dedup();
+        Ok(functions)
+    }
+
+    /// Returns `len` bytes starting at virtual address `addr`, taken from the Load segment which
+    /// contains it
+    fn bytes_at(&self, addr: Addr, len: usize) -> Result<&[u8], SegmentError> {
+        Ok(self.reader_at(addr, len)?.bytes)
+    }
+
+    /// Returns a reader over the table of `len` bytes at virtual address `addr`, bounded by the
+    /// table within the Load segment which contains it
+    fn reader_at(&self, addr: Addr, len: usize) -> Result<Reader<'_>, SegmentError> {
+        // Get the segment where the table is stored
+        let seg = self.loaded_segment_at(addr)?;
+
+        // Prepare the range of the table within the segment
+        let start: usize = (addr - seg.mem_range().start).into();
+        let end = start.checked_add(len).ok_or(ParseError::OutOfBounds)?;
+
+        Ok(self.elf_header.reader(&seg.data).sub_reader(start..end)?)
+    }
+
+    /// Returns the file contents of the Load segment containing `addr`, from `addr` on, like
+    /// `slice_at`, but telling missing addresses apart from contents which were not loaded
+    fn contents_at(&self, addr: Addr) -> Result<&[u8], SegmentError> {
+        let seg = self.loaded_segment_at(addr)?;
+        let start = usize::from(addr - seg.mem_range().start);
+        seg.data.get(start..).ok_or(SegmentError::BadPtLoadAddr(addr))
+    }
+
+    /// Returns the Load segment containing `addr`, whose contents must have been loaded
+    fn loaded_segment_at(&self, addr: Addr) -> Result<&ProgramHeader<'a>, SegmentError> {
+        let seg = self.segment_at(addr).ok_or(SegmentError::BadPtLoadAddr(addr))?;
+        if seg.data.len() as u64 != seg.p_filesz.0 {
+            return Err(SegmentError::NotLoaded(seg.p_vaddr));
+        }
+        Ok(seg)
+    }
+
+    /// Reads and returns the entries of the dynamic symbol table, which is located through
+    /// `DT_SYMTAB`, with entries `DT_SYMENT` bytes apart.
+    pub fn dynamic_symbols(&self) -> Result<Vec<SymbolEntry>, SegmentError> {
+        let (symtab_addr, syment) = self.dynamic_symbol_table()?;
+        let count = self.dynamic_symbol_count(symtab_addr)?;
+        let len = count.checked_mul(syment).ok_or(ParseError::OutOfBounds)?;
+        let mut reader = self.reader_at(symtab_addr, len)?;
+
+        let mut symbols = Vec::with_capacity(count);
+        for index in 0..count {
+            reader.seek(index * syment)?;
+            symbols.push(SymbolEntry::parse(&mut reader)?);
+        }
+
+        Ok(symbols)
+    }
+
+    /// Returns the name of the dynamic symbol `sym`, from the dynamic string table
+    pub fn symbol_name(&self, sym: &SymbolEntry) -> Result<Cow<'_, str>, StringError> {
+        self.dynamic_string_table()?.get(sym.st_name() as usize)
+    }
+
+    /// Returns an iterator over the dynamic symbols paired with their names, in the order of
+    /// `dynamic_symbols`
+    pub fn named_dynamic_symbols(
+        &self,
+    ) -> Result<impl Iterator<Item = (Cow<'_, str>, SymbolEntry)>, SegmentError> {
+        let strtab = self.dynamic_string_table()?;
+        let symbols = self.dynamic_symbols()?;
+        let names = symbols
+            .iter()
+            .map(|sym| strtab.get(sym.st_name() as usize))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(names.into_iter().zip(symbols))
+    }
+
+    /// Returns the address of the dynamic symbol table and the size of its entries
+    fn dynamic_symbol_table(&self) -> Result<(Addr, usize), SegmentError> {
+        let symtab_addr = self
+            .dynamic_entry(DynamicTag::SymTab)
+            .ok_or(DynamicError::TagNotFound(DynamicTag::SymTab))?;
+
+        // Entries are usually exactly the size of a symbol, but trust the dynamic table first
+        let syment: usize = self
+            .dynamic_entry(DynamicTag::SymEnt)
+            .map_or(SymbolEntry::size(self.elf_header.e_class()), Into::into);
+
+        Ok((symtab_addr, syment))
+    }
+
+    /// Reads the dynamic symbol at `index`, without reading the whole table
+    fn dynamic_symbol_at(&self, index: u32) -> Result<SymbolEntry, SegmentError> {
+        let (symtab_addr, syment) = self.dynamic_symbol_table()?;
+        let offset = (index as u64).checked_mul(syment as u64).ok_or(ParseError::OutOfBounds)?;
+        let addr = symtab_addr.0.checked_add(offset).ok_or(ParseError::OutOfBounds)?;
+        let mut reader = self.reader_at(Addr(addr), syment)?;
+        Ok(SymbolEntry::parse(&mut reader)?)
+    }
+
+    /// Looks up the defined dynamic symbol called `name` through the GNU or System V hash table,
+    /// the way the dynamic linker does, instead of scanning every dynamic symbol. Objects without
+    /// a usable hash table fall back to the scan.
+    pub fn lookup_symbol(&self, name: &str) -> Result<Option<SymbolEntry>, SegmentError> {
+        let strtab = self.dynamic_string_table()?;
+        let is_named = |sym: &SymbolEntry| {
+            sym.st_shndx() != sym::SHN_UNDEF
+                && strtab.get(sym.st_name() as usize).is_ok_and(|sym_name| sym_name == name)
+        };
+        let is_named_at = |index| self.dynamic_symbol_at(index).is_ok_and(|sym| is_named(&sym));
+
+        let index = match (self.gnu_hash_table(), self.hash_table()) {
+            (Some(Ok(table)), _) => table.lookup(name, is_named_at),
+            (_, Some(Ok(table))) => table.lookup(name, is_named_at),
+            _ => return Ok(self.dynamic_symbols()?.into_iter().find(is_named)),
+        };
+        index.map(|index| self.dynamic_symbol_at(index)).transpose()
+    }
+
+    /// Returns the addresses of the constructors in the init array, which run in order before the
+    /// entry point
+    pub fn init_functions(&self) -> Result<Vec<Addr>, SegmentError> {
+        self.function_array(DynamicTag::InitArray, DynamicTag::InitArraySz, SectionType::ShtInitArray)
+    }
+
+    /// Returns the addresses of the destructors in the fini array, which run in reverse order at
+    /// exit
+    pub fn fini_functions(&self) -> Result<Vec<Addr>, SegmentError> {
+        self.function_array(DynamicTag::FiniArray, DynamicTag::FiniArraySz, SectionType::ShtFiniArray)
+    }
+
+    /// Reads an array of function pointers, located through the dynamic table or, in static
+    /// files, through its section. Position independent files leave the pointers to relative
+    /// relocations, whose addends are taken instead of the file contents.
+    fn function_array(
+        &self,
+        tag: DynamicTag,
+        size_tag: DynamicTag,
+        sh_type: SectionType,
+    ) -> Result<Vec<Addr>, SegmentError> {
+        let (addr, bytes) = match (self.dynamic_entry(tag), self.dynamic_entry(size_tag)) {
+            (Some(addr), Some(size)) => (addr, self.bytes_at(addr, size.into())?),
+            _ => match self.sh_table.iter().find(|sh| sh.sh_type() == sh_type) {
+                Some(sh) => (sh.sh_addr(), &sh.data[..]),
+                None => return Ok(vec![]),
+            },
+        };
+
+        // Files without relocations have the final pointers in place
+        let relas = self.read_rela_entries().unwrap_or_default();
+        let word_size = self.elf_header.e_class().word_size();
+        let mut reader = self.elf_header.reader(bytes);
+        (0..bytes.len() / word_size)
+            .map(|index| {
+                let slot = addr + Addr((index * word_size) as u64);
+                let value = reader.read_word()?;
+                let relocated = relas
+                    .iter()
+                    .find(|rela| rela.r_offset == slot && rela.r_type.is_relative())
+                    .map(|rela| rela.r_addend as u64);
+                Ok(Addr(relocated.unwrap_or(value)))
+            })
+            .collect()
+    }
+
+    /// Returns the name of the function symbol of `.symtab` starting exactly at `addr`
+    pub fn function_name_at(&self, addr: Addr) -> Option<String> {
+        self.symbols()
+            .ok()?
+            .into_iter()
+            .find(|sym| {
+                sym.entry.st_info().st_type() == SymbolType::Func && sym.entry.st_value() == addr
+            })
+            .map(|sym| sym.name)
+    }
+
+    /// Returns the name of the function containing `addr` and the offset of `addr` in it, the way
+    /// backtraces print frames. Functions come from `.symtab`, or from the dynamic symbols of
+    /// stripped files. Addresses outside of every function resolve to the nearest function
+    /// before them.
+    pub fn symbolicate(&self, addr: Addr) -> Option<(String, u64)> {
+        let is_function = |sym: &SymbolEntry| {
+            matches!(sym.st_info().st_type(), SymbolType::Func | SymbolType::GnuIfunc)
+                && sym.st_shndx() != sym::SHN_UNDEF
+        };
+        let mut functions: Vec<_> = self
+            .symbols()
+            .ok()?
+            .into_iter()
+            .filter(|sym| is_function(&sym.entry))
+            .map(|sym| (Cow::Owned(sym.name), sym.entry))
+            .collect();
+        if functions.is_empty() {
+            let dynamic_symbols = self.named_dynamic_symbols().ok()?;
+            functions = dynamic_symbols.filter(|(_, sym)| is_function(sym)).collect();
+        }
+
+        let preceding = functions.iter().filter(|(_, sym)| sym.st_value() <= addr);
+        let containing = preceding
+            .clone()
+            .find(|(_, sym)| addr.0 - sym.st_value().0 < sym.st_size());
+        let nearest = || preceding.max_by_key(|(_, sym)| sym.st_value().0);
+        let (name, sym) = containing.or_else(nearest)?;
+        Some((name.to_string(), addr.0 - sym.st_value().0))
+    }
+
+    /// Returns the function containing the entry point and the offset of the entry point in it,
+    /// usually `_start` at offset 0
+    pub fn entry_symbol(&self) -> Option<(String, u64)> {
+        self.symbolicate(self.elf_header.e_entry)
+    }
+
+    /// Returns the address of `main`, from `.symtab`, or else guessed from the code at the entry
+    /// point. The `_start` of glibc on x86-64 loads the address of `main` in `rdi`, with a `mov`
+    /// or a `rip` relative `lea`, right before calling `__libc_start_main`, which gives it away in
+    /// stripped executables. This is a best effort: other machines and other startup code are not
+    /// recognized.
+    pub fn find_main(&self) -> Option<Addr> {
+        let symbols = self.symbols().unwrap_or_default();
+        let main = symbols.iter().find(|sym| {
+            sym.name == "main"
+                && sym.entry.st_info().st_type() == SymbolType::Func
+                && sym.entry.st_shndx() != sym::SHN_UNDEF
+        });
+        if let Some(main) = main {
+            return Some(main.entry.st_value());
+        }
+        if self.elf_header.e_machine != Machine::AmdX86_64 {
+            return None;
+        }
+
+        let entry = self.elf_header.e_entry;
+        let code = self.slice_at(entry)?;
+        let code = &code[..code.len().min(START_CODE_LEN)];
+        (0..code.len()).find_map(|index| {
+            let insn = code.get(index..index + 7)?;
+            let imm = i32::from_le_bytes([insn[3], insn[4], insn[5], insn[6]]) as i64 as u64;
+            let main = match insn[..3] {
+                // mov rdi, imm32
+                [0x48, 0xc7, 0xc7] => imm,
+                // lea rdi, [rip + disp32]
+                [0x48, 0x8d, 0x3d] => entry.0.wrapping_add(index as u64 + 7).wrapping_add(imm),
+                _ => return None,
+            };
+            // call rel32, addr32 call rel32 or call [rip + disp32], to `__libc_start_main`
+            let call = &code[index + 7..];
+            if ![&[0xe8][..], &[0x67, 0xe8], &[0xff, 0x15]].iter().any(|op| call.starts_with(op)) {
+                return None;
+            }
+            self.segment_at(Addr(main))
+                .filter(|ph| ph.p_flags().contains(SegmentFlags::EXEC))
+                .map(|_| Addr(main))
+        })
+    }
+
+    /// Returns the symbol versions defined by the object, from the table `DT_VERDEF` points to
+    pub fn version_definitions(&self) -> Result<Vec<VersionDefinition>, version::Error> {
+        let table = self.dynamic_entry(DynamicTag::VerDef);
+        let count = self.dynamic_entry(DynamicTag::VerDefNum);
+        let (Some(addr), Some(count)) = (table, count) else {
+            return Ok(vec![]);
+        };
+        // The table is only bounded by the count of its entries
+        let bytes = self.contents_at(addr)?;
+        let (class, endianness) = (self.elf_header.e_class(), self.elf_header.e_data());
+        let strtab = self.dynamic_string_table()?;
+        version::parse_definitions(bytes, count.into(), &strtab, class, endianness)
+    }
+
+    /// Returns the symbol versions the object needs from other objects, from the table
+    /// `DT_VERNEED` points to
+    pub fn version_requirements(&self) -> Result<Vec<VersionRequirement>, version::Error> {
+        let table = self.dynamic_entry(DynamicTag::VerNeed);
+        let count = self.dynamic_entry(DynamicTag::VerNeedNum);
+        let (Some(addr), Some(count)) = (table, count) else {
+            return Ok(vec![]);
+        };
+        let bytes = self.contents_at(addr)?;
+        let (class, endianness) = (self.elf_header.e_class(), self.elf_header.e_data());
+        let strtab = self.dynamic_string_table()?;
+        version::parse_requirements(bytes, count.into(), &strtab, class, endianness)
+    }
+
+    /// Returns the highest version of every interface the object needs, like `GLIBC_2.34` or
+    /// `GLIBCXX_3.4.29`, from its version requirements. The system running the object must
+    /// provide them.
+    pub fn required_symbol_versions(&self) -> Result<Vec<RequiredVersion>, version::Error> {
+        Ok(version::highest_versions(&self.version_requirements()?))
+    }
+
+    /// Returns the version of every dynamic symbol, in the order of `dynamic_symbols`, from the
+    /// table `DT_VERSYM` points to. Objects without symbol versioning return no versions.
+    pub fn dynamic_symbol_versions(&self) -> Result<Vec<Option<SymbolVersion>>, version::Error> {
+        let Some(versym_addr) = self.dynamic_entry(DynamicTag::VerSym) else {
+            return Ok(vec![]);
+        };
+        let symtab_addr = self
+            .dynamic_entry(DynamicTag::SymTab)
+            .ok_or(DynamicError::TagNotFound(DynamicTag::SymTab))
+            .map_err(SegmentError::from)?;
+        let count = self.dynamic_symbol_count(symtab_addr)?;
+        let len = count.checked_mul(2).ok_or(ParseError::OutOfBounds)?;
+        let mut reader = self.reader_at(versym_addr, len)?;
+
+        let definitions = self.version_definitions()?;
+        let requirements = self.version_requirements()?;
+        (0..count)
+            .map(|_| {
+                let versym = reader.read_u16()?;
+                Ok(SymbolVersion::resolve(versym, &definitions, &requirements))
+            })
+            .collect()
+    }
+
+    /// Returns the undefined dynamic symbols, with the library their version is needed from when
+    /// the object uses symbol versioning
+    pub fn imports(&self) -> Result<Vec<Import>, version::Error> {
+        let versions = self.dynamic_symbol_versions()?;
+        let imports = self
+            .named_dynamic_symbols()?
+            .enumerate()
+            .filter(|(_, (name, entry))| entry.st_shndx() == sym::SHN_UNDEF && !name.is_empty())
+            .map(|(index, (name, entry))| {
+                let version = versions.get(index).cloned().flatten();
+                Import {
+                    name: name.into(),
+                    library: version.as_ref().and_then(|version| version.file.clone()),
+                    version: version.map(|version| version.name),
+                    entry,
+                }
+            })
+            .collect();
+        Ok(imports)
+    }
+
+    /// Returns the defined global and weak dynamic symbols visible to other objects, which have
+    /// default or protected visibility
+    pub fn exports(&self) -> Result<Vec<Export>, version::Error> {
+        let versions = self.dynamic_symbol_versions()?;
+        let is_exported = |entry: &SymbolEntry| {
+            let binding = entry.st_info().st_binding();
+            entry.st_shndx() != sym::SHN_UNDEF
+                && matches!(
+                    binding,
+                    SymbolBinding::Global | SymbolBinding::Weak | SymbolBinding::GnuUnique
+                )
+                && matches!(
+                    entry.visibility(),
+                    SymbolVisibility::Default | SymbolVisibility::Protected
+                )
+        };
+        let exports = self
+            .named_dynamic_symbols()?
+            .enumerate()
+            .filter(|(_, (_, entry))| is_exported(entry))
+            .map(|(index, (name, entry))| Export {
+                name: name.into(),
+                version: versions.get(index).cloned().flatten().map(|version| version.name),
+                entry,
+            })
+            .collect();
+        Ok(exports)
+    }
+
+    /// Returns the names of the dynamic symbols with their version, the way tools print them:
+    /// `memcpy@GLIBC_2.14` for versions needed from other objects or hidden ones, and
+    /// `name@@VERSION` for the default version of a symbol the object defines.
+    pub fn versioned_dynamic_symbol_names(&self) -> Result<Vec<String>, version::Error> {
+        let versions = self.dynamic_symbol_versions()?;
+        let names = self
+            .named_dynamic_symbols()?
+            .enumerate()
+            .map(|(index, (name, _))| match versions.get(index) {
+                Some(Some(version)) if version.file.is_none() && !version.hidden => {
+                    format!("{}@@{}", name, version.name)
+                }
+                Some(Some(version)) => format!("{}@{}", name, version.name),
+                _ => name.into(),
+            })
+            .collect();
+        Ok(names)
+    }
+
+    /// The dynamic table does not record how many dynamic symbols there are. We take the count
+    /// from the section header of the table, or from the number of chains in the hash table, which
+    /// is always equal to the number of symbols, or from the chains of the GNU hash table.
+    fn dynamic_symbol_count(&self, symtab_addr: Addr) -> Result<usize, SegmentError> {
+        let dynsym = self
+            .sh_table
+            .iter()
+            .find(|sh| sh.sh_type() == SectionType::ShtDynsym && sh.sh_addr() == symtab_addr);
+        if let Some(dynsym) = dynsym {
+            let entsize = dynsym.sh_entsize().max(1);
+            return Ok((dynsym.sh_size().0 / entsize) as usize);
+        }
+
+        let count = match (self.hash_table(), self.gnu_hash_table()) {
+            (Some(Ok(table)), _) => table.symbol_count(),
+            (_, Some(Ok(table))) => table.symbol_count(),
+            _ => return Err(SymbolError::UnknownSymbolCount.into()),
+        };
+        Ok(count)
+    }
+
+    /// Returns the System V hash table `DT_HASH` points to
+    pub fn hash_table(&self) -> Option<Result<SysvHashTable, hash::Error>> {
+        let addr = self.dynamic_entry(DynamicTag::Hash)?;
+        let bytes = match self.contents_at(addr) {
+            Ok(bytes) => bytes,
+            Err(SegmentError::NotLoaded(vaddr)) => return Some(Err(hash::Error::NotLoaded(vaddr))),
+            Err(_) => return Some(Err(hash::Error::BadAddress(addr))),
+        };
+        let (class, endianness) = (self.elf_header.e_class(), self.elf_header.e_data());
+        Some(SysvHashTable::parse(bytes, class, endianness))
+    }
+
+    /// Returns the GNU hash table `DT_GNU_HASH` points to
+    pub fn gnu_hash_table(&self) -> Option<Result<GnuHashTable, hash::Error>> {
+        let addr = self.dynamic_entry(DynamicTag::GnuHash)?;
+        let bytes = match self.contents_at(addr) {
+            Ok(bytes) => bytes,
+            Err(SegmentError::NotLoaded(vaddr)) => return Some(Err(hash::Error::NotLoaded(vaddr))),
+            Err(_) => return Some(Err(hash::Error::BadAddress(addr))),
+        };
+        let (class, endianness) = (self.elf_header.e_class(), self.elf_header.e_data());
+        Some(GnuHashTable::parse(bytes, class, endianness))
+    }
+
+    /// Reads the symbol table from the `.symtab` section, resolving the name of each symbol through
+    /// the string table linked to it. Stripped files have no such table and return no symbols.
+    pub fn symbols(&self) -> Result<Vec<Symbol>, SymbolError> {
+        let Some(symtab_index) = self
+            .sh_table
+            .iter()
+            .position(|sh| sh.sh_type() == SectionType::ShtSymtab)
+        else {
+            return Ok(vec![]);
+        };
+        self.symbol_table(symtab_index)
+    }
+
+    /// Reads the symbols of the symbol table section at `symtab_index`, either `.symtab` or
+    /// `.dynsym`, resolving their names through the string table linked to it
+    fn symbol_table(&self, symtab_index: usize) -> Result<Vec<Symbol>, SymbolError> {
+        let symtab = self
+            .sh_table
+            .get(symtab_index)
+            .ok_or(SymbolError::BadSymbolTableIndex(symtab_index as u32))?;
+        let strtab = self
+            .sh_table
+            .get(symtab.sh_link() as usize)
+            .map(|sh| StringTable::new(&sh.data))
+            .ok_or(SymbolError::BadStringTableIndex(symtab.sh_link()))?;
+
+        let extended_indexes = self.extended_section_indexes(symtab_index);
+
+        let class = self.elf_header.e_class();
+        let entsize = (symtab.sh_entsize() as usize).max(SymbolEntry::size(class));
+        let count = symtab.data.len() / entsize;
+        let mut reader = self.elf_header.reader(&symtab.data);
+
+        let mut symbols = Vec::with_capacity(count);
+        for index in 0..count {
+            reader.seek(index * entsize)?;
+            let entry = SymbolEntry::parse(&mut reader)?;
+            let name = strtab
+                .get(entry.st_name() as usize)
+                .map_err(|_| SymbolError::BadNameOffset(entry.st_name()))?
+                .into();
+            let shndx = self
+                .symbol_section_index(&entry, index, extended_indexes)
+                .ok_or(SymbolError::BadExtendedIndex(index))?;
+            symbols.push(Symbol { name, shndx, entry });
+        }
+
+        Ok(symbols)
+    }
+
+    /// Section indexes which do not fit in `st_shndx` are held by a parallel table of 32-bit
+    /// indexes, linked to the symbol table at `symtab_index`
+    fn extended_section_indexes(&self, symtab_index: usize) -> Option<&[u8]> {
+        self.sh_table
+            .iter()
+            .find(|sh| {
+                sh.sh_type() == SectionType::ShtSymtabShndx
+                    && sh.sh_link() as usize == symtab_index
+            })
+            .map(|sh| &sh.data[..])
+    }
+
+    /// Returns the section index of the symbol `entry`, found at `index` in its table, reading it
+    /// from `extended_indexes` when it does not fit in `st_shndx`
+    fn symbol_section_index(
+        &self,
+        entry: &SymbolEntry,
+        index: usize,
+        extended_indexes: Option<&[u8]>,
+    ) -> Option<u32> {
+        if entry.st_shndx() != SHN_XINDEX {
+            return Some(entry.st_shndx().into());
+        }
+        let bytes = extended_indexes?.get(index * 4..index * 4 + 4)?;
+        self.elf_header.reader(bytes).read_u32().ok()
+    }
+
+    /// Checks every entry of the symbol tables, `.symtab` and `.dynsym`, instead of trusting the
+    /// file: names must be in the linked string table, section indexes must refer to a section
+    /// or be reserved, and values must fall inside the section of the symbol, end included.
+    /// Thread-local symbols are offsets in the TLS template, and the untyped empty symbols linkers
+    /// define to mark boundaries, like `_end` or `__bss_start`, point around their section, so
+    /// neither have their values checked.
+    pub fn validate_symbols(&self) -> Result<Vec<SymbolDiagnostic>, SymbolError> {
+        let mut diagnostics = vec![];
+        let class = self.elf_header.e_class();
+        let relocatable = self.elf_header.e_type == FileType::EtRel;
+        for (table, symtab) in self.sh_table.iter().enumerate() {
+            if !matches!(symtab.sh_type(), SectionType::ShtSymtab | SectionType::ShtDynsym) {
+                continue;
+            }
+            let strtab = self
+                .sh_table
+                .get(symtab.sh_link() as usize)
+                .map(|sh| StringTable::new(&sh.data))
+                .ok_or(SymbolError::BadStringTableIndex(symtab.sh_link()))?;
+            let extended_indexes = self.extended_section_indexes(table);
+
+            let entsize = (symtab.sh_entsize() as usize).max(SymbolEntry::size(class));
+            let mut reader = self.elf_header.reader(&symtab.data);
+            for index in 0..symtab.data.len() / entsize {
+                reader.seek(index * entsize)?;
+                let entry = SymbolEntry::parse(&mut reader)?;
+                let mut report = |issue| diagnostics.push(SymbolDiagnostic { table, index, issue });
+
+                if strtab.get(entry.st_name() as usize).is_err() {
+                    report(SymbolIssue::BadNameOffset(entry.st_name()));
+                }
+
+                let Some(shndx) = self.symbol_section_index(&entry, index, extended_indexes) else {
+                    report(SymbolIssue::MissingExtendedIndex);
+                    continue;
+                };
+                let reserved = shndx == u32::from(sym::SHN_UNDEF)
+                    || (entry.st_shndx() != SHN_XINDEX && entry.st_shndx() >= sym::SHN_LORESERVE);
+                if reserved {
+                    continue;
+                }
+                let Some(section) = self.sh_table.get(shndx as usize) else {
+                    report(SymbolIssue::BadSectionIndex(shndx));
+                    continue;
+                };
+
+                // Relocatable files hold offsets in the section rather than addresses
+                let value = entry.st_value();
+                let range = if relocatable {
+                    Addr(0)..Addr(0) + section.sh_size()
+                } else {
+                    section.mem_range()
+                };
+                let unchecked = match entry.st_info().st_type() {
+                    SymbolType::Tls => true,
+                    SymbolType::NoType => entry.st_size() == 0,
+                    _ => false,
+                };
+                if !unchecked && (value < range.start || value > range.end) {
+                    report(SymbolIssue::ValueOutsideSection { value, shndx });
+                }
+            }
+        }
+        Ok(diagnostics)
+    }
+
+    /// Returns the section name string table, `.shstrtab`, pointed to by `e_shstrndx`
+    pub fn section_name_table(&self) -> Option<StringTable<'_>> {
+        self.sh_table.get(self.shstrndx()).map(|sh| StringTable::new(&sh.data))
+    }
+
+    /// Looks up the name of `sh` in the section name string table
+    pub fn section_name(&self, sh: &SectionHeader) -> Option<String> {
+        let name = self.section_name_table()?.get(sh.sh_name() as usize).ok()?;
+        Some(name.into())
+    }
+
+    /// Returns an iterator over the sections, in the order of the section header table
+    pub fn sections(&self) -> impl Iterator<Item = Section<'_, 'a>> + '_ {
+        (0..self.sh_table.len()).filter_map(move |index| Section::new(self, index))
+    }
+
+    /// Returns the first section header named `name`, like `.text`. Its contents are in `data`.
+    pub fn section_by_name(&self, name: &str) -> Option<&SectionHeader<'a>> {
+        self.sh_table.iter().find(|sh| sh.name() == Some(name))
+    }
+
+    /// Returns the contents of the section `sh`, decompressing them if the section is compressed
+    pub fn section_data<'s>(
+        &self,
+        sh: &'s SectionHeader,
+    ) -> Result<Cow<'s, [u8]>, compress::Error> {
+        if !sh.sh_flags().contains(SectionFlags::COMPRESSED) {
+            return Ok(Cow::Borrowed(&sh.data));
+        }
+        let (class, endianness) = (self.elf_header.e_class(), self.elf_header.e_data());
+        Ok(Cow::Owned(compress::decompress(&sh.data, class, endianness)?))
+    }
+
+    /// Returns the identification strings the compilers and linkers which built the file left in
+    /// the `.comment` section, like `GCC: (Debian 12.2.0-14) 12.2.0`, without duplicates
+    pub fn comments(&self) -> Vec<String> {
+        let Some(comment) = self.section_by_name(".comment") else {
+            return vec![];
+        };
+        let mut comments: Vec<String> = vec![];
+        for (_, string) in StringTable::new(&comment.data).iter() {
+            if !string.is_empty() && !comments.iter().any(|known| *known == string) {
+                comments.push(string.into());
+            }
+        }
+        comments
+    }
+
+    /// Returns the contents of the `.gnu_debuglink` section, which names the file holding the
+    /// debug information stripped from this one
+    pub fn debug_link(&self) -> Result<Option<DebugLink>, ParseError> {
+        let Some(section) = self.section_by_name(".gnu_debuglink") else {
+            return Ok(None);
+        };
+        Ok(Some(DebugLink::parse(&mut self.elf_header.reader(&section.data))?))
+    }
+
+    /// Returns the notes of the `PT_NOTE` segments, decoding the ones core dumps use to describe the
+    /// crashed process
+    pub fn core_notes(&self) -> impl Iterator<Item = Result<CoreNote<'_>, core_dump::Error>> + '_ {
+        let header = &self.elf_header;
+        self.ph_table
+            .iter()
+            .filter(|ph| ph.p_type == SegmentType::PtNote)
+            .flat_map(move |ph| Notes::new(header.reader(&ph.data), ph.p_align))
+            .map(move |note| {
+                CoreNote::parse(note?, header.e_class, header.e_data, header.e_machine)
+            })
+    }
+
+    /// Returns the GNU note of type `n_type`, looking in the `PT_NOTE` segments first, then in the
+    /// section named `section` for files without program headers, like object files. Malformed
+    /// notes are skipped.
+    fn gnu_note(&self, n_type: u32, section: &str) -> Option<Note<'_>> {
+        let is_match = |note: &Note| note.name == note::GNU_NAME && note.n_type == n_type;
+        let from_segments = self
+            .ph_table
+            .iter()
+            .filter(|ph| ph.p_type == SegmentType::PtNote)
+            .flat_map(|ph| Notes::new(self.elf_header.reader(&ph.data), ph.p_align))
+            .filter_map(Result::ok)
+            .find(is_match);
+        from_segments.or_else(|| {
+            let section = self.section_by_name(section)?;
+            Notes::new(self.elf_header.reader(&section.data), section.sh_addralign())
+                .filter_map(Result::ok)
+                .find(is_match)
+        })
+    }
+
+    /// Returns the GNU build ID of the file
+    pub fn build_id(&self) -> Option<BuildId<'_>> {
+        let note = self.gnu_note(note::NT_GNU_BUILD_ID, ".note.gnu.build-id")?;
+        Some(BuildId { bytes: note.desc })
+    }
+
+    /// Returns the properties of the GNU property note, which the `PtGnuProperty` segment points
+    /// to, like the CET features or x86 ISA levels of the program
+    pub fn gnu_properties(&self) -> Option<Result<Vec<GnuProperty<'_>>, ParseError>> {
+        let note = match self.segment_of_type(SegmentType::PtGnuProperty) {
+            Some(ph) => Notes::new(self.elf_header.reader(&ph.data), ph.p_align)
+                .filter_map(Result::ok)
+                .find(|note| {
+                    note.name == note::GNU_NAME && note.n_type == note::NT_GNU_PROPERTY_TYPE_0
+                }),
+            None => self.gnu_note(note::NT_GNU_PROPERTY_TYPE_0, ".note.gnu.property"),
+        }?;
+        let machine = self.elf_header.e_machine;
+        Some(GnuProperty::parse_all(&mut self.elf_header.reader(note.desc), machine))
+    }
+
+    /// Returns the x86 features every object linked in the file supports, like IBT and shadow
+    /// stacks for CET. Files without the property support none.
+    pub fn x86_features(&self) -> X86Feature1 {
+        let properties = self.gnu_properties().and_then(Result::ok).unwrap_or_default();
+        let features = properties.into_iter().find_map(|property| match property {
+            GnuProperty::X86Feature1And(features) => Some(features),
+            _ => None,
+        });
+        features.unwrap_or_else(X86Feature1::empty)
+    }
+
+    /// Returns the GNU ABI tag of the file, giving the operating system and the oldest kernel
+    /// it runs on
+    pub fn abi_tag(&self) -> Option<Result<AbiTag, ParseError>> {
+        let note = self.gnu_note(note::NT_GNU_ABI_TAG, ".note.ABI-tag")?;
+        Some(AbiTag::parse(&mut self.elf_header.reader(note.desc)))
+    }
+
+    /// Looks for the separate debug file of the file at `path` in the usual `/usr/lib/debug`
+    /// layout. See `find_debug_file_in`.
+    pub fn find_debug_file(&self, path: &Path) -> Option<PathBuf> {
+        self.find_debug_file_in(path, Path::new(debug::DEBUG_ROOT))
+    }
+
+    /// Looks for the separate debug file of the file at `path`, first by build ID under
+    /// `debug_root`, then by the name in `.gnu_debuglink`. Files found through the debug link must
+    /// match its CRC.
+    pub fn find_debug_file_in(&self, path: &Path, debug_root: &Path) -> Option<PathBuf> {
+        let by_build_id = self
+            .build_id()
+            .and_then(|build_id| debug::build_id_path(build_id.bytes, debug_root))
+            .filter(|candidate| candidate.is_file());
+        if by_build_id.is_some() {
+            return by_build_id;
+        }
+
+        let link = self.debug_link().ok()??;
+        link.candidates(path, debug_root)
+            .into_iter()
+            .filter(|candidate| candidate != path)
+            .find(|candidate| fs::read(candidate).is_ok_and(|bytes| debug::crc32(&bytes) == link.crc))
+    }
+
+    /// Returns the index of the section header table entry that contains the section names.
+    /// Handles indexes that do not fit in `e_shstrndx` and are stored in the first section.
+    pub fn shstrndx(&self) -> usize {
+        match (self.elf_header.e_shstrndx, self.sh_table.first()) {
+            (SHN_XINDEX, Some(sh)) => sh.sh_link() as usize,
+            (shstrndx, _) => shstrndx.into(),
+        }
+    }
+
+    /// Returns the allocated section whose memory range contains `addr`, or `None` if `addr` is
+    /// not in any section loaded at run time
+    pub fn section_containing(&self, addr: Addr) -> Option<&SectionHeader<'a>> {
+        self.sh_table
+            .iter()
+            .filter(|sh| sh.sh_flags().contains(SectionFlags::ALLOC))
+            .find(|sh| sh.mem_range().contains(&addr))
+    }
+
+    /// Returns, for every program header, the sections which are part of its segment. This is the
+    /// "Section to Segment mapping" printed by `readelf -l`.
+    pub fn section_to_segment_mapping(&self) -> Vec<Vec<&SectionHeader<'a>>> {
+        self.ph_table
+            .iter()
+            .map(|ph| self.sh_table.iter().filter(|sh| ph.contains_section(sh)).collect())
+            .collect()
+    }
+
+    /// Returns the section header that start at EXACTLY this virtual address `addr`,
+    /// or `None` if we can't find one.
+    pub fn section_starting_at(&self, addr: Addr) -> Option<&SectionHeader<'a>> {
+        self.sh_table.iter().find(|&sh| sh.sh_addr() == addr)
+    }
+}
+
+/// Fetches the bytes of a header table starting at `table_offset` with `count` entries of
+/// `entry_size` bytes
+fn fetch_table<'a, F: Fetch<'a>>(
+    fetch: &F,
+    table_offset: FileOffset,
+    count: usize,
+    entry_size: usize,
+) -> Result<Cow<'a, [u8]>, ElfError> {
+    if count == 0 {
+        return Ok(Cow::Borrowed(&[]));
+    }
+    let len = count.checked_mul(entry_size).ok_or(ParseError::OutOfBounds)?;
+    fetch.fetch(table_offset.into(), len)
+}
+
+/// Tells whether `inner` is a non-empty range lying within `outer`
+fn contains_range(outer: &Range<Addr>, inner: &Range<Addr>) -> bool {
+    inner.start.0 < inner.end.0 && outer.start.0 <= inner.start.0 && inner.end.0 <= outer.end.0
+}
+
+/// Writes the contents of a segment or section at `offset`, checking they hold `size` bytes
+fn write_contents(
+    writer: &mut Writer,
+    offset: FileOffset,
+    size: Size,
+    data: &[u8],
+) -> Result<(), write::Error> {
+    if data.len() as u64 != size.0 {
+        return Err(write::Error::SizeMismatch { offset, size, len: data.len() });
+    }
+    writer.set_position(offset.into());
+    writer.write_slice(data);
+    Ok(())
+}
+
+impl fmt::Debug for Elf64<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}\n{}\n",
+            self.elf_header,
+            self.ph_table.iter().map(|ph_entry| format!("{:?}", ph_entry))
+            .collect::<Vec<_>>().join("\n"))
+    }
+}
+
+/// Tell the system how to create a process image. It is found at file offset
+/// `e_phoff` and consists of `e_phnum` entries, each with size `e_phentsize`.
+pub struct ProgramHeader<'a> {
+    /// Identifies the type of the segment
+    p_type: SegmentType,
+    /// BitMask for segment-dependent flags
+    p_flags: SegmentFlags,
+    /// Offset of the segment in the file image,
+    p_offset: FileOffset,
+    /// Virtual Address of the segment in memory,
+    p_vaddr: Addr,
+    /// On systems where physical address is relevant, reserved for segment's
+    /// physical address
+    p_paddr: Addr,
+    /// Size in bytes of the segment in the file image. May be 0.
+    p_filesz: Size,
+    /// Size in bytes of the segment in memory
+    p_memsz: Size,
+    /// 0 and 1 specify no alignment. Otherwise should be a positive, integral
+    /// power of 2 with p_vaddr = p_offset % p_align
+    p_align: u64,
+    /// The contents of the segment, borrowed from the parsed bytes
+    pub data: Cow<'a, [u8]>,
+    /// Contents of the current segment based on `SegmentType`
+    pub contents: SegmentContents,
+}
+
+impl<'a> ProgramHeader<'a> {
+    /// Returns the size, in bytes, of a program header for the given `class`
+    pub fn size(class: Class) -> usize {
+        match class {
+            Class::Elf32 => 32,
+            Class::Elf64 => 56,
+        }
+    }
+
+    /// Parses a program header and loads the contents of its segment
+    pub fn parse(reader: &mut Reader<'a>) -> Result<Self, ProgramHeaderError> {
+        let mut ph = Self::parse_header(reader)?;
+        ph.load_data(reader)?;
+        Ok(ph)
+    }
+
+    /// Parses a program header, leaving the contents of its segment empty
+    pub fn parse_header(reader: &mut Reader) -> Result<Self, ProgramHeaderError> {
+        let p_type = SegmentType::parse(reader)?;
+        // 64-bit files place the flags right after the type, to keep the following fields aligned.
+        // 32-bit files have them just before the alignment.
+        let mut p_flags = match reader.class {
+            Class::Elf32 => SegmentFlags::empty(),
+            Class::Elf64 => SegmentFlags::parse(reader)?,
+        };
+        let p_offset = FileOffset::parse(reader)?;
+        let p_vaddr = Addr::parse(reader)?;
+        let p_paddr = Addr::parse(reader)?;
+        let p_filesz = Size::parse(reader)?;
+        let p_memsz = Size::parse(reader)?;
+        if reader.class == Class::Elf32 {
+            p_flags = SegmentFlags::parse(reader)?;
+        }
+        let p_align = reader.read_word()?;
+
+        Ok(Self {
+            p_type,
+            p_flags,
+            p_offset,
+            p_vaddr,
+            p_paddr,
+            p_filesz,
+            p_memsz,
+            p_align,
+            data: Cow::Borrowed(&[]),
+            contents: SegmentContents::Unknown,
+        })
+    }
+
+    /// Writes the program header, without the contents of its segment
+    pub fn write_header(&self, writer: &mut Writer) -> Result<(), write::Error> {
+        writer.write_u32(self.p_type.into());
+        if writer.class == Class::Elf64 {
+            writer.write_u32(self.p_flags.bits());
+        }
+        writer.write_word(self.p_offset.into())?;
+        writer.write_word(self.p_vaddr.into())?;
+        writer.write_word(self.p_paddr.into())?;
+        writer.write_word(self.p_filesz.into())?;
+        writer.write_word(self.p_memsz.into())?;
+        if writer.class == Class::Elf32 {
+            writer.write_u32(self.p_flags.bits());
+        }
+        writer.write_word(self.p_align)
+    }
+
+    /// Borrows the contents of the segment from the file `reader` points to and decodes them,
+    /// based on the type of the segment
+    pub fn load_data(&mut self, reader: &Reader<'a>) -> Result<(), ProgramHeaderError> {
+        let segment_start: usize = self.p_offset.into();
+        let segment_end: usize = Into::<usize>::into(self.p_offset) +
+            Into::<usize>::into(self.p_filesz);
+
+        let segment_data_range = Range {
+            start: segment_start,
+            end: segment_end
+        };
+
+        let data = Cow::Borrowed(reader.read_slice_from(segment_data_range)?);
+        self.set_data(data, reader.class, reader.endianness)
+    }
+
+    /// Sets the contents of the segment to `data` and decodes them, based on the type of the
+    /// segment
+    pub fn set_data(
+        &mut self,
+        data: Cow<'a, [u8]>,
+        class: Class,
+        endianness: Endianness,
+    ) -> Result<(), ProgramHeaderError> {
+        self.data = data;
+
+        self.contents = match self.p_type {
+            SegmentType::PtDynamic => {
+                // Parse the dynamic table
+                let mut reader = Reader::new(&self.data, class, endianness);
+                SegmentContents::Dynamic(DynamicTable::parse(&mut reader)?)
+            },
+            // A path missing its NUL is left for `Elf64::interpreter` to report
+            SegmentType::PtInterp => match Reader::from_bytes(&self.data).read_cstr() {
+                Ok(path) => SegmentContents::Interp(String::from_utf8_lossy(path).into()),
+                Err(_) => SegmentContents::Unknown,
+            },
+            _ => SegmentContents::Unknown,
+        };
+
+        Ok(())
+    }
+
+    /// Returns whether the section `sh` is part of the segment, with the same rules as the
+    /// "Section to Segment mapping" of readelf: the section must lie inside the segment both in
+    /// the file and, when allocated, in memory, and its kind must suit the segment type.
+    pub fn contains_section(&self, sh: &SectionHeader) -> bool {
+        let flags = sh.sh_flags();
+        let tls = flags.contains(SectionFlags::TLS);
+        let alloc = flags.contains(SectionFlags::ALLOC);
+        let nobits = sh.sh_type() == SectionType::ShtNobits;
+
+        if sh.sh_type() == SectionType::ShtNull {
+            return false;
+        }
+        // Only loadable, TLS and RELRO segments hold TLS sections, and TLS segments hold nothing
+        // else. The program header table segment holds no section.
+        let type_fits = if tls {
+            matches!(
+                self.p_type,
+                SegmentType::PtTls | SegmentType::PtLoad | SegmentType::PtGnuRelro
+            )
+        } else {
+            !matches!(self.p_type, SegmentType::PtTls | SegmentType::PtPhdr)
+        };
+        // Segments describing memory only hold sections which take memory
+        let alloc_fits = alloc
+            || !matches!(
+                self.p_type,
+                SegmentType::PtLoad
+                    | SegmentType::PtDynamic
+                    | SegmentType::PtGnuEhFrame
+                    | SegmentType::PtGnuStack
+                    | SegmentType::PtGnuRelro
+                    | SegmentType::PtOsSpecific(PT_GNU_SFRAME)
+            );
+        // `.tbss` takes no room in the memory of the loadable segments, only in the TLS template
+        if !type_fits || !alloc_fits || (tls && nobits && self.p_type != SegmentType::PtTls) {
+            return false;
+        }
+
+        let size = sh.sh_size().0;
+        // Whether `start..start + size` lies in `seg_start..seg_start + seg_size`, with empty
+        // sections allowed anywhere but past the end
+        let inside = |start: u64, seg_start: u64, seg_size: u64| {
+            start >= seg_start
+                && start - seg_start < seg_size.max(1)
+                && (start - seg_start).saturating_add(size) <= seg_size
+        };
+        let in_file = nobits || inside(sh.sh_offset().0, self.p_offset.0, self.p_filesz.0);
+        let in_memory = !alloc || inside(sh.sh_addr().0, self.p_vaddr.0, self.p_memsz.0);
+        if !in_file || !in_memory {
+            return false;
+        }
+
+        // Empty sections at the edges of the dynamic table or of notes belong to their neighbours
+        if matches!(self.p_type, SegmentType::PtDynamic | SegmentType::PtNote)
+            && size == 0
+            && self.p_memsz.0 != 0
+        {
+            let strictly_inside = |start: u64, seg_start: u64, seg_size: u64| {
+                start > seg_start && start - seg_start < seg_size
+            };
+            return (nobits || strictly_inside(sh.sh_offset().0, self.p_offset.0, self.p_filesz.0))
+                && (!alloc || strictly_inside(sh.sh_addr().0, self.p_vaddr.0, self.p_memsz.0));
+        }
+
+        true
+    }
+
+    /// Returns a range where the segment is stored in the file
+    pub fn file_range(&self) -> Range<FileOffset> {
+        self.p_offset..self.p_offset + self.p_filesz
+    }
+
+    /// Returns the entropy of the file contents of the segment, in bits per byte
+    pub fn entropy(&self) -> f64 {
+        entropy::entropy(&self.data)
+    }
+
+    /// Returns a range where the segment should be stored in memory
+    pub fn mem_range(&self) -> Range<Addr> {
+        self.p_vaddr..self.p_vaddr + self.p_memsz
+    }
+
+    /// Returns the address `offset` of the file is loaded at, if the segment holds it
+    pub fn offset_to_addr(&self, offset: FileOffset) -> Option<Addr> {
+        self.file_range()
+            .contains(&offset)
+            .then(|| self.p_vaddr + Size(offset.0 - self.p_offset.0))
+    }
+
+    /// Returns the offset in the file of the byte loaded at `addr`, if the segment holds it and
+    /// it is not part of the zero filled memory past the file contents
+    pub fn addr_to_offset(&self, addr: Addr) -> Option<FileOffset> {
+        (self.p_vaddr..self.p_vaddr + self.p_filesz)
+            .contains(&addr)
+            .then(|| self.p_offset + Size(addr.0 - self.p_vaddr.0))
+    }
+
+    pub fn p_offset(&self) -> FileOffset {
+        self.p_offset
+    }
+
+    pub fn p_vaddr(&self) -> Addr {
+        self.p_vaddr
+    }
+
+    pub fn p_filesz(&self) -> Size {
+        self.p_filesz
+    }
+
+    pub fn p_memsz(&self) -> Size {
+        self.p_memsz
+    }
+
+    pub fn p_flags(&self) -> SegmentFlags {
+        self.p_flags
+    }
+
+    pub fn p_type(&self) -> SegmentType {
+        self.p_type
+    }
+
+    pub fn p_align(&self) -> u64 {
+        self.p_align
+    }
+
+    pub fn p_addr(&self) -> Addr {
+        self.p_paddr
+    }
+}
+
+impl fmt::Debug for ProgramHeader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            " Type: {:?}\n Flags: {:?}\n Contents: {:?}\n",
+            self.p_type,
+            self.p_flags,
+            self.contents,
+        )
+    }
+}
+
+const ELF_MAGIC_SIZE: usize = 4;
+/// Size of `e_ident`, the identification bytes at the start of the Elf header
+const EI_NIDENT: usize = 16;
+/// Index of the class in `e_ident`
+const EI_CLASS: usize = 4;
+/// Offset of `e_entry`, which is the same for both classes
+const E_ENTRY_OFFSET: usize = 24;
+/// Number of bytes of `_start` searched for the call to `__libc_start_main`
+const START_CODE_LEN: usize = 64;
+const ELF32_HEADER_SIZE: usize = 52;
+const ELF64_HEADER_SIZE: usize = 64;
+const ELF_MAGIC: &[u8] = &[0x7F, 0x45, 0x4C, 0x46];
+/// Value of `e_phnum` signaling that the real number of program headers is held by the `sh_info`
+/// field of the first section header
+const PN_XNUM: u16 = 0xFFFF;
+
+#[derive(Debug)]
+pub struct ElfHeader {
+    /// Class of the file, which gives the width of addresses and offsets
+    pub e_class: Class,
+    /// Byte order of the multi-byte fields in the file
+    pub e_data: Endianness,
+    /// Operating system and ABI the object is targeted for
+    pub e_osabi: OsAbi,
+    /// Version of the ABI given by `e_osabi`
+    pub e_abiversion: u8,
+    pub e_type: FileType,
+    pub e_machine: Machine,
+    /// Memory address of the entry point from where the process starts
+    /// executing
+    pub e_entry: Addr,
+    /// Points to the start of the program header table.
+    pub e_phoff: FileOffset,
+    /// Points to the start of the section header table.
+    pub e_shoff: FileOffset,
+    /// Flags specific to the processor
+    pub e_flags: u32,
+    /// Size of the Elf header
+    pub e_ehsize: u16,
+    /// Contains the size of a program header table entry.
+    pub e_phentsize: u16,
+    /// Contains the number of entries in the program header table.
+    pub e_phnum: u16,
+    /// Contains the size of a section header table entry.
+    pub e_shentsize: u16,
+    /// Contains the number of entries in the section header table.
+    pub e_shnum: u16,
+    /// Contains index of the section header table entry that contains the section names.
+    pub e_shstrndx: u16,
+}
+
+impl ElfHeader {
+    pub fn parse(reader: &mut Reader) -> Result<Self, ElfHeaderError> {
+        // Read the magic
+        let e_magic = reader.read_slice(ELF_MAGIC_SIZE)?;
+        // Check if we have an Elf files
+        if e_magic != ELF_MAGIC {
+            return Err(ElfHeaderError::BadMagic(format!("{:?}", e_magic)))
+        }
+
+        // Read the class
+        let e_class = Class::try_from(reader.read_u8()?)?;
+        // Every word sized field from now on is read according to the class
+        reader.class = e_class;
+
+        // Read the endianness
+        let e_data = Endianness::try_from(reader.read_u8()?)?;
+        // Every multi-byte field from now on is read in this byte order
+        reader.endianness = e_data;
+
+        // Read the version
+        let e_version = reader.read_u8()?;
+        // Should be 1 for the original and current version of Elf
+        if e_version != 1 {
+            return Err(ElfHeaderError::BadVersion)
+        }
+
+        // Read the target operating system ABI
+        let e_osabi = OsAbi::from(reader.read_u8()?);
+
+        let e_abiversion = reader.read_u8()?;
+
+        // Skip the remaining padding
+        let _ = reader.read_slice(7)?;
+
+        // Read the object file_type
+        let e_type: FileType = reader.read_u16()?.try_into()?;
+
+        // Read the object machine
+        let e_machine = Machine::from(reader.read_u16()?);
+
+        // Read yet another version
+        let e_version = reader.read_u32()?;
+
+        // Check if version has the only value possible
         if e_version != 1 {
             return Err(ElfHeaderError::NotOriginalVersion);
         }
 
-        // Read entry point
-        let e_entry = Addr::parse(reader)?;
+        // Read entry point
+        let e_entry = Addr::parse(reader)?;
+
+
+        // Read the offset of the Program Header table
+        let e_phoff = FileOffset::parse(reader)?;
+
+        // Read start of the section header table
+        let e_shoff = FileOffset::parse(reader)?;
+
+        let e_flags = reader.read_u32()?;
+        let e_ehsize = reader.read_u16()?;
+
+        // Read the size of a Program Header table entry.
+        let e_phentsize = reader.read_u16()?;
+        // Read Program Header table entries
+        let e_phnum = reader.read_u16()?;
+
+        // Read information about the section header table
+        let e_shentsize = reader.read_u16()?;
+        let e_shnum = reader.read_u16()?;
+        let e_shstrndx = reader.read_u16()?;
+
+
+        Ok(ElfHeader{
+            e_class,
+            e_data,
+            e_osabi,
+            e_abiversion,
+            e_type,
+            e_machine,
+            e_entry,
+            e_phoff,
+            e_shoff,
+            e_flags,
+            e_ehsize,
+            e_phentsize,
+            e_phnum,
+            e_shentsize,
+            e_shnum,
+            e_shstrndx,
+        })
+    }
+
+    /// Writes the header, switching `writer` to the class and byte order the header names
+    pub fn write(&self, writer: &mut Writer) -> Result<(), write::Error> {
+        writer.class = self.e_class;
+        writer.endianness = self.e_data;
+        writer.write_slice(ELF_MAGIC);
+        writer.write_u8(self.e_class as u8);
+        writer.write_u8(self.e_data as u8);
+        writer.write_u8(1);
+        writer.write_u8(self.e_osabi.into());
+        writer.write_u8(self.e_abiversion);
+        writer.write_zeros(7);
+        writer.write_u16(self.e_type.into());
+        writer.write_u16(self.e_machine.into());
+        writer.write_u32(1);
+        writer.write_word(self.e_entry.into())?;
+        writer.write_word(self.e_phoff.into())?;
+        writer.write_word(self.e_shoff.into())?;
+        writer.write_u32(self.e_flags);
+        writer.write_u16(self.e_ehsize);
+        writer.write_u16(self.e_phentsize);
+        writer.write_u16(self.e_phnum);
+        writer.write_u16(self.e_shentsize);
+        writer.write_u16(self.e_shnum);
+        writer.write_u16(self.e_shstrndx);
+        Ok(())
+    }
+
+    pub fn e_class(&self) -> Class {
+        self.e_class
+    }
+
+    pub fn e_data(&self) -> Endianness {
+        self.e_data
+    }
+
+    pub fn e_osabi(&self) -> OsAbi {
+        self.e_osabi
+    }
+
+    /// Returns a `Reader` over `bytes` that decodes fields with this file's class and byte order
+    pub fn reader<'a>(&self, bytes: &'a [u8]) -> Reader<'a> {
+        Reader::new(bytes, self.e_class, self.e_data)
+    }
+
+    pub fn e_phoff(&self) -> FileOffset {
+        self.e_phoff
+    }
+
+    pub fn e_shoff(&self) -> FileOffset {
+        self.e_shoff
+    }
+
+    pub fn e_phentsize(&self) -> u16 {
+        self.e_phentsize
+    }
+
+    pub fn e_shentsize(&self) -> u16 {
+        self.e_shentsize
+    }
+
+    pub fn e_phnum(&self) -> u16 {
+        self.e_phnum
+    }
+
+    pub fn e_shnum(&self) -> u16 {
+        self.e_shnum
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Appends the low `size` bytes of `value` to `bytes` in the given byte order
+    fn put(bytes: &mut Vec<u8>, value: u64, size: usize, endianness: Endianness) {
+        match endianness {
+            Endianness::Little => bytes.extend_from_slice(&value.to_le_bytes()[..size]),
+            Endianness::Big => bytes.extend_from_slice(&value.to_be_bytes()[8 - size..]),
+        }
+    }
+
+    /// Builds a minimal executable with a single `PtLoad` segment spanning the whole file
+    fn exec_fixture(class: Class, endianness: Endianness) -> Vec<u8> {
+        let (ehsize, phentsize) = match class {
+            Class::Elf32 => (52, 32),
+            Class::Elf64 => (64, 56),
+        };
+        let word = class.word_size();
+        let file_size = ehsize + phentsize;
+        let mut bytes = vec![];
+
+        bytes.extend_from_slice(ELF_MAGIC);
+        bytes.extend_from_slice(&[class as u8, endianness as u8, 1, 0]);
+        bytes.extend_from_slice(&[0; 8]);
+        put(&mut bytes, 2, 2, endianness);
+        put(&mut bytes, 0x3E, 2, endianness);
+        put(&mut bytes, 1, 4, endianness);
+        put(&mut bytes, 0x401000, word, endianness);
+        put(&mut bytes, ehsize, word, endianness);
+        put(&mut bytes, 0, word, endianness);
+        put(&mut bytes, 0, 4, endianness);
+        put(&mut bytes, ehsize, 2, endianness);
+        put(&mut bytes, phentsize, 2, endianness);
+        put(&mut bytes, 1, 2, endianness);
+        bytes.extend_from_slice(&[0; 6]);
+
+        put(&mut bytes, 1, 4, endianness);
+        if class == Class::Elf64 {
+            put(&mut bytes, 5, 4, endianness);
+        }
+        put(&mut bytes, 0, word, endianness);
+        put(&mut bytes, 0x400000, word, endianness);
+        put(&mut bytes, 0x400000, word, endianness);
+        put(&mut bytes, file_size, word, endianness);
+        put(&mut bytes, file_size, word, endianness);
+        if class == Class::Elf32 {
+            put(&mut bytes, 5, 4, endianness);
+        }
+        put(&mut bytes, 0x1000, word, endianness);
+
+        bytes
+    }
+
+    /// Appends sections with the given names, types and contents to `bytes`, together with the
+    /// section name string table and the section header table. Only for 64-bit little endian.
+    fn with_sections(mut bytes: Vec<u8>, sections: &[(&str, u32, &[u8])]) -> Vec<u8> {
+        let mut shstrtab = vec![0];
+        // The first entry is always the null section
+        let mut headers = vec![vec![0; 64]];
+        let mut push_section = |bytes: &mut Vec<u8>, name: &str, sh_type: u32, data: &[u8]| {
+            let mut header = vec![];
+            header.extend_from_slice(&(shstrtab.len() as u32).to_le_bytes());
+            header.extend_from_slice(&sh_type.to_le_bytes());
+            header.extend_from_slice(&[0; 16]);
+            header.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            header.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            header.extend_from_slice(&[0; 8]);
+            header.extend_from_slice(&4u64.to_le_bytes());
+            header.extend_from_slice(&[0; 8]);
+            headers.push(header);
+            shstrtab.extend_from_slice(name.as_bytes());
+            shstrtab.push(0);
+            bytes.extend_from_slice(data);
+        };
+        for (name, sh_type, data) in sections {
+            push_section(&mut bytes, name, *sh_type, data);
+        }
+        // The name of the string table is in the table itself, so it has to be pushed before
+        // the contents are known
+        let shstrndx = headers.len() as u16;
+        let name_offset = shstrtab.len() as u32;
+        shstrtab.extend_from_slice(b".shstrtab\0");
+        let mut header = vec![];
+        header.extend_from_slice(&name_offset.to_le_bytes());
+        header.extend_from_slice(&3u32.to_le_bytes());
+        header.extend_from_slice(&[0; 16]);
+        header.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        header.extend_from_slice(&(shstrtab.len() as u64).to_le_bytes());
+        header.extend_from_slice(&[0; 24]);
+        headers.push(header);
+        bytes.extend_from_slice(&shstrtab);
+
+        let shoff = bytes.len() as u64;
+        for header in &headers {
+            bytes.extend_from_slice(header);
+        }
+        bytes[40..48].copy_from_slice(&shoff.to_le_bytes());
+        bytes[58..60].copy_from_slice(&64u16.to_le_bytes());
+        bytes[60..62].copy_from_slice(&(headers.len() as u16).to_le_bytes());
+        bytes[62..64].copy_from_slice(&shstrndx.to_le_bytes());
+        bytes
+    }
+
+    // Offsets of the fields of the 64-bit section and program headers
+    const SH_FLAGS: usize = 8;
+    const SH_LINK: usize = 40;
+    const SH_INFO: usize = 44;
+    const P_TYPE: usize = 0;
+    const P_OFFSET: usize = 8;
+    const P_FILESZ: usize = 32;
+    const P_MEMSZ: usize = 40;
+    const P_ALIGN: usize = 48;
+
+    /// Sets the field at offset `field` of the header of section `index` of the 64-bit little
+    /// endian file `bytes` to `value`, which is as large as the field
+    fn set_section_field(bytes: &mut [u8], index: usize, field: usize, value: &[u8]) {
+        let shoff = u64::from_le_bytes(bytes[40..48].try_into().unwrap()) as usize;
+        bytes[shoff + index * 64 + field..][..value.len()].copy_from_slice(value);
+    }
+
+    /// Sets the field at offset `field` of the header of segment `index`, like
+    /// `set_section_field`
+    fn set_segment_field(bytes: &mut [u8], index: usize, field: usize, value: &[u8]) {
+        let phoff = u64::from_le_bytes(bytes[32..40].try_into().unwrap()) as usize;
+        bytes[phoff + index * 56 + field..][..value.len()].copy_from_slice(value);
+    }
+
+    #[test]
+    fn elf_header() {
+        let bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        let mut reader = Reader::from_bytes(&bytes);
+        let elf_header = ElfHeader::parse(&mut reader).unwrap();
+        assert_eq!(elf_header.e_type, FileType::EtExec);
+        assert_eq!(elf_header.e_machine, Machine::AmdX86_64);
+        assert_eq!(elf_header.e_osabi, OsAbi::SysV);
+        assert_eq!(Addr(0x00401000), elf_header.e_entry);
+    }
+
+    #[test]
+    fn other_os_abi() {
+        let mut bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        // FreeBSD
+        bytes[7] = 9;
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.elf_header.e_osabi(), OsAbi::FreeBsd);
+    }
+
+    #[test]
+    fn elf() {
+        let bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        let elf = Elf64::parse(&bytes).unwrap();
+        println!("{:?}", elf);
+    }
+
+    #[test]
+    fn offsets_and_addresses() {
+        let mut bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        // Leave 0x80 bytes of zero filled memory past the file contents
+        set_segment_field(&mut bytes, 0, P_MEMSZ, &(120u64 + 0x80).to_le_bytes());
+        let elf = Elf64::parse(&bytes).unwrap();
+        let ph = &elf.ph_table[0];
+        assert_eq!(ph.p_offset(), FileOffset(0));
+        assert_eq!(ph.p_filesz(), Size(120));
+        assert_eq!(ph.file_range(), FileOffset(0)..FileOffset(120));
+        assert_eq!(ph.offset_to_addr(FileOffset(0x40)), Some(Addr(0x400040)));
+        assert_eq!(ph.offset_to_addr(FileOffset(120)), None);
+        assert_eq!(ph.addr_to_offset(Addr(0x400040)), Some(FileOffset(0x40)));
+        assert_eq!(ph.addr_to_offset(Addr(0x400080)), None);
+        assert_eq!(ph.addr_to_offset(Addr(0x3fffff)), None);
+        assert_eq!(u64::from(FileOffset(7) + Size(3)), 10);
+        assert_eq!(FileOffset(u64::MAX) + Size(1), FileOffset(u64::MAX));
+    }
+
+    #[test]
+    fn write_round_trip() {
+        for class in [Class::Elf32, Class::Elf64] {
+            for endianness in [Endianness::Little, Endianness::Big] {
+                let bytes = exec_fixture(class, endianness);
+                assert_eq!(Elf64::parse(&bytes).unwrap().to_bytes().unwrap(), bytes);
+            }
+        }
+        let bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        let bytes = with_sections(bytes, &[(".text", 1, &[0xc3; 5]), (".bss", 8, &[])]);
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.to_bytes().unwrap(), bytes);
+
+        // Changes to the headers and contents make it to the new file
+        elf.elf_header.e_entry = Addr(0x400078);
+        elf.sh_table[1].data = Cow::Owned(vec![0x90; 5]);
+        let written = elf.to_bytes().unwrap();
+        let elf = Elf64::parse(&written).unwrap();
+        assert_eq!(elf.elf_header.e_entry, Addr(0x400078));
+        assert_eq!(&elf.section_by_name(".text").unwrap().data[..], &[0x90; 5]);
+
+        // Bytes nothing claims, like a signature appended to the file, are written back too
+        let mut signed = bytes.clone();
+        signed.extend_from_slice(b"\x01SIGNATURE");
+        let mut elf = Elf64::parse(&signed).unwrap();
+        assert_eq!(elf.to_bytes().unwrap(), signed);
+        elf.elf_header.e_entry = Addr(0x400078);
+        assert!(elf.to_bytes().unwrap().ends_with(b"\x01SIGNATURE"));
+        let options = ParseOptions { keep_unclaimed: true, ..Default::default() };
+        let source = Elf64::parse_source(&signed[..], options).unwrap();
+        assert_eq!(source.to_bytes().unwrap(), signed);
+        // Sources only read what they need unless asked to
+        let source = Elf64::parse_source(&signed[..], ParseOptions::default()).unwrap();
+        assert!(!source.to_bytes().unwrap().ends_with(b"\x01SIGNATURE"));
+
+        // Contents which were never loaded cannot be written
+        let elf = Elf64::parse_headers(&bytes).unwrap();
+        assert!(matches!(elf.to_bytes(), Err(write::Error::SizeMismatch { .. })));
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn write_round_trip_binary() {
+        let bytes = test_binary();
+        assert!(Elf64::parse(&bytes).unwrap().to_bytes().unwrap() == bytes);
+    }
+
+    #[test]
+    fn elf_builder() {
+        let code = [0xb8, 0x3c, 0, 0, 0, 0x31, 0xff, 0x0f, 0x05];
+        let data = [1, 2, 3, 4];
+        for class in [Class::Elf32, Class::Elf64] {
+            let bytes = ElfBuilder::new(Machine::AmdX86_64)
+                .class(class)
+                .entry(Addr(0x401000))
+                .add_load_segment(Addr(0x401000), SegmentFlags::READ | SegmentFlags::EXEC, &code)
+                .add_load_segment_with_size(Addr(0x402010), SegmentFlags::READ, &data, Size(0x20))
+                .build()
+                .unwrap();
+            let elf = Elf64::parse(&bytes).unwrap();
+            assert_eq!(elf.elf_header.e_class(), class);
+            assert_eq!(elf.elf_header.e_entry, Addr(0x401000));
+            assert_eq!(elf.object_kind(), ObjectKind::StaticExecutable);
+            assert_eq!(elf.ph_table.len(), 2);
+            for ph in elf.ph_table.iter() {
+                assert_eq!(ph.p_offset().0 % 0x1000, ph.p_vaddr().0 % 0x1000);
+            }
+            assert_eq!(elf.slice_at(Addr(0x401000)).unwrap(), &code);
+            assert_eq!(elf.segment_at(Addr(0x40202f)).unwrap().p_memsz(), Size(0x20));
+            assert_eq!(elf.to_bytes().unwrap(), bytes);
+        }
+        let too_far = ElfBuilder::new(Machine::X86).class(Class::Elf32).entry(Addr(1 << 32));
+        assert!(matches!(too_far.build(), Err(write::Error::WordTooLarge(_))));
+    }
+
+    #[test]
+    fn flat_binary() {
+        let code = [0xc3; 9];
+        let data = [1, 2, 3, 4];
+        let elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x401000), SegmentFlags::READ | SegmentFlags::EXEC, &code)
+            .add_load_segment_with_size(Addr(0x402010), SegmentFlags::READ, &data, Size(0x20))
+            .build_elf();
+        let image = elf.flat_binary().unwrap();
+        // Zeros fill the gap between the segments, but not the end of the second one
+        assert_eq!(image.len(), 0x1014);
+        assert_eq!(&image[..9], &code);
+        assert!(image[9..0x1010].iter().all(|&byte| byte == 0));
+        assert_eq!(&image[0x1010..], &data);
+        assert!(ElfBuilder::new(Machine::AmdX86_64).build_elf().flat_binary().unwrap().is_empty());
+
+        let bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        let bytes = with_sections(bytes, &[(".text", 1, &[0xc3; 5])]);
+        let elf = Elf64::parse(&bytes).unwrap();
+        let mut out = vec![];
+        elf.dump_section(".text", &mut out).unwrap();
+        assert_eq!(out, [0xc3; 5]);
+        assert!(matches!(elf.dump_section(".data", &mut out), Err(write::Error::NoSection(_))));
+    }
+
+    #[test]
+    fn loadable_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        let hashes = |data: &[u8]| {
+            let elf = ElfBuilder::new(Machine::AmdX86_64)
+                .add_load_segment(Addr(0x401000), SegmentFlags::READ | SegmentFlags::EXEC, &[0xc3])
+                .add_load_segment(Addr(0x402000), SegmentFlags::READ, data)
+                .build_elf();
+            (elf.text_hash(&mut DefaultHasher::new()), elf.loadable_hash(&mut DefaultHasher::new()))
+        };
+        let (text, loadable) = hashes(&[1, 2]);
+        assert_eq!(hashes(&[1, 2]), (text, loadable));
+        let (other_text, other_loadable) = hashes(&[1, 3]);
+        assert_eq!(other_text, text);
+        assert_ne!(other_loadable, loadable);
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn loadable_hash_ignores_strip() {
+        use std::collections::hash_map::DefaultHasher;
+        let bytes = test_binary();
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        let hash = elf.loadable_hash(&mut DefaultHasher::new());
+        elf.strip(StripOptions::default()).unwrap();
+        let written = elf.to_bytes().unwrap();
+        let elf = Elf64::parse(&written).unwrap();
+        assert_eq!(elf.loadable_hash(&mut DefaultHasher::new()), hash);
+    }
+
+    #[cfg(all(feature = "loader", unix))]
+    #[test]
+    fn loaded_image() {
+        // mov eax, 42; ret
+        let code = [0xb8, 0x2a, 0, 0, 0, 0xc3];
+        let data = [1, 2, 3, 4];
+        let elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x401000), SegmentFlags::READ | SegmentFlags::EXEC, &code)
+            .add_load_segment_with_size(Addr(0x402ffe), SegmentFlags::READ, &data, Size(0x10))
+            .build_elf();
+        let image = loader::LoadedImage::load(&elf).unwrap();
+        // Safety: `sysconf` has no preconditions
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        assert_eq!(image.bias() % page as u64, 0);
+        let end = (0x40300e + page - 1) & !(page - 1);
+        assert_eq!(image.size(), end - (0x401000 & !(page - 1)));
+        assert_eq!(image.slice_at(Addr(0x401000)).unwrap(), &code);
+        // The tail past the file contents crosses a page and is zeroed
+        let bss = image.slice_at(Addr(0x402ffe)).unwrap();
+        assert_eq!(bss, &[1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(image.slice_at(Addr(0x401800)).is_none());
+        assert!(image.ptr(Addr(0x400000)).is_none());
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let entry = image.ptr(Addr(0x401000)).unwrap();
+            // Safety: the segment is executable and holds a function returning 42
+            let function: extern "C" fn() -> u32 = unsafe { std::mem::transmute(entry) };
+            assert_eq!(function(), 42);
+        }
+
+        // The page shared with an inaccessible segment takes its permissions
+        let elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x1000), SegmentFlags::READ, &data)
+            .add_load_segment(Addr(0x1800), SegmentFlags::empty(), &data)
+            .build_elf();
+        let image = loader::LoadedImage::load(&elf).unwrap();
+        assert!(image.slice_at(Addr(0x1000)).is_none());
+
+        // File contents larger than the memory of their segment do not fit in it
+        let mut elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x1000), SegmentFlags::READ, &[0; 0x3000])
+            .build_elf();
+        elf.ph_table[0].p_memsz = Size(0x10);
+        let overflowing = loader::LoadedImage::load(&elf);
+        assert!(matches!(overflowing, Err(loader::Error::BadSegment(Addr(0x1000)))));
+
+        let elf = ElfBuilder::new(Machine::AmdX86_64).build_elf();
+        let empty = loader::LoadedImage::load(&elf);
+        assert!(matches!(empty, Err(loader::Error::NoLoadableSegments)));
+    }
+
+    #[cfg(all(feature = "loader", target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn relocate_loaded_image() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let mut image = loader::LoadedImage::load(&elf).unwrap();
+        let imported = 0x1234_5678;
+        let resolve = |_: &str, sym: &SymbolEntry| {
+            (sym.st_shndx() == sym::SHN_UNDEF).then_some(imported)
+        };
+        // Safety: the file has no `COPY` relocations
+        unsafe { image.relocate(&elf, image.bias(), resolve).unwrap() };
+
+        let symbols = elf.dynamic_symbols().unwrap();
+        let read = |addr: Addr| {
+            let bytes = image.slice_at(addr).unwrap();
+            u64::from_le_bytes(bytes[..8].try_into().unwrap())
+        };
+        for relocation in elf.relocations().unwrap() {
+            let value = read(relocation.r_offset);
+            let sym = &symbols[relocation.r_sym as usize];
+            if relocation.r_type.is_relative() {
+                assert_eq!(value, image.bias() + relocation.r_addend.unwrap() as u64);
+            } else if sym.st_shndx() == sym::SHN_UNDEF {
+                assert_eq!(value, imported);
+            } else {
+                assert_eq!(value, image.bias() + sym.st_value().0);
+            }
+        }
+
+        // A read-only range past the end of its segment is cut at the end of the segment
+        let mut elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x1000), SegmentFlags::READ | SegmentFlags::WRITE, &[0; 8])
+            .build_elf();
+        elf.ph_table.push(ProgramHeader {
+            p_type: SegmentType::PtGnuRelro,
+            p_flags: SegmentFlags::READ,
+            p_offset: elf.ph_table[0].p_offset,
+            p_vaddr: Addr(0x1000),
+            p_paddr: Addr(0x1000),
+            p_filesz: Size(8),
+            p_memsz: Size(0x10_0000),
+            p_align: 1,
+            data: Cow::Borrowed(&[]),
+            contents: SegmentContents::Unknown,
+        });
+        let mut image = loader::LoadedImage::load(&elf).unwrap();
+        unsafe { image.relocate(&elf, image.bias(), |_, _| None).unwrap() };
+        assert_eq!(image.slice_at(Addr(0x1000)).unwrap(), &[0; 8]);
+
+        let elf = ElfBuilder::new(Machine::AArch64)
+            .add_load_segment(Addr(0x1000), SegmentFlags::READ, &[0; 8])
+            .build_elf();
+        let mut image = loader::LoadedImage::load(&elf).unwrap();
+        let unsupported = unsafe { image.relocate(&elf, 0, |_, _| None) };
+        assert!(matches!(unsupported, Err(loader::Error::UnsupportedMachine(Machine::AArch64))));
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex_export() {
+        let data: Vec<u8> = (1..=20).collect();
+        let elf = ElfBuilder::new(Machine::Arm)
+            .class(Class::Elf32)
+            .entry(Addr(0x1fff8))
+            .add_load_segment(Addr(0x1fff8), SegmentFlags::READ | SegmentFlags::EXEC, &data)
+            .build_elf();
+        // Data records stop at the 64KiB boundary, where the upper address bits change
+        let lines = [
+            ":020000040001F9",
+            ":08FFF8000102030405060708DD",
+            ":020000040002F8",
+            ":0C000000090A0B0C0D0E0F101112131446",
+            ":040000050001FFF8FF",
+            ":00000001FF",
+        ];
+        assert_eq!(elf.to_intel_hex().unwrap(), lines.map(|line| format!("{line}\r\n")).concat());
+        let lines = [
+            "S0030000FC",
+            "S21401FFF80102030405060708090A0B0C0D0E0F106B",
+            "S20802000811121314A3",
+            "S80401FFF803",
+        ];
+        assert_eq!(elf.to_srec().unwrap(), lines.map(|line| format!("{line}\r\n")).concat());
+
+        let elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0xffff_fff0), SegmentFlags::READ, &data)
+            .build_elf();
+        assert!(matches!(elf.to_intel_hex(), Err(hex::Error::AddressTooLarge(_))));
+
+        // A segment ending at the last address, like a reset vector
+        let elf = ElfBuilder::new(Machine::Arm)
+            .class(Class::Elf32)
+            .add_load_segment(Addr(0xffff_fff0), SegmentFlags::READ, &data[..16])
+            .build_elf();
+        let hex = elf.to_intel_hex().unwrap();
+        assert!(hex.starts_with(":02000004FFFFFC\r\n:10FFF000"));
+        assert!(elf.to_srec().unwrap().contains("S315FFFFFFF0"));
+    }
+
+    #[test]
+    fn tls_layout() {
+        let template = TlsTemplate {
+            image: &[1, 2, 3],
+            vaddr: Addr(0x2004),
+            file_size: 3,
+            mem_size: 0x14,
+            align: 16,
+        };
+        // The block starts 4 bytes past an aligned address, like its image
+        let layout = template.static_layout(Machine::AmdX86_64).unwrap();
+        assert_eq!(layout, StaticTlsLayout { variant: TlsVariant::II, offset: -0x1c });
+        let layout = template.static_layout(Machine::AArch64).unwrap();
+        assert_eq!(layout.offset, 0x14);
+        assert_eq!(template.static_layout(Machine::RiscV).unwrap().offset, 4);
+        assert!(template.static_layout(Machine::Sparc).is_none());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn tls_template() {
+        use std::cell::Cell;
+        std::thread_local! {
+            static MARK: Cell<u64> = const { Cell::new(0x1122_3344_5566_7788) };
+        }
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let template = elf.tls_template().unwrap();
+        assert_eq!(template.image.len() as u64, template.file_size);
+        assert!(template.mem_size >= template.file_size);
+
+        // The block of the executable is where the thread local variables of this test are
+        let layout = template.static_layout(elf.elf_header.e_machine).unwrap();
+        let thread_pointer: u64;
+        // Safety: `fs:0` holds the thread pointer itself on x86-64 Linux
+        unsafe { std::arch::asm!("mov {}, fs:0", out(reg) thread_pointer) };
+        let start = thread_pointer.wrapping_add(layout.offset as u64);
+        let mark = MARK.with(|mark| mark.as_ptr() as u64);
+        assert!((start..start + template.mem_size).contains(&mark));
+        let offset = (mark - start) as usize;
+        assert_eq!(template.image[offset..offset + 8], 0x1122_3344_5566_7788u64.to_le_bytes());
+    }
+
+    #[test]
+    fn gnu_properties() {
+        let mut note = vec![];
+        let properties: [(u32, u32); 3] = [
+            (note::GNU_PROPERTY_X86_FEATURE_1_AND, 3),
+            (note::GNU_PROPERTY_X86_ISA_1_NEEDED, 3),
+            (0xc000_0010, 7),
+        ];
+        for (pr_type, value) in properties {
+            for word in [pr_type, 4, value, 0] {
+                note.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+        let header = [4, note.len() as u32, note::NT_GNU_PROPERTY_TYPE_0];
+        let mut data: Vec<u8> = header.iter().flat_map(|word| word.to_le_bytes()).collect();
+        data.extend_from_slice(b"GNU\0");
+        data.extend_from_slice(&note);
+
+        let bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        let bytes = with_sections(bytes, &[(".note.gnu.property", 7, &data)]);
+        let elf = Elf64::parse(&bytes).unwrap();
+        let properties = elf.gnu_properties().unwrap().unwrap();
+        assert_eq!(
+            properties,
+            [
+                GnuProperty::X86Feature1And(X86Feature1::IBT | X86Feature1::SHSTK),
+                GnuProperty::X86Isa1Needed(X86Isa1::BASELINE | X86Isa1::V2),
+                GnuProperty::Other { pr_type: 0xc000_0010, data: &[7, 0, 0, 0] },
+            ]
+        );
+        assert_eq!(elf.x86_features(), X86Feature1::IBT | X86Feature1::SHSTK);
+
+        // Processor specific properties mean something else on other machines
+        let mut reader = Reader::new(&note, Class::Elf64, Endianness::Little);
+        let properties = GnuProperty::parse_all(&mut reader, Machine::AArch64).unwrap();
+        assert!(matches!(properties[0], GnuProperty::Other { pr_type: 0xc000_0002, .. }));
+    }
+
+    #[test]
+    fn patch_entry() {
+        for class in [Class::Elf32, Class::Elf64] {
+            for endianness in [Endianness::Little, Endianness::Big] {
+                let bytes = exec_fixture(class, endianness);
+                let mut elf = Elf64::parse(&bytes).unwrap();
+                elf.set_entry(Addr(0x400040));
+                let mut patched = bytes.clone();
+                elf.patch_entry(&mut patched).unwrap();
+                assert_eq!(patched, elf.to_bytes().unwrap());
+                let mut changed = (0..bytes.len()).filter(|&i| bytes[i] != patched[i]);
+                assert!(changed.all(|i| (24..24 + class.word_size()).contains(&i)));
+                assert_eq!(Elf64::parse(&patched).unwrap().elf_header.e_entry, Addr(0x400040));
+            }
+        }
+        let bytes = exec_fixture(Class::Elf32, Endianness::Little);
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        let mut short = bytes[..26].to_vec();
+        assert!(matches!(
+            elf.patch_entry(&mut short),
+            Err(write::Error::PatchOutOfBounds { offset: 24, size: 4 })
+        ));
+        assert_eq!(short, &bytes[..26]);
+        elf.set_entry(Addr(1 << 32));
+        assert!(matches!(elf.patch_entry(&mut bytes.clone()), Err(write::Error::WordTooLarge(_))));
+    }
+
+    #[test]
+    fn add_load_segment() {
+        let bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        // The fixture is loaded in the page at 0x400000
+        assert!(matches!(
+            elf.add_load_segment(Addr(0x400800), SegmentFlags::READ, b"payload"),
+            Err(write::Error::BadSegmentAddr(Addr(0x400800)))
+        ));
+        let at = elf.add_load_segment(Addr(0x600000), SegmentFlags::EXEC, b"payload").unwrap();
+        assert_eq!(at, Addr(0x600000 + 2 * 56));
+
+        let written = elf.to_bytes().unwrap();
+        let elf = Elf64::parse(&written).unwrap();
+        assert_eq!(elf.elf_header.e_phnum(), 2);
+        assert_eq!(elf.elf_header.e_phoff(), FileOffset(0x1000));
+        let ph = &elf.ph_table[1];
+        assert_eq!(ph.p_flags(), SegmentFlags::READ | SegmentFlags::EXEC);
+        assert_eq!(ph.offset_to_addr(FileOffset(0x1000)), Some(Addr(0x600000)));
+        assert_eq!(elf.slice_at(at).unwrap(), b"payload");
+        // The table in the new segment is the one the header points to
+        let mut reader = elf.elf_header.reader(elf.slice_at(Addr(0x600000)).unwrap());
+        reader.seek(56).unwrap();
+        assert_eq!(ProgramHeader::parse_header(&mut reader).unwrap().p_vaddr(), Addr(0x600000));
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn runpath_editing() {
+        let bytes = test_binary();
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        let needed = elf.needed_libraries().unwrap();
+        let runpath = |elf: &Elf64| -> Vec<_> {
+            elf.dynamic_entry_strings(DynamicTag::RunPath).collect()
+        };
+        assert!(runpath(&elf).is_empty());
+
+        // The dynamic table of the test binary has no spare entry, so both tables move
+        elf.set_runpath("$ORIGIN/../lib:/opt/lib").unwrap();
+        let written = elf.to_bytes().unwrap();
+        let mut elf = Elf64::parse(&written).unwrap();
+        assert_eq!(runpath(&elf), ["$ORIGIN/../lib:/opt/lib"]);
+        assert_eq!(elf.needed_libraries().unwrap(), needed);
+        let added = elf.ph_table.last().unwrap().p_vaddr();
+        let dynamic = elf.segment_of_type(SegmentType::PtDynamic).unwrap().p_vaddr();
+        assert_eq!(elf.segment_at(dynamic).unwrap().p_vaddr(), added);
+        assert!(elf.section_by_name(".dynstr").unwrap().sh_addr() > added);
+        assert!(elf.dynamic_symbols().is_ok());
+
+        // A shorter path takes the place of the old one
+        let phnum = elf.ph_table.len();
+        elf.set_runpath("/opt/lib").unwrap();
+        assert_eq!(runpath(&elf), ["/opt/lib"]);
+        assert_eq!(elf.ph_table.len(), phnum);
+
+        elf.remove_rpath().unwrap();
+        let written = elf.to_bytes().unwrap();
+        let elf = Elf64::parse(&written).unwrap();
+        assert!(runpath(&elf).is_empty());
+        assert_eq!(elf.needed_libraries().unwrap(), needed);
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn needed_editing() {
+        let bytes = test_binary();
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        let mut needed = elf.needed_libraries().unwrap();
+        elf.add_needed("libgrafted.so.1").unwrap();
+        let phnum = elf.ph_table.len();
+        // Adding it twice changes nothing
+        elf.add_needed("libgrafted.so.1").unwrap();
+        assert_eq!(elf.ph_table.len(), phnum);
+
+        let written = elf.to_bytes().unwrap();
+        let mut elf = Elf64::parse(&written).unwrap();
+        needed.push("libgrafted.so.1".into());
+        assert_eq!(elf.needed_libraries().unwrap(), needed);
+
+        elf.remove_needed(&needed.remove(0)).unwrap();
+        let written = elf.to_bytes().unwrap();
+        let elf = Elf64::parse(&written).unwrap();
+        assert_eq!(elf.needed_libraries().unwrap(), needed);
+    }
+
+    #[test]
+    fn segment_flags() {
+        for class in [Class::Elf32, Class::Elf64] {
+            for endianness in [Endianness::Little, Endianness::Big] {
+                let bytes = exec_fixture(class, endianness);
+                let mut elf = Elf64::parse(&bytes).unwrap();
+                elf.set_segment_flags(0, SegmentFlags::READ).unwrap();
+                let mut patched = bytes.clone();
+                elf.patch_segment_flags(0, &mut patched).unwrap();
+                assert_eq!(patched, elf.to_bytes().unwrap());
+                let elf = Elf64::parse(&patched).unwrap();
+                assert_eq!(elf.ph_table[0].p_flags(), SegmentFlags::READ);
+            }
+        }
+        let mut elf = ElfBuilder::new(Machine::AmdX86_64).build_elf();
+        let flags = elf.set_segment_flags(0, SegmentFlags::READ);
+        assert!(matches!(flags, Err(write::Error::NoSegment(0))));
+    }
+
+    #[test]
+    fn gnu_segment_types() {
+        let types = [
+            (PT_GNU_EH_FRAME, SegmentType::PtGnuEhFrame),
+            (PT_GNU_STACK, SegmentType::PtGnuStack),
+            (PT_GNU_RELRO, SegmentType::PtGnuRelro),
+            (PT_GNU_PROPERTY, SegmentType::PtGnuProperty),
+            (PT_GNU_SFRAME, SegmentType::PtOsSpecific(PT_GNU_SFRAME)),
+        ];
+        for (value, p_type) in types {
+            assert_eq!(SegmentType::try_from(value).unwrap(), p_type);
+            assert_eq!(u32::from(p_type), value);
+        }
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn gnu_stack_and_relro() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.gnu_stack(), Some(SegmentFlags::READ | SegmentFlags::WRITE));
+        let relro = elf.gnu_relro_range().unwrap();
+        let load = elf.segment_at(relro.start).unwrap();
+        assert!(load.p_flags().contains(SegmentFlags::WRITE));
+        assert!(relro.end <= load.mem_range().end);
+        assert!(ElfBuilder::new(Machine::AmdX86_64).build_elf().gnu_stack().is_none());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn relro_level() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        // Test binaries are linked with `-z now`
+        assert_eq!(elf.relro_level(), RelroLevel::Full);
+        let plain = ElfBuilder::new(Machine::AmdX86_64).build_elf();
+        assert_eq!(plain.relro_level(), RelroLevel::None);
+    }
+
+    #[test]
+    fn wx_audit() {
+        let rwx = SegmentFlags::all();
+        let elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x401000), SegmentFlags::READ | SegmentFlags::EXEC, &[0xc3])
+            .add_load_segment(Addr(0x402000), rwx, &[0xc3])
+            .build_elf();
+        let wx = elf.wx_segments();
+        assert_eq!(wx.len(), 1);
+        assert_eq!(wx[0].p_vaddr(), Addr(0x402000));
+        // Without `PtGnuStack` the stack is executable
+        assert!(elf.has_executable_stack());
+        assert!(!ElfBuilder::new(Machine::AmdX86_64).build_elf().has_executable_stack());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn wx_audit_binary() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert!(!elf.has_executable_stack());
+        assert!(elf.wx_segments().is_empty());
+    }
+
+    #[test]
+    fn validate_segments() {
+        let flags = SegmentFlags::READ;
+        let mut elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x401000), flags, &[1; 16])
+            .add_load_segment(Addr(0x402000), flags, &[2; 16])
+            .build_elf();
+        let size = elf.to_bytes().unwrap().len() as u64;
+        assert!(elf.validate_segments(size).is_empty());
+
+        elf.ph_table[0].p_memsz = Size(0x1010);
+        elf.ph_table[1].p_vaddr = Addr(0x402008);
+        let end = elf.ph_table[1].file_range().end;
+        let issues = elf.validate_segments(end.0 - 1);
+        let issues: Vec<_> = issues.into_iter().map(|diagnostic| diagnostic.issue).collect();
+        let offset = elf.ph_table[1].p_offset;
+        assert_eq!(issues, [
+            SegmentIssue::PastEndOfFile { end },
+            SegmentIssue::Misaligned { vaddr: Addr(0x402008), offset, align: 0x1000 },
+            SegmentIssue::OverlappingLoad { other: 0 },
+        ]);
+    }
+
+    #[test]
+    fn file_map() {
+        let elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x401000), SegmentFlags::READ, &[1; 16])
+            .build_elf();
+        let mut bytes = elf.to_bytes().unwrap();
+        let size = bytes.len() as u64;
+        // Data appended to the file is claimed by nothing
+        bytes.extend_from_slice(b"hidden");
+        let elf = Elf64::parse(&bytes).unwrap();
+        let map = elf.file_map(bytes.len() as u64);
+        assert_eq!(map[0].range, FileOffset(0)..FileOffset(64));
+        assert_eq!(map[0].region, FileRegion::ElfHeader);
+        let segment = map.iter().find(|entry| entry.region == FileRegion::Segment(0)).unwrap();
+        assert_eq!(segment.range, elf.ph_table[0].file_range());
+        assert!(map.iter().any(|entry| entry.region == FileRegion::ProgramHeaderTable));
+        let last = map.last().unwrap();
+        assert_eq!(last.region, FileRegion::Gap);
+        assert_eq!(last.range, FileOffset(size)..FileOffset(size + 6));
+    }
+
+    #[test]
+    fn find_caves() {
+        let mut code = [0; 0x100];
+        code[..0x40].fill(0xc3);
+        let flags = SegmentFlags::READ | SegmentFlags::EXEC;
+        let mut elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x401000), flags, &code)
+            .add_load_segment(Addr(0x402000), SegmentFlags::READ, &[0; 0x100])
+            .build_elf();
+        // Only the start of the segment is in a section. The rest of it, and the rest of its
+        // page, are padding.
+        let offset = elf.ph_table[0].p_offset;
+        let text = SectionHeader::new(0, SectionType::ShtProgbits, offset, code[..0x40].into());
+        let null = SectionHeader::new(0, SectionType::ShtNull, FileOffset(0), Cow::Borrowed(&[]));
+        elf.sh_table = vec![null, text];
+        let caves = elf.find_caves(0x80);
+        assert_eq!(caves.len(), 1);
+        assert_eq!(caves[0].segment, 0);
+        assert_eq!(caves[0].file_range, offset + Size(0x40)..offset + Size(0x1000));
+        assert_eq!(caves[0].mem_range, Addr(0x401040)..Addr(0x402000));
+        assert_eq!(caves[0].flags, flags);
+        assert!(elf.find_caves(0x1000).is_empty());
+    }
+
+    #[test]
+    fn entropy() {
+        let all_bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(entropy::entropy(&[]), 0.0);
+        assert_eq!(entropy::entropy(&[7; 100]), 0.0);
+        assert_eq!(entropy::entropy(&[0, 1, 0, 1]), 1.0);
+        assert_eq!(entropy::entropy(&all_bytes), 8.0);
+        let data = [0, 0, 0, 0, 1, 1, 2, 3];
+        assert_eq!(entropy::window_entropy(&data, 4, 2), [0.0, 1.0, 1.5]);
+        assert_eq!(entropy::window_entropy(&data, 2, 3), [0.0, 1.0, 1.0]);
+        assert!(entropy::window_entropy(&data, 9, 1).is_empty());
+
+        let elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x401000), SegmentFlags::READ, &all_bytes)
+            .build_elf();
+        assert_eq!(elf.ph_table[0].entropy(), 8.0);
+    }
+
+    #[test]
+    fn detect_packer() {
+        let packed: Vec<u8> = (0..=255).cycle().take(0x1000).collect();
+        let elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x401000), SegmentFlags::all(), &packed)
+            .build_elf();
+        let mut bytes = elf.to_bytes().unwrap();
+        let PackerInfo::Suspicious(anomalies) = elf.detect_packer(&bytes) else { panic!() };
+        assert_eq!(anomalies, [
+            PackerAnomaly::NoSectionHeaders,
+            PackerAnomaly::WritableCode(0),
+            PackerAnomaly::HighEntropy { index: 0, entropy: 8.0 },
+        ]);
+
+        // UPX follows the program headers with a checksum and its magic
+        let info = 64 + 56 + 4;
+        bytes[info..info + 4].copy_from_slice(b"UPX!");
+        assert_eq!(elf.detect_packer(&bytes), PackerInfo::Upx { version: None });
+        let id_start = bytes.len();
+        bytes.extend_from_slice(b"$Id: UPX 4.22 Copyright (C) 1996-2024 the UPX Team. $");
+        let version = Some("4.22".to_string());
+        assert_eq!(elf.detect_packer(&bytes), PackerInfo::Upx { version });
+        // A version running to the end of the file is kept whole
+        bytes.truncate(id_start + 13);
+        let version = Some("4.22".to_string());
+        assert_eq!(elf.detect_packer(&bytes), PackerInfo::Upx { version });
+
+        // Other packers are known by the strings of their stubs or their section names
+        let mut stub = packed.clone();
+        stub[0x100..0x10C].copy_from_slice(b"TEEE burneye");
+        let elf = ElfBuilder::new(Machine::AmdX86_64)
+            .add_load_segment(Addr(0x401000), SegmentFlags::all(), &stub)
+            .build_elf();
+        let bytes = elf.to_bytes().unwrap();
+        assert_eq!(elf.detect_packer(&bytes), PackerInfo::Known { name: "Burneye" });
+        let bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".MPRESS1", 1, &[0; 16]), (".MPRESS2", 1, &[0; 16])],
+        );
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.detect_packer(&bytes), PackerInfo::Known { name: "MPRESS" });
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn detect_packer_linked() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.detect_packer(&bytes), PackerInfo::NotPacked);
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn segment_flags_conflict() {
+        let bytes = test_binary();
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        let index_of = |elf: &Elf64, p_type| elf.ph_table.iter().position(|ph| ph.p_type == p_type);
+        let stack = index_of(&elf, SegmentType::PtGnuStack).unwrap();
+        elf.set_segment_flags(stack, SegmentFlags::READ | SegmentFlags::WRITE).unwrap();
+
+        // The dynamic table is writable, so the segment loading it has to stay writable too
+        let dynamic = index_of(&elf, SegmentType::PtDynamic).unwrap();
+        let addr = elf.ph_table[dynamic].p_vaddr();
+        let load = elf.ph_table.iter().position(|ph| ph.mem_range().contains(&addr)).unwrap();
+        let flags = elf.ph_table[load].p_flags();
+        let conflict = elf.set_segment_flags(load, SegmentFlags::READ);
+        assert!(matches!(
+            conflict,
+            Err(write::Error::SegmentFlagsConflict { inner, outer })
+                if inner == dynamic && outer == load
+        ));
+        assert_eq!(elf.ph_table[load].p_flags(), flags);
+        assert!(elf.set_segment_flags(dynamic, SegmentFlags::all()).is_err());
+        elf.set_segment_flags(dynamic, SegmentFlags::READ).unwrap();
+        elf.set_segment_flags(load, SegmentFlags::READ).unwrap();
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn strip() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let needed = elf.needed_libraries().unwrap();
+        // Names of the sections the dynamic symbols are defined in
+        let defined_in = |elf: &Elf64| -> Vec<Option<String>> {
+            let symbols = elf.dynamic_symbols().unwrap();
+            symbols
+                .iter()
+                .map(|sym| elf.sh_table.get(sym.st_shndx() as usize))
+                .map(|sh| sh.and_then(|sh| elf.section_name(sh)))
+                .collect()
+        };
+        let sections = defined_in(&elf);
+
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        elf.strip(StripOptions::default()).unwrap();
+        let written = elf.to_bytes().unwrap();
+        assert!(written.len() < bytes.len());
+        let elf = Elf64::parse(&written).unwrap();
+        assert!(elf.section_by_name(".symtab").is_none());
+        assert!(elf.section_by_name(".debug_info").is_none());
+        assert!(elf.section_by_name(".dynsym").is_some());
+        assert_eq!(elf.needed_libraries().unwrap(), needed);
+        assert_eq!(defined_in(&elf), sections);
+
+        let mut elf = ElfBuilder::new(Machine::AmdX86_64).file_type(FileType::EtRel).build_elf();
+        assert!(matches!(elf.strip(StripOptions::default()), Err(write::Error::StripRelocatable)));
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn diff() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert!(elf.diff(&elf).unwrap().is_empty());
+
+        let mut patched = Elf64::parse(&bytes).unwrap();
+        patched.set_interpreter("/lib/ld.so").unwrap();
+        patched.set_entry(Addr(0x1234));
+        let patched_bytes = patched.to_bytes().unwrap();
+        let patched = Elf64::parse(&patched_bytes).unwrap();
+        let diff = elf.diff(&patched).unwrap();
+        let old = format!("{:?}", elf.elf_header.e_entry);
+        let entry = FieldChange { field: "e_entry", old, new: "0x00001234".to_string() };
+        assert_eq!(diff.header, [entry]);
+        assert_eq!(diff.sections, [SectionChange::Modified(".interp".to_string())]);
+        assert!(diff.symbols.is_empty() && diff.dynamic_symbols.is_empty());
+        assert!(diff.dynamic.is_empty());
+
+        let mut stripped = Elf64::parse(&bytes).unwrap();
+        stripped.strip(StripOptions::default()).unwrap();
+        let stripped_bytes = stripped.to_bytes().unwrap();
+        let stripped = Elf64::parse(&stripped_bytes).unwrap();
+        let diff = elf.diff(&stripped).unwrap();
+        assert!(diff.sections.contains(&SectionChange::Removed(".symtab".to_string())));
+        assert!(diff.symbols.iter().any(|change| change.name == "main"));
+        assert!(diff.symbols.iter().all(|change| change.new.is_none()));
+        assert!(diff.dynamic_symbols.is_empty());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn entry_and_main() {
+        let bytes = test_binary();
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.entry_symbol(), Some(("_start".to_string(), 0)));
+        let main = elf.find_main().unwrap();
+        assert_eq!(elf.function_name_at(main).as_deref(), Some("main"));
+        // Without symbols, `main` is found through the call to `__libc_start_main`
+        elf.strip(StripOptions::default()).unwrap();
+        assert!(elf.symbols().unwrap().is_empty());
+        assert_eq!(elf.find_main(), Some(main));
+    }
+
+    #[test]
+    fn find_main_past_file_contents() {
+        // The entry is in the zeroed tail of its segment, which holds no code to scan
+        let flags = SegmentFlags::READ | SegmentFlags::EXEC;
+        let elf = ElfBuilder::new(Machine::AmdX86_64)
+            .entry(Addr(0x401080))
+            .add_load_segment_with_size(Addr(0x401000), flags, &[0xc3; 8], Size(0x100))
+            .build_elf();
+        assert_eq!(elf.find_main(), None);
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn set_interpreter() {
+        let bytes = test_binary();
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        let phnum = elf.ph_table.len();
+        elf.set_interpreter("/lib/ld.so").unwrap();
+        assert_eq!(elf.ph_table.len(), phnum);
+        let interp = elf.segment_of_type(SegmentType::PtInterp).unwrap();
+        assert!(matches!(&interp.contents, SegmentContents::Interp(path) if path == "/lib/ld.so"));
+        let written = elf.to_bytes().unwrap();
+        let elf = Elf64::parse(&written).unwrap();
+        assert_eq!(elf.interpreter().unwrap().unwrap(), "/lib/ld.so");
+        // The rest of the old path is zeroed out
+        assert_eq!(elf.section_by_name(".interp").unwrap().data.len(), 28);
+
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        let path = "/nix/store/0000000000000000-glibc/lib/ld-linux-x86-64.so.2";
+        elf.set_interpreter(path).unwrap();
+        assert_eq!(elf.interpreter().unwrap().unwrap(), path);
+        let written = elf.to_bytes().unwrap();
+        let elf = Elf64::parse(&written).unwrap();
+        assert_eq!(elf.interpreter().unwrap().unwrap(), path);
+        let interp = elf.segment_of_type(SegmentType::PtInterp).unwrap();
+        assert_eq!(interp.p_filesz().0, path.len() as u64 + 1);
+        let added = elf.ph_table[phnum].p_vaddr();
+        assert_eq!(elf.segment_at(interp.p_vaddr()).unwrap().p_vaddr(), added);
+        assert_eq!(elf.section_by_name(".interp").unwrap().sh_addr(), interp.p_vaddr());
+
+        let mut elf = ElfBuilder::new(Machine::AmdX86_64).build_elf();
+        assert!(matches!(elf.set_interpreter(path), Err(write::Error::NoInterpreter)));
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn add_load_segment_moves_phdr() {
+        let bytes = test_binary();
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        let phnum = elf.ph_table.len();
+        elf.add_load_segment(Addr(0x4000_0000), SegmentFlags::READ, &[0xcc; 16]).unwrap();
+        let written = elf.to_bytes().unwrap();
+        let elf = Elf64::parse(&written).unwrap();
+        let phdr = elf.segment_of_type(SegmentType::PtPhdr).unwrap();
+        assert_eq!(phdr.p_vaddr(), Addr(0x4000_0000));
+        assert_eq!(phdr.p_offset().0, elf.elf_header.e_phoff().0);
+        assert_eq!(phdr.p_filesz(), Size(56 * (phnum as u64 + 1)));
+        assert_eq!(elf.interpreter().unwrap().unwrap(), "/lib64/ld-linux-x86-64.so.2");
+    }
+
+    #[test]
+    fn add_section() {
+        let bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        let bytes = with_sections(bytes, &[(".text", 1, &[0xc3; 5])]);
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.add_section(".meta", SectionType::ShtNote, b"payload").unwrap(), 3);
+        let written = elf.to_bytes().unwrap();
+        let elf = Elf64::parse(&written).unwrap();
+        assert_eq!(elf.elf_header.e_shnum(), 4);
+        assert_eq!(elf.elf_header.e_shoff().0 % 8, 0);
+        let names: Vec<_> = elf.sections().map(|section| section.name().unwrap()).collect();
+        assert_eq!(names, ["", ".text", ".shstrtab", ".meta"]);
+        let meta = elf.section_by_name(".meta").unwrap();
+        assert_eq!(meta.sh_type(), SectionType::ShtNote);
+        assert_eq!(&meta.data[..], b"payload");
+        assert_eq!(&elf.section_by_name(".text").unwrap().data[..], &[0xc3; 5]);
+
+        // Files without sections get the null section and a section name string table
+        let mut elf = ElfBuilder::new(Machine::AmdX86_64).build_elf();
+        assert_eq!(elf.add_section(".meta", SectionType::ShtProgbits, b"payload").unwrap(), 2);
+        let written = elf.to_bytes().unwrap();
+        let elf = Elf64::parse(&written).unwrap();
+        let names: Vec<_> = elf.sections().map(|section| section.name().unwrap()).collect();
+        assert_eq!(names, ["", ".shstrtab", ".meta"]);
+        assert_eq!(&elf.section_by_name(".meta").unwrap().data[..], b"payload");
+    }
+
+    #[test]
+    fn file_types() {
+        let mut bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        bytes[16..18].copy_from_slice(&0xFE01u16.to_le_bytes());
+        assert_eq!(Elf64::parse(&bytes).unwrap().elf_header.e_type, FileType::OsSpecific(0xFE01));
+        bytes[16..18].copy_from_slice(&0xFF80u16.to_le_bytes());
+        assert_eq!(Elf64::parse(&bytes).unwrap().elf_header.e_type, FileType::ProcSpecific(0xFF80));
+
+        // Without an interpreter nor the PIE flag, shared objects are libraries
+        let mut bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        assert_eq!(Elf64::parse(&bytes).unwrap().object_kind(), ObjectKind::StaticExecutable);
+        bytes[16..18].copy_from_slice(&3u16.to_le_bytes());
+        assert_eq!(Elf64::parse(&bytes).unwrap().object_kind(), ObjectKind::SharedLibrary);
+    }
+
+    #[test]
+    fn machines() {
+        let mut bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        bytes[18..20].copy_from_slice(&0xB7u16.to_le_bytes());
+        assert_eq!(Elf64::parse(&bytes).unwrap().elf_header.e_machine, Machine::AArch64);
+        bytes[18..20].copy_from_slice(&0x1234u16.to_le_bytes());
+        assert_eq!(Elf64::parse(&bytes).unwrap().elf_header.e_machine, Machine::Other(0x1234));
+    }
+
+    #[test]
+    fn section_types() {
+        assert_eq!(SectionType::from(11), SectionType::ShtDynsym);
+        assert_eq!(SectionType::from(0x6FFF_FFF6), SectionType::ShtGnuHash);
+        assert_eq!(SectionType::from(0x6000_0001), SectionType::ShtOsSpecific(0x6000_0001));
+        assert_eq!(SectionType::from(0x7000_0003), SectionType::ShtProcSpecific(0x7000_0003));
+        assert_eq!(SectionType::from(0x1234), SectionType::ShtUnknown(0x1234));
+        assert_eq!(u32::from(SectionType::ShtGnuVersym), 0x6FFF_FFFF);
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn dynamic_strings() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let needed: Vec<_> = elf.dynamic_entry_strings(DynamicTag::Needed).collect();
+        assert!(needed.iter().any(|lib| lib == "libc.so.6"));
+        assert_eq!(elf.needed_libraries().unwrap(), needed);
+        assert_eq!(elf.soname().unwrap(), None);
+
+        let values = elf.dynamic_values().unwrap();
+        let value = |tag| values.iter().find(|(d_tag, _)| *d_tag == tag).map(|(_, value)| value);
+        assert_eq!(value(DynamicTag::Needed), Some(&DynamicValue::String(needed[0].clone())));
+        assert!(matches!(value(DynamicTag::SymTab), Some(DynamicValue::Address(_))));
+        assert_eq!(value(DynamicTag::SymEnt), Some(&DynamicValue::Bytes(24)));
+        assert_eq!(values.last(), Some(&(DynamicTag::Null, DynamicValue::Ignored)));
+        let dynstr = elf.dynamic_string_table().unwrap();
+        assert_eq!(dynstr.bytes(), &elf.section_by_name(".dynstr").unwrap().data[..]);
+        assert_eq!(elf.object_kind(), ObjectKind::Pie);
+        assert!(elf.is_pie());
+        // Rust links its executables as PIE with immediate binding
+        assert!(elf.dynamic_flags_1().contains(DynamicFlags1::PIE | DynamicFlags1::NOW));
+        assert!(!elf.dynamic_flags().contains(DynamicFlags::TEXTREL));
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn interpreter() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.interpreter().unwrap().unwrap(), "/lib64/ld-linux-x86-64.so.2");
+        let bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        assert!(Elf64::parse(&bytes).unwrap().interpreter().is_none());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn section_to_segment_mapping() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let mapping = elf.section_to_segment_mapping();
+        let names = |p_type| {
+            let index = elf.ph_table.iter().position(|ph| ph.p_type() == p_type).unwrap();
+            mapping[index].iter().map(|sh| sh.name().unwrap()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(SegmentType::PtPhdr), Vec::<&str>::new());
+        assert_eq!(names(SegmentType::PtInterp), [".interp"]);
+        assert_eq!(names(SegmentType::PtDynamic), [".dynamic"]);
+        assert!(names(SegmentType::PtTls).contains(&".tbss"));
+        // `.tbss` takes no memory outside of the TLS template
+        assert_eq!(mapping.iter().flatten().filter(|sh| sh.name() == Some(".tbss")).count(), 1);
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn init_fini_functions() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let init = elf.init_functions().unwrap();
+        let fini = elf.fini_functions().unwrap();
+        let init_array = elf.section_by_name(".init_array").unwrap();
+        assert_eq!(init.len() as u64, init_array.sh_size().0 / 8);
+        assert_eq!(fini.len(), 1);
+        // Every pointer lands in code, although the file only holds relocations for them
+        for addr in init.iter().chain(&fini) {
+            let section = elf.section_containing(*addr).unwrap();
+            assert!(section.sh_flags().contains(SectionFlags::EXECINSTR));
+        }
+        assert!(elf.function_name_at(fini[0]).is_some());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn abi_tag() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let abi_tag = elf.abi_tag().unwrap().unwrap();
+        assert_eq!(abi_tag.os, AbiTagOs::Linux);
+        assert!(abi_tag.kernel_version >= (2, 6, 0));
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn symbol_versions() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let requirements = elf.version_requirements().unwrap();
+        let libc = requirements.iter().find(|req| req.file == "libc.so.6").unwrap();
+        assert!(libc.versions.iter().any(|version| version.name == "GLIBC_2.2.5"));
+
+        let versions = elf.dynamic_symbol_versions().unwrap();
+        assert_eq!(versions.len(), elf.dynamic_symbols().unwrap().len());
+        // The reserved first symbol is local
+        assert_eq!(versions[0], None);
+        let names = elf.versioned_dynamic_symbol_names().unwrap();
+        assert!(names.iter().any(|name| name == "malloc@GLIBC_2.2.5"));
+
+        let imports = elf.imports().unwrap();
+        let malloc = imports.iter().find(|import| import.name == "malloc").unwrap();
+        assert_eq!(malloc.version.as_deref(), Some("GLIBC_2.2.5"));
+        assert!(malloc.library.as_deref().is_some_and(|file| file.starts_with("libc.so")));
+        // Executables do not export their functions unless asked to
+        assert!(elf.exports().unwrap().is_empty());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn required_symbol_versions() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let required = elf.required_symbol_versions().unwrap();
+        let glibc = required.iter().find(|version| version.interface == "GLIBC").unwrap();
+        assert_eq!(glibc.file, "libc.so.6");
+        assert!(glibc.numbers > vec![2, 2, 5]);
+    }
+
+    #[test]
+    fn highest_versions() {
+        let needed = |name: &str| VersionNeeded {
+            vna_hash: 0,
+            vna_flags: 0,
+            vna_other: 0,
+            name: name.to_string(),
+        };
+        let requirement = |file: &str, names: &[&str]| VersionRequirement {
+            file: file.to_string(),
+            versions: names.iter().map(|name| needed(name)).collect(),
+        };
+        let requirements = [
+            requirement("libstdc++.so.6", &["GLIBCXX_3.4.9", "GLIBCXX_3.4.29", "CXXABI_1.3"]),
+            requirement("libc.so.6", &["GLIBC_2.4", "GLIBC_PRIVATE", "GLIBC_2.34"]),
+            requirement("libm.so.6", &["GLIBC_2.29"]),
+        ];
+        let highest = version::highest_versions(&requirements);
+        let names: Vec<_> = highest.iter().map(|version| version.name.as_str()).collect();
+        assert_eq!(names, ["GLIBCXX_3.4.29", "CXXABI_1.3", "GLIBC_2.34"]);
+        assert_eq!(highest[2].numbers, [2, 34]);
+        assert_eq!(highest[2].file, "libc.so.6");
+        assert_eq!(version::split_version_name("GLIBC_PRIVATE"), None);
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn gnu_hash_table() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let table = elf.gnu_hash_table().unwrap().unwrap();
+        assert_eq!(table.symbol_count(), elf.dynamic_symbols().unwrap().len());
+        assert!(table.bloom.len().is_power_of_two());
+        // Symbols are sorted by bucket and the chains hold their hashes
+        let dynstr = elf.dynamic_string_table().unwrap();
+        let symbols = elf.dynamic_symbols().unwrap();
+        for (chain, sym) in table.chains.iter().zip(&symbols[table.symoffset as usize..]) {
+            let name = dynstr.get(sym.st_name() as usize).unwrap();
+            assert_eq!(chain | 1, hash::gnu_hash(name.as_bytes()) | 1);
+        }
+        assert_eq!(hash::gnu_hash(b""), 5381);
+        // Imports are not defined here, so lookups skip them
+        assert!(elf.lookup_symbol("malloc").unwrap().is_none());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn section_flags() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let flags = |name| elf.section_by_name(name).unwrap().sh_flags();
+        assert_eq!(flags(".text"), SectionFlags::ALLOC | SectionFlags::EXECINSTR);
+        assert!(flags(".tbss").contains(SectionFlags::TLS | SectionFlags::WRITE));
+        assert!(flags(".dynstr").contains(SectionFlags::ALLOC));
+        assert!(!flags(".symtab").contains(SectionFlags::ALLOC));
+        assert!(elf.section_by_name(".no_such_section").is_none());
 
+        let text = elf.section_by_name(".text").unwrap();
+        let inside = text.sh_addr() + Addr(text.sh_size().0 / 2);
+        assert_eq!(elf.section_containing(inside).unwrap().name(), Some(".text"));
+        assert_eq!(elf.section_containing(text.sh_addr()).unwrap().name(), Some(".text"));
+        assert!(elf.section_containing(Addr(0)).is_none());
+    }
 
-        // Read the offset of the Program Header table
-        let e_phoff = Addr::parse(reader)?;
+    #[test]
+    fn extended_counts() {
+        let mut bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        let shoff = bytes.len() as u64;
+        // Only the first section header, holding the real counts and the string table index
+        let mut sh0 = vec![0; 64];
+        sh0[32..40].copy_from_slice(&1u64.to_le_bytes());
+        sh0[44..48].copy_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&sh0);
+        bytes[40..48].copy_from_slice(&shoff.to_le_bytes());
+        bytes[56..58].copy_from_slice(&PN_XNUM.to_le_bytes());
+        bytes[58..60].copy_from_slice(&64u16.to_le_bytes());
+        bytes[60..62].copy_from_slice(&0u16.to_le_bytes());
+        bytes[62..64].copy_from_slice(&SHN_XINDEX.to_le_bytes());
 
-        // Read start of the section header table
-        let e_shoff = Addr::parse(reader)?;
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.ph_table.len(), 1);
+        assert_eq!(elf.sh_table.len(), 1);
+        assert_eq!(elf.shstrndx(), 0);
+    }
 
-        // Skip `e_flags` 4-bytes and `e_ehsize` 2-bytes
-        let _ = reader.read_slice(6)?;
+    #[test]
+    fn entry_sizes() {
+        let mut bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        // Too small to hold a program header
+        bytes[54..56].copy_from_slice(&48u16.to_le_bytes());
+        assert!(matches!(
+            Elf64::parse(&bytes),
+            Err(ElfError::ElfHeader(ElfHeaderError::BadPhEntSize(48)))
+        ));
 
-        // Read the size of a Program Header table entry.
-        let e_phentsize = reader.read_u16()?;
-        // Read Program Header table entries
-        let e_phnum = reader.read_u16()?;
+        // Larger entries are walked with the bigger stride
+        bytes[54..56].copy_from_slice(&64u16.to_le_bytes());
+        bytes.extend_from_slice(&[0; 8]);
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.ph_table[0].p_align(), 0x1000);
+    }
 
-        // Read information about the section header table
-        let e_shentsize = reader.read_u16()?;
-        let e_shnum = reader.read_u16()?;
-        let e_shstrndx = reader.read_u16()?;
+    #[test]
+    fn headers_only() {
+        let bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        let mut elf = Elf64::parse_headers(&bytes).unwrap();
+        assert_eq!(elf.ph_table[0].mem_range(), Addr(0x400000)..Addr(0x400000 + 120));
+        assert!(elf.ph_table[0].data.is_empty());
 
+        elf.load_data(&bytes).unwrap();
+        assert_eq!(&elf.ph_table[0].data[..], &bytes[..]);
+        assert!(matches!(elf.ph_table[0].data, Cow::Borrowed(_)));
 
-        Ok(ElfHeader{
-            e_type,
-            e_machine,
-            e_entry,
-            e_phoff,
-            e_shoff,
-            e_phentsize,
-            e_phnum,
-            e_shentsize,
-            e_shnum,
-            e_shstrndx,
-        })
+        // The dynamic table is read right away, but what it points to only once loaded
+        let bytes = with_dynamic(&[(DynamicTag::StrTab, 0x400000), (DynamicTag::StrSz, 8)]);
+        let mut elf = Elf64::parse_headers(&bytes).unwrap();
+        assert_eq!(elf.dynamic_entry(DynamicTag::StrTab), Some(Addr(0x400000)));
+        let strtab = elf.dynamic_string_table();
+        assert!(matches!(strtab, Err(StringError::NotLoaded(Addr(0x400000)))));
+        elf.load_data(&bytes).unwrap();
+        assert!(elf.dynamic_string_table().is_ok());
     }
 
-    pub fn e_phoff(&self) -> Addr {
-        self.e_phoff
+    #[test]
+    fn elf32() {
+        let bytes = exec_fixture(Class::Elf32, Endianness::Little);
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.elf_header.e_class(), Class::Elf32);
+        assert_eq!(Addr(0x00401000), elf.elf_header.e_entry);
+
+        let load = &elf.ph_table[0];
+        assert_eq!(load.p_type(), SegmentType::PtLoad);
+        assert_eq!(load.p_flags(), SegmentFlags::READ | SegmentFlags::EXEC);
+        assert_eq!(load.mem_range(), Addr(0x400000)..Addr(0x400000 + 84));
+        assert_eq!(load.data.len(), 84);
     }
 
-    pub fn e_shoff(&self) -> Addr {
-        self.e_shoff
+    /// The test binary itself is a dynamically linked executable with all the usual tables
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    fn test_binary() -> Vec<u8> {
+        std::fs::read(std::env::current_exe().unwrap()).unwrap()
     }
 
-    pub fn e_phnum(&self) -> u16 {
-        self.e_phnum
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn dynamic_symbols() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let symbols = elf.dynamic_symbols().unwrap();
+        // The first entry is reserved and all zeroes
+        assert_eq!(symbols[0].st_name(), 0);
+        assert_eq!(elf.symbol_name(&symbols[0]).unwrap(), "");
+        let mut named = elf.named_dynamic_symbols().unwrap();
+        assert!(named.any(|(name, sym)| name == "malloc" && sym.st_shndx() == sym::SHN_UNDEF));
+        assert_eq!(symbols[0].st_value(), Addr(0));
+        assert_eq!(symbols[0].to_string(), "0000000000000000     0 NOTYPE  LOCAL  DEFAULT  UND");
+        // Functions such as `malloc` are imported from libc
+        assert!(symbols.iter().any(|sym| {
+            sym.st_info().st_type() == SymbolType::Func
+                && sym.st_info().st_binding() == SymbolBinding::Global
+                && sym.st_shndx() == sym::SHN_UNDEF
+        }));
     }
 
-    pub fn e_shnum(&self) -> u16 {
-        self.e_shnum
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn symbols() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let symbols = elf.symbols().unwrap();
+        let main = symbols.iter().find(|sym| sym.name == "main").unwrap();
+        assert_eq!(main.entry.st_info().st_type(), SymbolType::Func);
+        assert!(elf.segment_at(main.entry.st_value()).is_some());
+        assert_eq!(main.entry.visibility(), SymbolVisibility::Default);
+        let (value, size) = (main.entry.st_value().0, main.entry.st_size());
+        let line = format!("{:016x} {:5} FUNC    GLOBAL DEFAULT", value, size);
+        assert!(main.entry.to_string().starts_with(&line));
+        let inside_main = Addr(main.entry.st_value().0 + 1);
+        assert_eq!(elf.symbolicate(inside_main), Some(("main".to_string(), 1)));
+        assert_eq!(elf.symbolicate(Addr(0)), None);
+        let dso_handle = symbols.iter().find(|sym| sym.name == "__dso_handle").unwrap();
+        assert_eq!(dso_handle.entry.visibility(), SymbolVisibility::Hidden);
     }
-}
 
+    #[cfg(all(feature = "mmap", target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn mapped_file() {
+        let file = mmap::MappedFile::open(std::env::current_exe().unwrap()).unwrap();
+        let elf = file.parse().unwrap();
+        assert!(elf.dynamic_symbols().unwrap().len() > 1);
+    }
 
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn parse_source() {
+        let file = std::fs::File::open(std::env::current_exe().unwrap()).unwrap();
+        let elf = Elf64::parse_source(&file, ParseOptions::default()).unwrap();
+        assert!(elf.dynamic_symbols().unwrap().len() > 1);
+    }
 
-#[cfg(test)]
-mod tests {
-    use std::fs;
-    use super::*;
     #[test]
-    fn elf_header() {
-        let bytes = include_bytes!("/home/m3m0ry/fun/osdev/assembly/hello");
+    fn seek_source() {
+        let bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        let source = source::SeekSource::new(std::io::Cursor::new(&bytes));
+        let options = ParseOptions { load_data: false, ..Default::default() };
+        let elf = Elf64::parse_source(&source, options).unwrap();
+        assert_eq!(elf.ph_table[0].p_align(), 0x1000);
+        assert!(elf.ph_table[0].data.is_empty());
+    }
+
+    #[test]
+    fn archive() {
+        /// Appends a member with a GNU style header
+        fn push_member(archive: &mut Vec<u8>, name: &str, data: &[u8]) {
+            archive.extend_from_slice(format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+                name, 0, 0, 0, 644, data.len()).as_bytes());
+            archive.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                archive.push(b'\n');
+            }
+        }
+
+        let object = exec_fixture(Class::Elf64, Endianness::Little);
+        let long_name = "a_rather_long_object_name.o";
+        let mut bytes = b"!<arch>\n".to_vec();
+        push_member(&mut bytes, "/", &[0; 4]);
+        push_member(&mut bytes, "//", format!("{}/\n", long_name).as_bytes());
+        push_member(&mut bytes, "short.o/", &object);
+        push_member(&mut bytes, "/0", &object);
+
+        let archive = archive::Archive::parse(&bytes).unwrap();
+        let objects = archive.objects().collect::<Result<Vec<_>, _>>().unwrap();
+        let names = objects.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["short.o", long_name]);
+        assert_eq!(objects[1].1.elf_header.e_entry, Addr(0x401000));
+    }
+
+    #[test]
+    fn debug_link() {
+        let dir = std::env::temp_dir().join(format!("parse-elf-debug-link-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let debug_file = dir.join("hello.debug");
+        fs::write(&debug_file, b"debug information").unwrap();
+
+        let mut debuglink = b"hello.debug\0".to_vec();
+        debuglink.extend_from_slice(&debug::crc32(b"debug information").to_le_bytes());
+        let bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".gnu_debuglink", 1, &debuglink)],
+        );
+        let elf = Elf64::parse(&bytes).unwrap();
+
+        let names: Vec<_> = elf.sections().map(|section| section.name()).collect();
+        assert_eq!(names, [Some(""), Some(".gnu_debuglink"), Some(".shstrtab")]);
+        assert_eq!(elf.sections().nth(2).unwrap().section_type(), SectionType::ShtStrtab);
+        assert_eq!(elf.section_name(&elf.sh_table[2]).as_deref(), Some(".shstrtab"));
+        let link = elf.debug_link().unwrap().unwrap();
+        assert_eq!(link.filename, "hello.debug");
+        let found = elf.find_debug_file_in(&dir.join("hello"), &dir.join("root"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, Some(debug_file));
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn resolve_dependencies() {
+        let path = std::env::current_exe().unwrap();
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let needed = elf.needed_libraries().unwrap();
+        let graph = resolver::resolve(&elf, &path, &SearchPaths::new(Path::new("/"))).unwrap();
+        assert_eq!(graph.missing().count(), 0);
+        let direct: Vec<_> =
+            graph.objects[0].needed.iter().map(|index| &graph.objects[*index]).collect();
+        let names: Vec<_> = direct.iter().map(|object| object.name.clone()).collect();
+        assert_eq!(names, needed);
+        let libc = direct.iter().find(|object| object.name == "libc.so.6").unwrap();
+
+        // Only the C library is in the sysroot, in a directory listed by an included file
+        let name = format!("parse-elf-sysroot-{}", std::process::id());
+        let sysroot = std::env::temp_dir().join(name);
+        fs::create_dir_all(sysroot.join("etc/ld.so.conf.d")).unwrap();
+        fs::create_dir_all(sysroot.join("opt/libs")).unwrap();
+        fs::write(sysroot.join("etc/ld.so.conf"), "include /etc/ld.so.conf.d/*.conf\n").unwrap();
+        fs::write(sysroot.join("etc/ld.so.conf.d/libs.conf"), "/opt/libs # C library\n").unwrap();
+        let sysroot_libc = sysroot.join("opt/libs/libc.so.6");
+        std::os::unix::fs::symlink(libc.path.as_ref().unwrap(), &sysroot_libc).unwrap();
+        let paths = SearchPaths::new(&sysroot);
+        let graph = resolver::resolve(&elf, &path, &paths);
+        fs::remove_dir_all(&sysroot).unwrap();
+        assert_eq!(paths.config_dirs, [PathBuf::from("/opt/libs")]);
+        let graph = graph.unwrap();
+        let libc = graph.objects.iter().find(|object| object.name == "libc.so.6").unwrap();
+        assert_eq!(libc.path, Some(sysroot_libc));
+        let missing: Vec<_> = graph.missing().collect();
+        assert!(missing.contains(&"ld-linux-x86-64.so.2"));
+        assert_eq!(missing.len(), graph.objects.len() - 2);
+    }
+
+    #[test]
+    fn ld_so_conf_include_loop() {
+        // A file including itself is read once
+        let name = format!("parse-elf-include-loop-{}", std::process::id());
+        let sysroot = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(sysroot.join("etc")).unwrap();
+        let conf = "include /etc/ld.so.conf\n/opt/libs\n";
+        std::fs::write(sysroot.join("etc/ld.so.conf"), conf).unwrap();
+        let paths = SearchPaths::new(&sysroot);
+        std::fs::remove_dir_all(&sysroot).unwrap();
+        assert_eq!(paths.config_dirs, [std::path::PathBuf::from("/opt/libs")]);
+    }
+
+    #[test]
+    fn comments() {
+        let bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".comment", 1, b"\0GCC: (GNU) 12.2.0\0Linker: LLD 16.0.6\0GCC: (GNU) 12.2.0\0")],
+        );
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.comments(), ["GCC: (GNU) 12.2.0", "Linker: LLD 16.0.6"]);
+    }
+
+    #[test]
+    fn build_id() {
+        let mut note = vec![];
+        for field in [4u32, 4, 3] {
+            note.extend_from_slice(&field.to_le_bytes());
+        }
+        note.extend_from_slice(b"GNU\0");
+        note.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        let bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".note.gnu.build-id", 7, &note)],
+        );
+        let elf = Elf64::parse(&bytes).unwrap();
+
+        let build_id = elf.build_id().unwrap();
+        assert_eq!(build_id.bytes, &[0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(build_id.to_hex(), "deadbeef");
+
+        let bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        assert!(Elf64::parse(&bytes).unwrap().build_id().is_none());
+    }
+
+    #[test]
+    fn core_notes() {
+        let mut bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        bytes[16..18].copy_from_slice(&4u16.to_le_bytes());
+        bytes[18..20].copy_from_slice(&0x3Eu16.to_le_bytes());
+
+        let mut notes = vec![];
+        let mut push_note = |n_type: u32, desc: &[u8]| {
+            for field in [5, desc.len() as u32, n_type] {
+                notes.extend_from_slice(&field.to_le_bytes());
+            }
+            notes.extend_from_slice(b"CORE\0\0\0\0");
+            notes.extend_from_slice(desc);
+        };
+        let mut prstatus = vec![0; 336];
+        prstatus[0..4].copy_from_slice(&11u32.to_le_bytes());
+        prstatus[32..36].copy_from_slice(&1234u32.to_le_bytes());
+        // `rip` is the 17th register
+        prstatus[112 + 16 * 8..112 + 17 * 8].copy_from_slice(&0x401000u64.to_le_bytes());
+        push_note(core_dump::NT_PRSTATUS, &prstatus);
+        let mut auxv = vec![];
+        for word in [9u64, 0x401000, 0, 0] {
+            auxv.extend_from_slice(&word.to_le_bytes());
+        }
+        push_note(core_dump::NT_AUXV, &auxv);
+        let mut file = vec![];
+        for word in [1u64, 0x1000, 0x400000, 0x401000, 2] {
+            file.extend_from_slice(&word.to_le_bytes());
+        }
+        file.extend_from_slice(b"/bin/true\0");
+        file.resize(file.len().next_multiple_of(4), 0);
+        push_note(core_dump::NT_FILE, &file);
+
+        // Turn the only segment into a note segment covering the notes
+        let offset = bytes.len() as u64;
+        set_segment_field(&mut bytes, 0, P_TYPE, &4u32.to_le_bytes());
+        set_segment_field(&mut bytes, 0, P_OFFSET, &offset.to_le_bytes());
+        set_segment_field(&mut bytes, 0, P_FILESZ, &(notes.len() as u64).to_le_bytes());
+        set_segment_field(&mut bytes, 0, P_ALIGN, &4u64.to_le_bytes());
+        bytes.extend_from_slice(&notes);
+
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.elf_header.e_type, FileType::EtCore);
+        let notes: Vec<_> = elf.core_notes().collect::<Result<_, _>>().unwrap();
+        let CoreNote::PrStatus(prstatus) = &notes[0] else { panic!("{:?}", notes[0]) };
+        assert_eq!(prstatus.si_signo, 11);
+        assert_eq!(prstatus.pr_pid, 1234);
+        assert_eq!(prstatus.pr_reg.rip, 0x401000);
+        let CoreNote::Auxv(auxv) = &notes[1] else { panic!("{:?}", notes[1]) };
+        assert_eq!(auxv, &[core_dump::AuxvEntry { a_type: 9, a_val: 0x401000 }]);
+        let CoreNote::File(files) = &notes[2] else { panic!("{:?}", notes[2]) };
+        assert_eq!(files[0].start..files[0].end, Addr(0x400000)..Addr(0x401000));
+        assert_eq!(files[0].file_offset, 0x2000);
+        assert_eq!(files[0].path, "/bin/true");
+    }
+
+    #[test]
+    fn string_table() {
+        let table = StringTable::new(b"\0.text\0.data\0tail");
+        assert_eq!(table.get(1).unwrap(), ".text");
+        // Names may share the suffix of a longer one
+        assert_eq!(table.get(2).unwrap(), "text");
+        assert_eq!(table.get(0).unwrap(), "");
+        assert!(matches!(table.get(14), Err(StringError::Unterminated(14))));
+        assert!(matches!(table.get(100), Err(StringError::OffsetOutOfBounds(100))));
+        let strings: Vec<_> = table.iter().collect();
+        assert_eq!(strings, [(0, "".into()), (1, ".text".into()), (7, ".data".into())]);
+    }
+
+    /// Returns a file with a `.dynamic` section holding `entries`, for files without a
+    /// `PT_DYNAMIC` segment, like `with_sections`
+    fn with_dynamic(entries: &[(DynamicTag, u64)]) -> Vec<u8> {
+        let mut dynamic = vec![];
+        for (tag, value) in entries.iter().chain(&[(DynamicTag::Null, 0)]) {
+            dynamic.extend_from_slice(&u64::from(*tag).to_le_bytes());
+            dynamic.extend_from_slice(&value.to_le_bytes());
+        }
+        with_sections(exec_fixture(Class::Elf64, Endianness::Little), &[(".dynamic", 6, &dynamic)])
+    }
+
+    #[test]
+    fn gnu_hash_table_past_file_contents() {
+        // The only segment maps its 120 bytes of contents, followed by zeros
+        let tail = 0x400100;
+        let bytes = with_dynamic(&[
+            (DynamicTag::GnuHash, tail),
+            (DynamicTag::SymTab, 0x400000),
+            (DynamicTag::SymEnt, 24),
+        ]);
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        elf.ph_table[0].p_memsz = Size(0x1000);
+        assert!(elf.slice_at(Addr(tail)).is_none());
+        assert!(matches!(elf.gnu_hash_table(), Some(Err(hash::Error::BadAddress(_)))));
+        assert!(elf.dynamic_symbols().is_err());
+    }
+
+    #[test]
+    fn version_tables_past_file_contents() {
+        let tail = 0x400100;
+        let bytes = with_dynamic(&[
+            (DynamicTag::VerDef, tail),
+            (DynamicTag::VerDefNum, 1),
+            (DynamicTag::VerNeed, tail),
+            (DynamicTag::VerNeedNum, 1),
+        ]);
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        elf.ph_table[0].p_memsz = Size(0x1000);
+        assert!(elf.version_definitions().is_err());
+        assert!(elf.version_requirements().is_err());
+        assert!(elf.required_symbol_versions().is_err());
+    }
+
+    #[test]
+    fn hash_table_past_file_contents() {
+        let bytes = with_dynamic(&[(DynamicTag::Hash, 0x400100)]);
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        elf.ph_table[0].p_memsz = Size(0x1000);
+        assert!(matches!(elf.hash_table(), Some(Err(hash::Error::BadAddress(Addr(0x400100))))));
+    }
+
+    #[test]
+    fn sysv_hash_table() {
+        // Two buckets over three symbols, the last two sharing a bucket
+        let mut bytes = vec![];
+        for word in [2u32, 3, 1, 2, 0, 0, 1] {
+            bytes.extend_from_slice(&word.to_be_bytes());
+        }
+        let table = SysvHashTable::parse(&bytes, Class::Elf32, Endianness::Big).unwrap();
+        assert_eq!(table.buckets, [1, 2]);
+        assert_eq!(table.chains, [0, 0, 1]);
+        assert_eq!(table.symbol_count(), 3);
+        assert!(SysvHashTable::parse(&bytes[..20], Class::Elf32, Endianness::Big).is_err());
+
+        assert_eq!(hash::elf_hash(b"printf"), 0x077905A6);
+        assert_eq!(hash::elf_hash(b"malloc"), 0x07383353);
+    }
+
+    #[cfg(feature = "demangle")]
+    #[test]
+    fn demangle() {
+        assert_eq!(sym::demangle("_ZN4core3fmt5write17h0123456789abcdefE"), "core::fmt::write");
+        assert_eq!(
+            sym::demangle("_ZNSt6vectorIiSaIiEE9push_backERKi"),
+            "std::vector<int, std::allocator<int> >::push_back(int const&)"
+        );
+        assert_eq!(sym::demangle("main"), "main");
+    }
+
+    #[test]
+    fn dynamic_tags() {
+        assert_eq!(DynamicTag::try_from(0x6FFF_FFFB).unwrap(), DynamicTag::Flags1);
+        assert_eq!(DynamicTag::try_from(0x6FFF_FFF9).unwrap(), DynamicTag::RelaCount);
+        assert_eq!(DynamicTag::try_from(32).unwrap(), DynamicTag::PreinitArray);
+        // Relr tables of recent glibc builds used to stop parsing
+        assert_eq!(DynamicTag::try_from(36).unwrap(), DynamicTag::Relr);
+        assert_eq!(DynamicTag::try_from(0x6000_0010).unwrap(), DynamicTag::AndroidRelSz);
+        assert_eq!(DynamicTag::try_from(0x6000_0020).unwrap(), DynamicTag::OsSpecific(0x6000_0020));
+        assert!(DynamicTag::try_from(38).is_err());
+    }
+
+    #[test]
+    fn symbol_info() {
+        // An IFUNC of glibc, like `memcpy`, or a GNU unique object
+        let info = SymbolInfo::try_from(0x1A).unwrap();
+        assert_eq!(info.st_type(), SymbolType::GnuIfunc);
+        assert_eq!(info.st_binding(), SymbolBinding::Global);
+        let info = SymbolInfo::try_from(0xA1).unwrap();
+        assert_eq!(info.st_binding(), SymbolBinding::GnuUnique);
+        assert_eq!(SymbolType::try_from(6).unwrap(), SymbolType::Tls);
+        assert_eq!(SymbolType::try_from(11).unwrap(), SymbolType::OsSpecific(11));
+
+        assert_eq!(SymbolType::GnuIfunc.to_string(), "IFUNC");
+        assert_eq!(SymbolBinding::GnuUnique.to_string(), "UNIQUE");
+        assert_eq!(SymbolType::ProcSpecific(13).to_string(), "<processor specific>: 13");
+    }
+
+    #[test]
+    fn hash_table_lookup() {
+        let names = ["", "printf", "malloc"];
+        let is_named = |name: &'static str| move |index: u32| names[index as usize] == name;
+
+        // A single bucket chaining both symbols, behind a single bloom filter word
+        let hashes: Vec<_> = names[1..].iter().map(|name| hash::gnu_hash(name.as_bytes())).collect();
+        let bloom = hashes
+            .iter()
+            .fold(0u64, |word, hash| word | 1 << (hash % 64) | 1 << ((hash >> 6) % 64));
+        let table = GnuHashTable {
+            symoffset: 1,
+            bloom_shift: 6,
+            bloom: vec![bloom],
+            class: Class::Elf64,
+            buckets: vec![1],
+            chains: vec![hashes[0] & !1, hashes[1] | 1],
+        };
+        assert_eq!(table.lookup("printf", is_named("printf")), Some(1));
+        assert_eq!(table.lookup("malloc", is_named("malloc")), Some(2));
+        assert_eq!(table.lookup("free", is_named("free")), None);
+
+        // Shifts as wide as the hashes are rejected rather than overflowing
+        let bad = [1u32, 1, 1, 32, 0, 0, 0, 0].map(u32::to_le_bytes).concat();
+        let parsed = GnuHashTable::parse(&bad, Class::Elf64, Endianness::Little);
+        assert!(matches!(parsed, Err(hash::Error::BadBloomShift(32))));
+        let table = GnuHashTable { bloom_shift: 32, ..table };
+        assert_eq!(table.lookup("printf", is_named("printf")), None);
+
+        let table = SysvHashTable { buckets: vec![2], chains: vec![0, 0, 1] };
+        assert_eq!(table.lookup("printf", is_named("printf")), Some(1));
+        assert_eq!(table.lookup("free", is_named("free")), None);
+    }
+
+    #[test]
+    fn extended_symbol_indexes() {
+        // The reserved null symbol, then a function defined in section 70000
+        let mut symtab = vec![0; 24];
+        symtab.extend_from_slice(&1u32.to_le_bytes());
+        symtab.extend_from_slice(&[0x12, 0]);
+        symtab.extend_from_slice(&SHN_XINDEX.to_le_bytes());
+        symtab.extend_from_slice(&[0; 16]);
+        let mut shndx = 0u32.to_le_bytes().to_vec();
+        shndx.extend_from_slice(&70000u32.to_le_bytes());
+
+        let mut bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".symtab", 2, &symtab), (".strtab", 3, b"\0big\0"), (".symtab_shndx", 18, &shndx)],
+        );
+        // Link the symbol table to its string table and the index table to the symbol table
+        for (section, link) in [(1, 2u32), (3, 1)] {
+            set_section_field(&mut bytes, section, SH_LINK, &link.to_le_bytes());
+        }
+
+        let elf = Elf64::parse(&bytes).unwrap();
+        let symbols = elf.symbols().unwrap();
+        assert_eq!(symbols[1].name, "big");
+        assert_eq!(symbols[1].entry.st_shndx(), SHN_XINDEX);
+        assert_eq!(symbols[1].shndx, 70000);
+        assert_eq!(symbols[0].shndx, u32::from(sym::SHN_UNDEF));
+    }
+
+    #[cfg(feature = "zlib")]
+    #[test]
+    fn compressed_sections() {
+        use std::io::Write;
+
+        let contents = b"debug information ".repeat(16);
+        let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&contents).unwrap();
+        let mut data = vec![];
+        for field in [compress::ELFCOMPRESS_ZLIB, 0] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [contents.len() as u64, 1] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(&encoder.finish().unwrap());
+
+        let mut bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".debug_info", 1, &data)],
+        );
+        // Flag the section as compressed
+        set_section_field(&mut bytes, 1, SH_FLAGS, &SectionFlags::COMPRESSED.bits().to_le_bytes());
+
+        let elf = Elf64::parse(&bytes).unwrap();
+        let section = elf.sections().find(|section| section.name() == Some(".debug_info")).unwrap();
+        assert_eq!(section.index(), 1);
+        assert!(section.flags().contains(SectionFlags::COMPRESSED));
+        assert_eq!(&section.data().unwrap()[..], &contents[..]);
+        let shstrtab = elf.section_by_name(".shstrtab").unwrap();
+        assert!(matches!(elf.section_data(shstrtab).unwrap(), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn dynamic_section() {
+        // Without a PT_DYNAMIC segment, the table is read from the section
+        let mut dynamic = vec![];
+        for field in [0x6FFF_FFFBu64, 0x800_0000, 0, 0] {
+            dynamic.extend_from_slice(&field.to_le_bytes());
+        }
+        let bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".dynamic", 6, &dynamic)],
+        );
+        let elf = Elf64::parse(&bytes).unwrap();
+        assert_eq!(elf.dynamic_table().unwrap().len(), 2);
+        assert_eq!(elf.dynamic_flags_1(), DynamicFlags1::PIE);
+
+        // A table cut before its null entry is reported, rather than read past its end
+        let bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".dynamic", 6, &dynamic[..24])],
+        );
+        let err = Elf64::parse(&bytes).unwrap_err();
+        assert!(matches!(
+            err,
+            ElfError::SectionError(section::SectionError::DynamicError(SegmentError::DynamicError(
+                DynamicError::Truncated(1)
+            )))
+        ));
+    }
+
+    #[test]
+    fn validate_symbols() {
+        let mut symtab = vec![0; 24];
+        let symbols: [(u32, u8, u16, u64); 6] = [
+            (1, 0x11, sym::SHN_ABS, 0x1234),
+            (100, 0x11, sym::SHN_ABS, 0),
+            (1, 0x11, 50, 0),
+            (1, 0x12, 2, 0x1000),
+            (1, 0x12, 2, 2),
+            (1, 0x12, SHN_XINDEX, 0),
+        ];
+        for (st_name, st_info, st_shndx, st_value) in symbols {
+            symtab.extend_from_slice(&st_name.to_le_bytes());
+            symtab.extend_from_slice(&[st_info, 0]);
+            symtab.extend_from_slice(&st_shndx.to_le_bytes());
+            symtab.extend_from_slice(&st_value.to_le_bytes());
+            symtab.extend_from_slice(&1u64.to_le_bytes());
+        }
+        let mut bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".symtab", 2, &symtab), (".strtab", 3, b"\0ok\0")],
+        );
+        set_section_field(&mut bytes, 1, SH_LINK, &2u32.to_le_bytes());
+
+        let elf = Elf64::parse(&bytes).unwrap();
+        let issues: Vec<_> = elf
+            .validate_symbols()
+            .unwrap()
+            .into_iter()
+            .map(|diagnostic| (diagnostic.table, diagnostic.index, diagnostic.issue))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (1, 2, SymbolIssue::BadNameOffset(100)),
+                (1, 3, SymbolIssue::BadSectionIndex(50)),
+                (1, 4, SymbolIssue::ValueOutsideSection { value: Addr(0x1000), shndx: 2 }),
+                (1, 6, SymbolIssue::MissingExtendedIndex),
+            ]
+        );
+    }
+
+    #[test]
+    fn rel_entries() {
+        // The table and the dynamic section follow the headers, in the only loadable segment
+        let mut rel = vec![];
+        for field in [0x40_2000u64, 8, 0x40_2008, 6 | 3 << 32] {
+            rel.extend_from_slice(&field.to_le_bytes());
+        }
+        let rel_addr = 0x40_0000 + 120;
+        let mut dynamic = vec![];
+        for field in [17, rel_addr, 18, rel.len() as u64, 0, 0] {
+            dynamic.extend_from_slice(&field.to_le_bytes());
+        }
+        let mut bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".rel.dyn", 9, &rel), (".dynamic", 6, &dynamic)],
+        );
+        let file_size = (bytes.len() as u64).to_le_bytes();
+        set_segment_field(&mut bytes, 0, P_FILESZ, &file_size);
+        set_segment_field(&mut bytes, 0, P_MEMSZ, &file_size);
+
+        let elf = Elf64::parse(&bytes).unwrap();
+        let rels = elf.read_rel_entries().unwrap();
+        assert_eq!(rels.len(), 2);
+        assert_eq!(rels[0].r_type, RelType::X86_64(X64RelType::Relative));
+        let glob_dat = &rels[1];
+        let glob_dat_type = RelType::X86_64(X64RelType::GlobDat);
+        assert_eq!((glob_dat.r_offset, glob_dat.r_type), (Addr(0x40_2008), glob_dat_type));
+        assert_eq!(glob_dat.r_sym, 3);
+        assert!(elf.read_plt_rel_entries().is_err());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn relative_relocations() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        // The linker sorts the relative relocations first and counts them in DT_RELACOUNT
+        let count = elf.relative_count().unwrap();
+        let relatives = elf.relative_relocations().unwrap().collect::<Vec<_>>();
+        assert_eq!(relatives.len(), count + elf.relr_offsets().unwrap().len());
+        let decoded = elf.relocations().unwrap().filter(|rel| rel.r_type.is_relative());
+        for (relative, decoded) in relatives.iter().zip(decoded) {
+            assert_eq!(relative.r_offset, decoded.r_offset);
+            assert_eq!(relative.r_addend, decoded.r_addend);
+        }
+    }
+
+    #[test]
+    fn relocation_sections() {
+        // A call from `.text` to the global function `foo`, like in an object file
+        let mut symtab = vec![0; 24];
+        symtab.extend_from_slice(&1u32.to_le_bytes());
+        symtab.extend_from_slice(&[0x12, 0]);
+        symtab.extend_from_slice(&1u16.to_le_bytes());
+        symtab.extend_from_slice(&[0; 16]);
+        let mut rela = vec![];
+        for field in [4u64, 4 | 1 << 32, -4i64 as u64] {
+            rela.extend_from_slice(&field.to_le_bytes());
+        }
+        let mut bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[
+                (".text", 1, &[0; 16]),
+                (".strtab", 3, b"\0foo\0"),
+                (".symtab", 2, &symtab),
+                (".rela.text", 4, &rela),
+            ],
+        );
+        set_section_field(&mut bytes, 3, SH_LINK, &2u32.to_le_bytes());
+        // The relocations use symbol table 3 and apply to section 1
+        set_section_field(&mut bytes, 4, SH_LINK, &3u32.to_le_bytes());
+        set_section_field(&mut bytes, 4, SH_INFO, &1u32.to_le_bytes());
+
+        let elf = Elf64::parse(&bytes).unwrap();
+        let sections = elf.relocation_sections().unwrap();
+        assert_eq!(sections.len(), 1);
+        let section = &sections[0];
+        assert_eq!((section.index, section.symtab, section.target), (4, 3, 1));
+        let call = &section.relocations[0];
+        assert_eq!(call.relocation.r_type, RelType::X86_64(X64RelType::Plt32));
+        assert_eq!(call.relocation.r_addend, Some(-4));
+        assert_eq!(call.relocation.table, RelocationTable::Section(4));
+        let foo = call.symbol.as_ref().unwrap();
+        assert_eq!((foo.name.as_str(), foo.shndx), ("foo", 1));
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn named_relocations() {
+        let bytes = test_binary();
+        let elf = Elf64::parse(&bytes).unwrap();
+        let relocations = elf.named_relocations().unwrap();
+        assert_eq!(relocations.len(), elf.relocations().unwrap().count());
+        // Functions called through the PLT, like `_Unwind_Resume`, are imported from libgcc_s
+        let unwind_resume = relocations
+            .iter()
+            .filter(|named| named.relocation.table == RelocationTable::JmpRel)
+            .find(|named| named.symbol.as_ref().is_some_and(|sym| sym.name == "_Unwind_Resume"))
+            .unwrap();
+        assert_eq!(unwind_resume.relocation.r_type, RelType::X86_64(X64RelType::JumpSlot));
+        let entry = &unwind_resume.symbol.as_ref().unwrap().entry;
+        assert_eq!(entry.st_info().st_type(), SymbolType::Func);
+        assert_eq!(entry.st_info().st_binding(), SymbolBinding::Global);
+        // Relative relocations have no symbol
+        let relative = relocations.iter().find(|named| named.relocation.r_type.is_relative());
+        assert!(relative.unwrap().symbol.is_none());
+    }
+
+    #[test]
+    fn rel_types() {
+        // The same value is a different relocation on every machine
+        assert_eq!(RelType::new(Machine::AmdX86_64, 8), RelType::X86_64(X64RelType::Relative));
+        assert_eq!(RelType::new(Machine::X86, 8), RelType::I386(I386RelType::Relative));
+        assert_eq!(RelType::new(Machine::Arm, 23), RelType::Arm(ArmRelType::Relative));
+        assert_eq!(RelType::new(Machine::RiscV, 3), RelType::RiscV(RiscVRelType::Relative));
+        let relative = RelType::new(Machine::AArch64, 1027);
+        assert_eq!(relative, RelType::AArch64(AArch64RelType::Relative));
+        assert!(relative.is_relative() && !relative.is_jump_slot());
+        assert_eq!(RelType::new(Machine::AArch64, 8), RelType::Other(8));
+        // Thread local and relaxable GOT relocations of x86-64 objects
+        assert_eq!(RelType::new(Machine::AmdX86_64, 22), RelType::X86_64(X64RelType::GotTpOff));
+        assert_eq!(X64RelType::try_from(42).unwrap(), X64RelType::RexGotPcRelX);
+        assert_eq!(X64RelType::try_from(16).unwrap(), X64RelType::DtpMod64);
+        // Unknown types of every machine share the same variant
+        assert_eq!(RelType::new(Machine::AmdX86_64, 39), RelType::Other(39));
+        assert_eq!(RelType::new(Machine::Mips, 3), RelType::Other(3));
+    }
+
+    #[test]
+    fn plt_relocations() {
+        // Two lazily bound functions, imported through symbols 1 and 2
+        let mut rela = vec![];
+        for field in [0x40_3018u64, 7 | 1 << 32, 0, 0x40_3020, 7 | 2 << 32, 0] {
+            rela.extend_from_slice(&field.to_le_bytes());
+        }
+        let rela_addr = 0x40_0000 + 120;
+        let mut dynamic = vec![];
+        for field in [23, rela_addr, 2, rela.len() as u64, 20, 7, 0, 0] {
+            dynamic.extend_from_slice(&field.to_le_bytes());
+        }
+        let mut bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".rela.plt", 4, &rela), (".dynamic", 6, &dynamic)],
+        );
+        let file_size = (bytes.len() as u64).to_le_bytes();
+        set_segment_field(&mut bytes, 0, P_FILESZ, &file_size);
+        set_segment_field(&mut bytes, 0, P_MEMSZ, &file_size);
+
+        let elf = Elf64::parse(&bytes).unwrap();
+        let plt = elf.read_plt_relocations().unwrap();
+        assert!(matches!(plt, PltRelocations::Rela(_)));
+        let entries = plt.entries().collect::<Vec<_>>();
+        let jump_slot = RelType::X86_64(X64RelType::JumpSlot);
+        assert_eq!(entries, [(Addr(0x40_3018), jump_slot, 1), (Addr(0x40_3020), jump_slot, 2)]);
+        // The main table is a separate one, which this file lacks
+        assert!(elf.read_rela_entries().is_err());
+        let relocations = elf.relocations().unwrap().collect::<Vec<_>>();
+        assert_eq!(relocations.len(), 2);
+        let expected = Relocation {
+            r_offset: Addr(0x40_3020),
+            r_type: jump_slot,
+            r_sym: 2,
+            r_addend: Some(0),
+            table: RelocationTable::JmpRel,
+        };
+        assert_eq!(relocations[1], expected);
+
+        let static_bytes = exec_fixture(Class::Elf64, Endianness::Little);
+        let static_exec = Elf64::parse(&static_bytes).unwrap();
+        assert!(static_exec.read_plt_relocations().unwrap().is_empty());
+    }
+
+    #[test]
+    fn relr() {
+        // An address, then a bitmap relocating the first and third words after it
+        let mut relr = vec![];
+        for entry in [0x1_0000u64, 0b1011, 1 << 63 | 1] {
+            relr.extend_from_slice(&entry.to_le_bytes());
+        }
+        let offsets = reloc::decode_relr(&relr, Class::Elf64, Endianness::Little).unwrap();
+        let last = 0x1_0008 + 63 * 8 + 62 * 8;
+        assert_eq!(offsets, [Addr(0x1_0000), Addr(0x1_0008), Addr(0x1_0018), Addr(last)]);
+    }
+
+    #[test]
+    fn relr_relocations_without_type() {
+        // The zero padding of the identification is a table with one address entry, 0
+        let bytes = with_dynamic(&[(DynamicTag::Relr, 0x400008), (DynamicTag::RelrSz, 8)]);
+        let mut elf = Elf64::parse(&bytes).unwrap();
+        let relocations: Vec<_> = elf.relocations().unwrap().collect();
+        assert_eq!(relocations[0].r_type, RelType::X86_64(X64RelType::Relative));
+
+        // MIPS has no relocation table here, which must not turn the entries into `R_*_NONE`
+        elf.elf_header.e_machine = Machine::Mips;
+        let relocations: Vec<_> = elf.relocations().unwrap().collect();
+        assert_eq!(relocations.len(), 1);
+        assert_eq!(relocations[0].r_offset, Addr(0));
+        assert_eq!(relocations[0].r_type, RelType::Relr);
+        assert!(relocations[0].r_type.is_relative());
+    }
+
+    #[test]
+    fn reader_lookahead() {
+        let bytes = [0x7F, b'E', b'L', b'F', 2, 1];
+        let mut reader = Reader::new(&bytes, Class::Elf64, Endianness::Big);
+        assert_eq!(reader.peek_slice(4).unwrap(), b"\x7fELF");
+        assert_eq!(reader.peek_u32().unwrap(), 0x7F45_4C46);
+        assert_eq!(reader.position(), 0);
+
+        let checkpoint = reader.checkpoint();
+        assert_eq!(reader.read_u32().unwrap(), 0x7F45_4C46);
+        assert_eq!(reader.peek_u16().unwrap(), 0x0201);
+        assert!(reader.peek_u64().is_err());
+        assert_eq!(reader.position(), 4);
+        reader.rollback(checkpoint);
+        assert_eq!(reader.peek_u8().unwrap(), 0x7F);
+
+        reader.set_position(1).unwrap();
+        reader.align_to(4).unwrap();
+        assert_eq!(reader.position(), 4);
+        reader.align_to(4).unwrap();
+        assert_eq!(reader.position(), 4);
+        assert!(reader.align_to(8).is_err());
+
+        // A table whose length field claims more than its range holds cannot read past it
+        let mut table = reader.sub_reader(2..4).unwrap();
+        assert_eq!(table.peek_u16().unwrap(), 0x4C46);
+        assert!(table.read_u32().is_err());
+        assert!(reader.sub_reader(4..8).is_err());
+
+        // The end of the bytes is a valid position, but nothing can be read from it
+        reader.set_position(bytes.len()).unwrap();
+        assert!(reader.peek_u8().is_err());
+        assert!(reader.set_position(bytes.len() + 1).is_err());
+    }
+
+    #[test]
+    fn read_cstr() {
+        let bytes = b"libc.so.6\0\0ld\0tail";
         let mut reader = Reader::from_bytes(bytes);
-        let elf_header = ElfHeader::parse(&mut reader).unwrap();
-        assert_eq!(elf_header.e_type, FileType::EtExec);
-        assert_eq!(elf_header.e_machine, Machine::AmdX86_64);
-        assert_eq!(Addr(0x00401000), elf_header.e_entry);
+        assert_eq!(reader.read_cstr().unwrap(), b"libc.so.6");
+        assert_eq!(reader.read_cstr().unwrap(), b"");
+        assert_eq!(reader.read_cstr_at(5).unwrap(), b"so.6");
+        assert_eq!(reader.read_cstr().unwrap(), b"ld");
+        assert!(matches!(reader.read_cstr(), Err(ParseError::Unterminated(14))));
+        assert_eq!(reader.position(), 14);
     }
 
     #[test]
-    fn elf() {
-        let bytes = fs::read("/home/m3m0ry/fun/osdev/assembly/hello").unwrap();
+    fn failed_reads_keep_position() {
+        let bytes = [1, 2, 3, 0x80];
+        let mut reader = Reader::from_bytes(&bytes);
+        reader.read_u16().unwrap();
+        assert!(matches!(
+            reader.read_u32(),
+            Err(ParseError::ReadOutOfBounds { offset: 2, size: 4 })
+        ));
+        assert_eq!(reader.position(), 2);
+        // A length field close to the maximum must not overflow the index
+        assert!(reader.read_slice(usize::MAX).is_err());
+        assert_eq!(reader.position(), 2);
+        assert_eq!(reader.read_u8().unwrap(), 3);
+        // The LEB128 value is cut by the end of the bytes
+        assert!(reader.read_uleb128().is_err());
+        assert_eq!(reader.position(), 3);
+    }
+
+    #[test]
+    fn big_endian_notes() {
+        // An ABI tag note of a big-endian file, like one for PowerPC or s390x
+        let mut bytes = vec![];
+        for field in [4u32, 16, 1] {
+            bytes.extend_from_slice(&field.to_be_bytes());
+        }
+        bytes.extend_from_slice(b"GNU\0");
+        for field in [0u32, 3, 2, 0] {
+            bytes.extend_from_slice(&field.to_be_bytes());
+        }
+        let reader = Reader::new(&bytes, Class::Elf64, Endianness::Big);
+        let note = Notes::new(reader, 4).next().unwrap().unwrap();
+        assert_eq!((note.n_type, note.name), (1, &b"GNU"[..]));
+        let mut reader = Reader::new(note.desc, Class::Elf64, Endianness::Big);
+        let tag = AbiTag::parse(&mut reader).unwrap();
+        assert_eq!(tag, AbiTag { os: AbiTagOs::Linux, kernel_version: (3, 2, 0) });
+    }
+
+    #[test]
+    fn signed_reads() {
+        let bytes = [0xFC, 0xFF, 0xFF, 0xFF, 0x80, 0xFF];
+        let mut reader = Reader::new(&bytes, Class::Elf32, Endianness::Little);
+        // 32-bit addends are sign-extended, so -4 does not turn into 0xFFFF_FFFC
+        assert_eq!(reader.read_sword().unwrap(), -4);
+        assert_eq!(reader.read_i8().unwrap(), -128);
+        assert_eq!(reader.read_i8().unwrap(), -1);
+        reader.seek(0).unwrap();
+        assert_eq!(reader.read_i16().unwrap(), -4);
+        let bytes = (-0x1234i64).to_be_bytes();
+        assert_eq!(Reader::new(&bytes, Class::Elf64, Endianness::Big).read_i64().unwrap(), -0x1234);
+    }
+
+    #[test]
+    fn android_packed_relocations() {
+        fn sleb(bytes: &mut Vec<u8>, mut value: i64) {
+            loop {
+                let byte = (value & 0x7F) as u8;
+                value >>= 7;
+                if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+                    bytes.push(byte);
+                    return;
+                }
+                bytes.push(byte | 0x80);
+            }
+        }
+        let mut table = b"APS2".to_vec();
+        // Three relocations from 0x1000: two relative ones 8 bytes apart, with their own addends,
+        // then a GLOB_DAT of symbol 5 without addend
+        let relative = 0x403;
+        let glob_dat = 5 << 32 | 0x401;
+        for value in [3, 0x1000, 2, 0b1011, 8, relative, 0x10, -8, 1, 0, 0x100, glob_dat] {
+            sleb(&mut table, value);
+        }
+        let decode = |is_rela| {
+            let (class, endianness) = (Class::Elf64, Endianness::Little);
+            let machine = Machine::AArch64;
+            reloc::decode_android_packed(&table, class, endianness, machine, is_rela, 0x100)
+        };
+        let relocations = decode(true).unwrap();
+        let relative = RelType::AArch64(AArch64RelType::Relative);
+        let glob_dat = RelType::AArch64(AArch64RelType::GlobDat);
+        let expected =
+            [(0x1008, relative, 0, 0x10), (0x1010, relative, 0, 0x8), (0x1110, glob_dat, 5, 0)];
+        assert_eq!(relocations.len(), expected.len());
+        for (relocation, (r_offset, r_type, r_sym, r_addend)) in relocations.iter().zip(expected) {
+            let expected = PackedRelocation { r_offset: Addr(r_offset), r_type, r_sym, r_addend };
+            assert_eq!(*relocation, expected);
+        }
+        // Rel tables cannot carry addends
+        assert!(decode(false).is_err());
+
+        // Groups sharing their info and offset delta, without addends, take no bytes per
+        // relocation, so their count is bounded
+        let mut table = b"APS2".to_vec();
+        for value in [2_000_000, 0x1000, 2_000_000, 0b11, 8, 0x403] {
+            sleb(&mut table, value);
+        }
+        let (class, endianness, machine) = (Class::Elf64, Endianness::Little, Machine::AArch64);
+        let decoded =
+            reloc::decode_android_packed(&table, class, endianness, machine, false, 0x100);
+        assert!(matches!(decoded, Err(reloc::Error::TooManyRelocations(2_000_000))));
+
+        let mut reader = Reader::from_bytes(&[0xE5, 0x8E, 0x26, 0x7F, 0x80, 0x7F]);
+        assert_eq!(reader.read_uleb128().unwrap(), 624_485);
+        assert_eq!(reader.read_sleb128().unwrap(), -1);
+        assert_eq!(reader.read_sleb128().unwrap(), -128);
+    }
+
+    #[test]
+    fn indirect_functions() {
+        // A static executable keeps its IRELATIVE relocations in `.rela.iplt`
+        let mut rela = vec![];
+        for field in [0x4A_4000u64, 37, 0x41_E570] {
+            rela.extend_from_slice(&field.to_le_bytes());
+        }
+        let mut bytes = with_sections(
+            exec_fixture(Class::Elf64, Endianness::Little),
+            &[(".rela.iplt", 4, &rela)],
+        );
+        set_section_field(&mut bytes, 1, SH_FLAGS, &SectionFlags::ALLOC.bits().to_le_bytes());
+
         let elf = Elf64::parse(&bytes).unwrap();
-        println!("{:?}", elf);
+        let function = IndirectFunction { resolver: Addr(0x41_E570), target: Addr(0x4A_4000) };
+        assert_eq!(elf.indirect_functions().unwrap(), [function]);
+    }
+
+    #[test]
+    fn big_endian() {
+        for class in [Class::Elf32, Class::Elf64] {
+            let bytes = exec_fixture(class, Endianness::Big);
+            let elf = Elf64::parse(&bytes).unwrap();
+            assert_eq!(elf.elf_header.e_data(), Endianness::Big);
+            assert_eq!(elf.elf_header.e_machine, Machine::AmdX86_64);
+            assert_eq!(Addr(0x00401000), elf.elf_header.e_entry);
+            assert_eq!(elf.ph_table[0].p_flags(), SegmentFlags::READ | SegmentFlags::EXEC);
+            assert_eq!(elf.ph_table[0].p_align(), 0x1000);
+        }
     }
 }
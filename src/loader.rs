@@ -0,0 +1,349 @@
+//! Module mapping the loadable segments of a file in memory, the way the kernel or the dynamic
+//! loader lays them out before running it, and applying its dynamic relocations. Nothing is run.
+use std::{io, ops::Range, ptr};
+
+use thiserror::Error;
+
+use crate::{
+    sym::{SymbolEntry, SHN_ABS, SHN_UNDEF},
+    Addr, Elf64, Machine, RelType, Relocation, SegmentError, SegmentFlags, SegmentType,
+    SymbolBinding, X64RelType,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("The file has no loadable segment")]
+    NoLoadableSegments,
+    #[error("Contents of the segment at {0} were not loaded")]
+    NotLoaded(Addr),
+    #[error("Segment at {0} does not fit in the address space")]
+    BadSegment(Addr),
+    #[error("Mapping failed {0}")]
+    Map(#[from] io::Error),
+    #[error("Relocations of {0:?} files are not supported")]
+    UnsupportedMachine(Machine),
+    #[error("Relocation {r_type:?} at {r_offset} is not supported")]
+    UnsupportedRelocation { r_type: RelType, r_offset: Addr },
+    #[error("Relocation at {0} is outside of the image")]
+    BadRelocation(Addr),
+    #[error("Symbol {0} could not be resolved")]
+    UnresolvedSymbol(String),
+    #[error("Reading the relocations failed {0}")]
+    Relocations(#[from] SegmentError),
+}
+
+/// Loadable segments mapped in anonymous memory, keeping the distances between them. The image
+/// goes wherever the system finds room, so every address in it is `bias` away from the virtual
+/// address the file gives, and everything is unmapped when this is dropped.
+pub struct LoadedImage {
+    base: *mut u8,
+    size: usize,
+    /// Virtual address the file gives to the start of the image
+    start: u64,
+    /// Virtual address ranges of the segments, as the file gives them, with their permissions
+    segments: Vec<(Range<u64>, SegmentFlags)>,
+}
+
+impl LoadedImage {
+    /// Maps the loadable segments of `elf`, copying their file contents and leaving the rest of
+    /// `p_memsz`, like `.bss`, zeroed. The image is aligned to the largest segment alignment and
+    /// the pages of each segment get the permissions from its flags, those of the later segment
+    /// winning on pages two segments share. Contents must have been loaded.
+    pub fn load(elf: &Elf64) -> Result<Self, Error> {
+        let page = page_size();
+        let loads: Vec<_> = elf
+            .ph_table
+            .iter()
+            .filter(|ph| ph.p_type() == SegmentType::PtLoad && ph.p_memsz().0 != 0)
+            .collect();
+        let mut segments = vec![];
+        for ph in loads.iter() {
+            if ph.data.len() as u64 != ph.p_filesz().0 {
+                return Err(Error::NotLoaded(ph.p_vaddr()));
+            }
+            // The file contents are copied into the memory of the segment, so they must fit
+            let range = ph.mem_range();
+            let too_large = ph.p_filesz().0 > ph.p_memsz().0;
+            if too_large || range.end.0 < range.start.0 || range.end.0 > u64::MAX - page {
+                return Err(Error::BadSegment(ph.p_vaddr()));
+            }
+            segments.push((range.start.0..range.end.0, ph.p_flags()));
+        }
+        let align = loads.iter().map(|ph| ph.p_align()).fold(page, u64::max);
+        if !align.is_power_of_two() {
+            return Err(Error::BadSegment(Addr(align)));
+        }
+        let start = segments.iter().map(|(range, _)| range.start).min();
+        let start = start.ok_or(Error::NoLoadableSegments)? & !(align - 1);
+        let end = segments.iter().map(|(range, _)| range.end).max().unwrap_or(start);
+        let size = usize::try_from(align_up(end, page) - start)
+            .map_err(|_| Error::BadSegment(Addr(start)))?;
+
+        // Reserve more than needed and give back what is left around the aligned image
+        let reserved = size.checked_add(align as usize).ok_or(Error::BadSegment(Addr(start)))?;
+        let reservation = map(reserved)?;
+        let base = align_up(reservation as u64, align) as *mut u8;
+        let head = base as usize - reservation as usize;
+        unmap(reservation, head);
+        unmap(base.wrapping_add(size), reserved - head - size);
+        let image = Self { base, size, start, segments };
+
+        for (ph, (range, _)) in loads.iter().zip(image.segments.iter()) {
+            image.protect(range, libc::PROT_READ | libc::PROT_WRITE)?;
+            // Safety: the pages of the segment were made writable right above, inside the image
+            unsafe {
+                let dest = image.base.add(image.offset(range.start));
+                ptr::copy_nonoverlapping(ph.data.as_ptr(), dest, ph.data.len());
+            }
+        }
+        for (range, flags) in image.segments.iter() {
+            image.protect(range, protection(*flags))?;
+        }
+        Ok(image)
+    }
+
+    /// Applies the dynamic relocations of `elf`, the file this image was loaded from, as if its
+    /// virtual address 0 were at `base`. Passing `bias` relocates the image to run where it is,
+    /// while another base prepares it to be copied elsewhere.
+    /// Symbols are looked up through `resolve` first, which returns their address, or `None` to
+    /// fall back to the definition in the image itself. Undefined weak symbols resolve to 0.
+    /// Only x86-64 relative, `64`, `GLOB_DAT`, `JUMP_SLOT` and `COPY` relocations are supported.
+    /// Pages are writable while relocating, then get the permissions of their segment back, and
+    /// those of `PtGnuRelro` end up read-only, up to the end of the segment it starts in.
+    ///
+    /// # Safety
+    ///
+    /// `COPY` relocations copy the contents of their symbol, so the addresses `resolve` returns
+    /// for them must be readable for the size of the symbol.
+    pub unsafe fn relocate(
+        &mut self,
+        elf: &Elf64,
+        base: u64,
+        mut resolve: impl FnMut(&str, &SymbolEntry) -> Option<u64>,
+    ) -> Result<(), Error> {
+        let machine = elf.elf_header.e_machine;
+        if machine != Machine::AmdX86_64 {
+            return Err(Error::UnsupportedMachine(machine));
+        }
+        let relocations: Vec<_> = elf.relocations()?.collect();
+        let symbols = match relocations.iter().any(|relocation| relocation.r_sym != 0) {
+            true => elf.named_dynamic_symbols()?.collect(),
+            false => vec![],
+        };
+
+        for (range, _) in self.segments.iter() {
+            self.protect(range, libc::PROT_READ | libc::PROT_WRITE)?;
+        }
+        for relocation in relocations {
+            let Relocation { r_offset, r_type, r_sym, r_addend, .. } = relocation;
+            let symbol = symbols.get(r_sym as usize).filter(|_| r_sym != 0);
+            let value = |resolve: &mut dyn FnMut(&str, &SymbolEntry) -> Option<u64>| {
+                let (name, sym) = symbol.ok_or(Error::BadRelocation(r_offset))?;
+                resolve(name, sym)
+                    .or_else(|| match sym.st_shndx() {
+                        SHN_UNDEF if sym.st_info().st_binding() == SymbolBinding::Weak => Some(0),
+                        SHN_UNDEF => None,
+                        SHN_ABS => Some(sym.st_value().0),
+                        _ => Some(base.wrapping_add(sym.st_value().0)),
+                    })
+                    .ok_or_else(|| Error::UnresolvedSymbol(name.to_string()))
+            };
+            let RelType::X86_64(r_type) = r_type else {
+                return Err(Error::UnsupportedRelocation { r_type, r_offset });
+            };
+            match r_type {
+                X64RelType::None => {}
+                X64RelType::Relative => {
+                    let addend = match r_addend {
+                        Some(addend) => addend as u64,
+                        None => self.read_u64(r_offset)?,
+                    };
+                    self.write_u64(r_offset, base.wrapping_add(addend))?;
+                }
+                X64RelType::W64 => {
+                    let addend = r_addend.unwrap_or(0) as u64;
+                    self.write_u64(r_offset, value(&mut resolve)?.wrapping_add(addend))?;
+                }
+                X64RelType::GlobDat | X64RelType::JumpSlot => {
+                    self.write_u64(r_offset, value(&mut resolve)?)?;
+                }
+                X64RelType::Copy => {
+                    let (name, sym) = symbol.ok_or(Error::BadRelocation(r_offset))?;
+                    let source = resolve(name, sym)
+                        .ok_or_else(|| Error::UnresolvedSymbol(name.to_string()))?;
+                    let dest = self.bytes_mut(r_offset, sym.st_size() as usize)?;
+                    // Safety: the caller guarantees the symbol is readable where it resolves
+                    ptr::copy(source as *const u8, dest.as_mut_ptr(), dest.len());
+                }
+                _ => {
+                    let r_type = RelType::X86_64(r_type);
+                    return Err(Error::UnsupportedRelocation { r_type, r_offset });
+                }
+            }
+        }
+
+        for (range, flags) in self.segments.iter() {
+            self.protect(range, protection(*flags))?;
+        }
+        let relro = elf.segment_of_type(SegmentType::PtGnuRelro);
+        let relro = relro.and_then(|relro| Some((relro, self.segment(relro.p_vaddr())?.0.end)));
+        if let Some((relro, segment_end)) = relro {
+            // Only whole pages of the segment the range starts in become read-only, the end of
+            // the last one stays as it was
+            let range = relro.mem_range();
+            let end = range.end.0.min(segment_end);
+            let end = end - self.offset(end) as u64 % page_size();
+            if end > range.start.0 {
+                self.protect(&(range.start.0..end), libc::PROT_READ)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns how far the image is from the virtual addresses of the file
+    pub fn bias(&self) -> u64 {
+        (self.base as u64).wrapping_sub(self.start)
+    }
+
+    /// Returns the size of the image, from the first page of the lowest segment to the last page
+    /// of the highest one
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns a pointer to where `addr`, a virtual address of the file, is mapped, if a segment
+    /// covers it. Using it is up to the caller, who must respect the permissions of the segment.
+    pub fn ptr(&self, addr: Addr) -> Option<*const u8> {
+        self.segment(addr)?;
+        Some(self.base.wrapping_add(self.offset(addr.0)) as *const u8)
+    }
+
+    /// Returns the mapped bytes from `addr` to the end of its segment, like `Elf64::slice_at`
+    /// does for the file, including the zeroed tail. Segments with an unreadable page, including
+    /// a page shared with a later unreadable segment, give `None`.
+    pub fn slice_at(&self, addr: Addr) -> Option<&[u8]> {
+        let (range, _) = self.segment(addr)?;
+        let page = page_size() as usize;
+        let first_page = self.offset(addr.0) & !(page - 1);
+        let mut pages = (first_page..self.offset(range.end)).step_by(page);
+        let readable = |offset| self.page_flags(offset).contains(SegmentFlags::READ);
+        if !pages.all(readable) {
+            return None;
+        }
+        let len = (range.end - addr.0) as usize;
+        // Safety: the segment lies inside the image, which stays mapped and readable while it is
+        // borrowed, and only the caller of `ptr` can write to it
+        Some(unsafe { std::slice::from_raw_parts(self.base.add(self.offset(addr.0)), len) })
+    }
+
+    /// Returns the `len` mapped bytes at `addr`, which must all lie in one segment
+    fn bytes_mut(&mut self, addr: Addr, len: usize) -> Result<&mut [u8], Error> {
+        let (range, _) = self.segment(addr).ok_or(Error::BadRelocation(addr))?;
+        if range.end - addr.0 < len as u64 {
+            return Err(Error::BadRelocation(addr));
+        }
+        // Safety: the bytes lie inside the image, which this borrows mutably
+        Ok(unsafe { std::slice::from_raw_parts_mut(self.base.add(self.offset(addr.0)), len) })
+    }
+
+    fn read_u64(&mut self, addr: Addr) -> Result<u64, Error> {
+        let bytes = self.bytes_mut(addr, 8)?;
+        Ok(u64::from_le_bytes(<[u8; 8]>::try_from(&*bytes).unwrap_or_default()))
+    }
+
+    fn write_u64(&mut self, addr: Addr, value: u64) -> Result<(), Error> {
+        self.bytes_mut(addr, 8)?.copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn segment(&self, addr: Addr) -> Option<&(Range<u64>, SegmentFlags)> {
+        self.segments.iter().rev().find(|(range, _)| range.contains(&addr.0))
+    }
+
+    /// Returns the flags of the page at `offset` in the image, those of the last segment on it,
+    /// whose permissions it was given
+    fn page_flags(&self, offset: usize) -> SegmentFlags {
+        let page = page_size() as usize;
+        let on_page = |(range, _): &&(Range<u64>, SegmentFlags)| {
+            let start = self.offset(range.start) & !(page - 1);
+            let end = align_up(self.offset(range.end) as u64, page as u64) as usize;
+            (start..end).contains(&offset)
+        };
+        let segment = self.segments.iter().rev().find(on_page);
+        segment.map(|(_, flags)| *flags).unwrap_or(SegmentFlags::empty())
+    }
+
+    /// Returns the offset of the virtual address `addr` from the start of the image
+    fn offset(&self, addr: u64) -> usize {
+        (addr - self.start) as usize
+    }
+
+    /// Changes the permissions of the pages `range` spans
+    fn protect(&self, range: &Range<u64>, prot: libc::c_int) -> Result<(), Error> {
+        let page = page_size();
+        let start = self.offset(range.start) & !(page as usize - 1);
+        let end = align_up(self.offset(range.end) as u64, page) as usize;
+        // Safety: the pages are part of the image, which this owns
+        let result = unsafe { libc::mprotect(self.base.add(start).cast(), end - start, prot) };
+        if result != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LoadedImage {
+    fn drop(&mut self) {
+        unmap(self.base, self.size);
+    }
+}
+
+fn page_size() -> u64 {
+    // Safety: `sysconf` has no preconditions
+    let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    u64::try_from(page).unwrap_or(0x1000)
+}
+
+fn align_up(value: u64, align: u64) -> u64 {
+    value.wrapping_add(align - 1) & !(align - 1)
+}
+
+fn protection(flags: SegmentFlags) -> libc::c_int {
+    let mut prot = libc::PROT_NONE;
+    if flags.contains(SegmentFlags::READ) {
+        prot |= libc::PROT_READ;
+    }
+    if flags.contains(SegmentFlags::WRITE) {
+        prot |= libc::PROT_WRITE;
+    }
+    if flags.contains(SegmentFlags::EXEC) {
+        prot |= libc::PROT_EXEC;
+    }
+    prot
+}
+
+/// Maps `size` bytes of inaccessible anonymous memory
+fn map(size: usize) -> Result<*mut u8, Error> {
+    // Safety: a new private anonymous mapping does not alias any memory
+    let addr = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            size,
+            libc::PROT_NONE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        )
+    };
+    if addr == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(addr.cast())
+}
+
+fn unmap(addr: *mut u8, size: usize) {
+    if size != 0 {
+        // Safety: callers only give back pages of mappings they own and no longer use
+        unsafe { libc::munmap(addr.cast(), size) };
+    }
+}
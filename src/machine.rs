@@ -1,22 +1,67 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Machine {
-    X86 = 0x03,
-    AmdX86_64 = 0x3E,
+    /// No machine
+    None,
+    Sparc,
+    X86,
+    Mips,
+    PowerPc,
+    PowerPc64,
+    /// IBM S/390, also used by 64-bit s390x objects
+    S390,
+    Arm,
+    /// SPARC Version 9, 64-bit
+    SparcV9,
+    AmdX86_64,
+    AArch64,
+    RiscV,
+    /// Linux eBPF
+    Bpf,
+    LoongArch,
+    /// Value not covered by the variants above
+    Other(u16),
 }
 
-impl TryFrom<u16> for Machine {
-    type Error = Error;
-    fn try_from(value: u16) -> Result<Machine, Self::Error> {
+impl From<u16> for Machine {
+    fn from(value: u16) -> Machine {
         match value {
-            0x03 => Ok(Machine::X86),
-            0x3E => Ok(Machine::AmdX86_64),
-            _ => Err(Error::NotSupported),
+            0x00 => Machine::None,
+            0x02 => Machine::Sparc,
+            0x03 => Machine::X86,
+            0x08 => Machine::Mips,
+            0x14 => Machine::PowerPc,
+            0x15 => Machine::PowerPc64,
+            0x16 => Machine::S390,
+            0x28 => Machine::Arm,
+            0x2B => Machine::SparcV9,
+            0x3E => Machine::AmdX86_64,
+            0xB7 => Machine::AArch64,
+            0xF3 => Machine::RiscV,
+            0xF7 => Machine::Bpf,
+            0x102 => Machine::LoongArch,
+            _ => Machine::Other(value),
         }
     }
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum Error {
-    #[error("Machine not supported")]
-    NotSupported,
+impl From<Machine> for u16 {
+    fn from(value: Machine) -> u16 {
+        match value {
+            Machine::None => 0x00,
+            Machine::Sparc => 0x02,
+            Machine::X86 => 0x03,
+            Machine::Mips => 0x08,
+            Machine::PowerPc => 0x14,
+            Machine::PowerPc64 => 0x15,
+            Machine::S390 => 0x16,
+            Machine::Arm => 0x28,
+            Machine::SparcV9 => 0x2B,
+            Machine::AmdX86_64 => 0x3E,
+            Machine::AArch64 => 0xB7,
+            Machine::RiscV => 0xF3,
+            Machine::Bpf => 0xF7,
+            Machine::LoongArch => 0x102,
+            Machine::Other(value) => value,
+        }
+    }
 }
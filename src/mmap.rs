@@ -0,0 +1,34 @@
+//! Module for parsing files straight from a read-only memory mapping, without reading them in
+//! memory first.
+use std::{fs::File, path::Path};
+
+use memmap2::Mmap;
+
+use crate::{Elf64, ElfError};
+
+/// A file mapped read-only in memory. The mapping owns the bytes that the `Elf64` returned by
+/// `parse` borrows from, so it must outlive it.
+pub struct MappedFile {
+    mmap: Mmap,
+}
+
+impl MappedFile {
+    /// Maps the file at `path` in memory
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ElfError> {
+        let file = File::open(path)?;
+        // Safety: the mapping is read-only. As with any file mapping, the file being truncated or
+        // modified by another process while mapped is outside of what we can guard against.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Ok(Self { mmap })
+    }
+
+    /// Returns the mapped bytes
+    pub fn bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Parses the mapped file. Segment and section contents point into the mapping.
+    pub fn parse(&self) -> Result<Elf64<'_>, ElfError> {
+        Elf64::parse(&self.mmap)
+    }
+}
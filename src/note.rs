@@ -0,0 +1,245 @@
+//! Module describing notes, the vendor tagged records found in `PT_NOTE` segments and `SHT_NOTE`
+//! sections
+use std::fmt;
+
+use bitflags::bitflags;
+
+use crate::{Machine, ParseError, Reader};
+
+/// Name of the notes defined by the GNU toolchain
+pub const GNU_NAME: &[u8] = b"GNU";
+/// GNU note type holding the operating system and the oldest kernel version supported
+pub const NT_GNU_ABI_TAG: u32 = 1;
+/// GNU note type holding the unique build ID bitstring
+pub const NT_GNU_BUILD_ID: u32 = 3;
+/// GNU note type holding the program properties, found in `.note.gnu.property`
+pub const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+
+/// Property giving the stack size the program needs
+pub const GNU_PROPERTY_STACK_SIZE: u32 = 1;
+/// Property telling that protected data symbols are not copied with `COPY` relocations
+pub const GNU_PROPERTY_NO_COPY_ON_PROTECTED: u32 = 2;
+/// x86 property holding the features every input object supports, like CET
+pub const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc000_0002;
+/// x86 property holding the features some input object needs
+pub const GNU_PROPERTY_X86_FEATURE_2_NEEDED: u32 = 0xc000_8001;
+/// x86 property holding the ISA levels some input object needs
+pub const GNU_PROPERTY_X86_ISA_1_NEEDED: u32 = 0xc000_8002;
+/// x86 property holding the features the input objects use
+pub const GNU_PROPERTY_X86_FEATURE_2_USED: u32 = 0xc001_0001;
+/// x86 property holding the ISA levels the input objects use
+pub const GNU_PROPERTY_X86_ISA_1_USED: u32 = 0xc001_0002;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note<'a> {
+    /// Type of the note, whose meaning depends on the name
+    pub n_type: u32,
+    /// Name of the originator of the note, without the null terminator
+    pub name: &'a [u8],
+    /// Contents of the note
+    pub desc: &'a [u8],
+}
+
+/// Unique ID of a build, stamped by the linker in the GNU build-id note. Debuggers and symbol
+/// servers use it to match a stripped file with its debug information.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuildId<'a> {
+    pub bytes: &'a [u8],
+}
+
+impl BuildId<'_> {
+    /// Returns the ID as a lowercase hex string, the way tools like `file` and `readelf` print it
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for BuildId<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.bytes {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Operating system named by the GNU ABI tag
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AbiTagOs {
+    Linux,
+    Hurd,
+    Solaris,
+    FreeBsd,
+    Other(u32),
+}
+
+impl From<u32> for AbiTagOs {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => Self::Linux,
+            1 => Self::Hurd,
+            2 => Self::Solaris,
+            3 => Self::FreeBsd,
+            _ => Self::Other(value),
+        }
+    }
+}
+
+/// Contents of the GNU ABI tag note, found in `.note.ABI-tag`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AbiTag {
+    pub os: AbiTagOs,
+    /// Oldest kernel version the file runs on, as major, minor and patch numbers
+    pub kernel_version: (u32, u32, u32),
+}
+
+impl AbiTag {
+    /// Parses the descriptor of the note `reader` points to
+    pub fn parse(reader: &mut Reader) -> Result<Self, ParseError> {
+        let os = AbiTagOs::from(reader.read_u32()?);
+        let kernel_version = (reader.read_u32()?, reader.read_u32()?, reader.read_u32()?);
+        Ok(Self { os, kernel_version })
+    }
+}
+
+bitflags! {
+    /// Features of `GNU_PROPERTY_X86_FEATURE_1_AND`, which the linker only keeps when every input
+    /// object has them
+    pub struct X86Feature1: u32 {
+        /// Indirect branch tracking, indirect jumps and calls land on `endbr` instructions
+        const IBT = 0x1;
+        /// Shadow stack, return addresses are checked against a copy the program cannot write
+        const SHSTK = 0x2;
+        /// Linear address masking with 48-bit user space pointers
+        const LAM_U48 = 0x4;
+        /// Linear address masking with 57-bit user space pointers
+        const LAM_U57 = 0x8;
+    }
+}
+
+bitflags! {
+    /// x86-64 micro-architecture levels of `GNU_PROPERTY_X86_ISA_1_*`
+    pub struct X86Isa1: u32 {
+        const BASELINE = 0x1;
+        /// Adds CMPXCHG16B, LAHF/SAHF, POPCNT, SSE3, SSSE3, SSE4.1 and SSE4.2
+        const V2 = 0x2;
+        /// Adds AVX, AVX2, BMI1, BMI2, F16C, FMA, LZCNT, MOVBE and XSAVE
+        const V3 = 0x4;
+        /// Adds AVX512F, AVX512BW, AVX512CD, AVX512DQ and AVX512VL
+        const V4 = 0x8;
+    }
+}
+
+/// A property of the GNU property note. Properties in the processor specific range are only
+/// decoded for the machines they belong to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GnuProperty<'a> {
+    StackSize(u64),
+    NoCopyOnProtected,
+    X86Feature1And(X86Feature1),
+    X86Isa1Needed(X86Isa1),
+    X86Isa1Used(X86Isa1),
+    /// Bitmap of the register sets and instructions some input object needs, like x87 or XSAVE
+    X86Feature2Needed(u32),
+    /// Bitmap of the register sets and instructions the input objects use
+    X86Feature2Used(u32),
+    Other { pr_type: u32, data: &'a [u8] },
+}
+
+impl<'a> GnuProperty<'a> {
+    /// Parses the properties in the descriptor of the note `reader` points to, each of which is
+    /// padded to the word size of the file
+    pub fn parse_all(reader: &mut Reader<'a>, machine: Machine) -> Result<Vec<Self>, ParseError> {
+        let x86 = matches!(machine, Machine::X86 | Machine::AmdX86_64);
+        let mut properties = vec![];
+        while reader.index < reader.bytes.len() {
+            let pr_type = reader.read_u32()?;
+            let pr_datasz = reader.read_u32()? as usize;
+            // The data is read through a reader which cannot go past its size
+            let start = reader.position();
+            let end = start.checked_add(pr_datasz).ok_or(ParseError::OutOfBounds)?;
+            let mut data = reader.sub_reader(start..end)?;
+            reader.set_position(end)?;
+            let property = match pr_type {
+                GNU_PROPERTY_STACK_SIZE => Self::StackSize(data.read_word()?),
+                GNU_PROPERTY_NO_COPY_ON_PROTECTED => Self::NoCopyOnProtected,
+                GNU_PROPERTY_X86_FEATURE_1_AND if x86 => {
+                    Self::X86Feature1And(X86Feature1::from_bits_truncate(data.read_u32()?))
+                }
+                GNU_PROPERTY_X86_ISA_1_NEEDED if x86 => {
+                    Self::X86Isa1Needed(X86Isa1::from_bits_truncate(data.read_u32()?))
+                }
+                GNU_PROPERTY_X86_ISA_1_USED if x86 => {
+                    Self::X86Isa1Used(X86Isa1::from_bits_truncate(data.read_u32()?))
+                }
+                GNU_PROPERTY_X86_FEATURE_2_NEEDED if x86 => {
+                    Self::X86Feature2Needed(data.read_u32()?)
+                }
+                GNU_PROPERTY_X86_FEATURE_2_USED if x86 => Self::X86Feature2Used(data.read_u32()?),
+                _ => Self::Other { pr_type, data: data.bytes },
+            };
+            properties.push(property);
+            // The padding after the last property may be missing
+            if reader.align_to(reader.class.word_size()).is_err() {
+                break;
+            }
+        }
+        Ok(properties)
+    }
+}
+
+/// Iterator over the notes stored one after the other in a segment or section
+pub struct Notes<'a> {
+    reader: Reader<'a>,
+    /// Alignment of the name and the descriptor. 4 for most notes, 8 for notes placed in 8-byte
+    /// aligned segments or sections, like the GNU property note.
+    align: usize,
+}
+
+impl<'a> Notes<'a> {
+    /// Creates an iterator over the notes `reader` points to. `align` is the alignment of the
+    /// segment or section holding them.
+    pub fn new(reader: Reader<'a>, align: u64) -> Self {
+        let align = if align == 8 { 8 } else { 4 };
+        Self { reader, align }
+    }
+
+    fn parse_note(&mut self) -> Result<Note<'a>, ParseError> {
+        let n_namesz = self.reader.read_u32()? as usize;
+        let n_descsz = self.reader.read_u32()? as usize;
+        let n_type = self.reader.read_u32()?;
+
+        let name = self.reader.read_slice(n_namesz)?;
+        self.skip_padding();
+        let desc = self.reader.read_slice(n_descsz)?;
+        self.skip_padding();
+
+        // The name size includes the null terminator, though some producers leave it out
+        let name = Reader::from_bytes(name).read_cstr().unwrap_or(name);
+        Ok(Note { n_type, name, desc })
+    }
+
+    fn skip_padding(&mut self) {
+        // The padding after the last descriptor may be missing
+        if self.reader.align_to(self.align).is_err() {
+            self.reader.index = self.reader.bytes.len();
+        }
+    }
+}
+
+impl<'a> Iterator for Notes<'a> {
+    type Item = Result<Note<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.reader.index >= self.reader.bytes.len() {
+            return None;
+        }
+
+        let note = self.parse_note();
+        if note.is_err() {
+            // Nothing after a malformed note can be trusted
+            self.reader.index = self.reader.bytes.len();
+        }
+        Some(note)
+    }
+}
@@ -0,0 +1,91 @@
+//! Module describing the operating system and ABI the object is targeted for
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OsAbi {
+    /// UNIX System V ABI, also used by most objects that do not care about the OS
+    SysV,
+    HpUx,
+    NetBsd,
+    /// Objects using GNU extensions. Also known as `ELFOSABI_GNU`
+    Linux,
+    GnuHurd,
+    Solaris,
+    Aix,
+    Irix,
+    FreeBsd,
+    Tru64,
+    NovellModesto,
+    OpenBsd,
+    OpenVms,
+    NonStopKernel,
+    Aros,
+    FenixOs,
+    CloudAbi,
+    OpenVos,
+    /// ARM EABI
+    ArmEabi,
+    /// ARM, for objects predating the ARM EABI
+    Arm,
+    /// Standalone (embedded) application
+    Standalone,
+    /// Value not covered by the variants above
+    Other(u8),
+}
+
+impl From<u8> for OsAbi {
+    fn from(value: u8) -> OsAbi {
+        match value {
+            0 => OsAbi::SysV,
+            1 => OsAbi::HpUx,
+            2 => OsAbi::NetBsd,
+            3 => OsAbi::Linux,
+            4 => OsAbi::GnuHurd,
+            6 => OsAbi::Solaris,
+            7 => OsAbi::Aix,
+            8 => OsAbi::Irix,
+            9 => OsAbi::FreeBsd,
+            10 => OsAbi::Tru64,
+            11 => OsAbi::NovellModesto,
+            12 => OsAbi::OpenBsd,
+            13 => OsAbi::OpenVms,
+            14 => OsAbi::NonStopKernel,
+            15 => OsAbi::Aros,
+            16 => OsAbi::FenixOs,
+            17 => OsAbi::CloudAbi,
+            18 => OsAbi::OpenVos,
+            64 => OsAbi::ArmEabi,
+            97 => OsAbi::Arm,
+            255 => OsAbi::Standalone,
+            _ => OsAbi::Other(value),
+        }
+    }
+}
+
+impl From<OsAbi> for u8 {
+    fn from(value: OsAbi) -> u8 {
+        match value {
+            OsAbi::SysV => 0,
+            OsAbi::HpUx => 1,
+            OsAbi::NetBsd => 2,
+            OsAbi::Linux => 3,
+            OsAbi::GnuHurd => 4,
+            OsAbi::Solaris => 6,
+            OsAbi::Aix => 7,
+            OsAbi::Irix => 8,
+            OsAbi::FreeBsd => 9,
+            OsAbi::Tru64 => 10,
+            OsAbi::NovellModesto => 11,
+            OsAbi::OpenBsd => 12,
+            OsAbi::OpenVms => 13,
+            OsAbi::NonStopKernel => 14,
+            OsAbi::Aros => 15,
+            OsAbi::FenixOs => 16,
+            OsAbi::CloudAbi => 17,
+            OsAbi::OpenVos => 18,
+            OsAbi::ArmEabi => 64,
+            OsAbi::Arm => 97,
+            OsAbi::Standalone => 255,
+            OsAbi::Other(value) => value,
+        }
+    }
+}
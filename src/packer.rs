@@ -0,0 +1,150 @@
+//! Module telling packed executables apart, whose code is compressed or encrypted and unpacked in
+//! memory by a stub at run time. UPX is recognized by its headers, a few other packers by the
+//! strings of their stubs or the names of their sections, and unknown ones by the marks they
+//! leave on the layout of the file, which only tell them apart from linked files.
+use crate::{entropy, Elf64, FileType, ProgramHeader, SegmentFlags, SegmentType, Size};
+
+/// Magic of the headers UPX writes after the program headers and at the end of the file
+const UPX_MAGIC: &[u8] = b"UPX!";
+/// Start of the identification string of the UPX stub, followed by the version
+const UPX_ID: &[u8] = b"$Id: UPX ";
+/// Section names UPX gives the sections of the files it packs, when it keeps sections
+const UPX_SECTIONS: [&str; 3] = ["UPX0", "UPX1", "UPX2"];
+/// Number of bytes at the end of the file searched for the UPX trailer
+const UPX_TRAILER_LEN: usize = 64;
+
+/// Strings the stubs of other packers keep in their code segment, with the names of the packers
+const STUB_SIGNATURES: [(&str, &[u8]); 1] = [
+    // Identification string of the TESO ELF Encryption Engine
+    ("Burneye", b"TEEE burneye"),
+];
+/// Section names other packers give the sections of the files they pack, with the names of the
+/// packers
+const SECTION_SIGNATURES: [(&str, &str); 2] = [("MPRESS", ".MPRESS1"), ("MPRESS", ".MPRESS2")];
+
+/// Entropy, in bits per byte, past which executable contents are taken for compressed or
+/// encrypted data rather than code
+const HIGH_ENTROPY: f64 = 7.2;
+/// Number of times its file size the memory size of the last loadable segment has to be for it to
+/// be taken for room to unpack into
+const UNPACK_RATIO: u64 = 8;
+/// Smallest memory size of the last loadable segment taken for room to unpack into
+const MIN_UNPACK_SIZE: u64 = 0x10_0000;
+
+/// What the file looks like, packed or not
+#[derive(Debug, Clone, PartialEq)]
+pub enum PackerInfo {
+    /// Packed by UPX, with its version when the stub still holds its identification string
+    Upx { version: Option<String> },
+    /// Packed by the packer `name`, other than UPX, whose signature was found
+    Known { name: &'static str },
+    /// Packed by a packer which is not named, going by the anomalies found
+    Suspicious(Vec<PackerAnomaly>),
+    NotPacked,
+}
+
+/// Feature of the layout of a file which is usual for packed files, but not for linked ones
+#[derive(Debug, Clone, PartialEq)]
+pub enum PackerAnomaly {
+    /// An executable or shared object without a section header table, which linkers always write
+    NoSectionHeaders,
+    /// The last loadable segment is executable and reserves much more memory than its file
+    /// contents need, where linked files only have zeroed data
+    LargeLastLoad { file_size: Size, mem_size: Size },
+    /// The loadable segment at this index is writable and executable, as unpacked code is
+    /// written where it runs
+    WritableCode(usize),
+    /// The contents of the executable loadable segment at this index are as random as compressed
+    /// or encrypted data
+    HighEntropy { index: usize, entropy: f64 },
+}
+
+/// Looks for signs that the file, whose bytes are `bytes`, is packed
+pub fn detect(elf: &Elf64, bytes: &[u8]) -> PackerInfo {
+    if is_upx(elf, bytes) {
+        let version = find(bytes, UPX_ID).map(|index| {
+            let version = &bytes[index + UPX_ID.len()..];
+            let len = version.iter().position(|byte| !byte.is_ascii_graphic());
+            let len = len.unwrap_or(version.len());
+            String::from_utf8_lossy(&version[..len]).into_owned()
+        });
+        return PackerInfo::Upx { version };
+    }
+    if let Some(name) = signature(elf) {
+        return PackerInfo::Known { name };
+    }
+
+    let anomalies = anomalies(elf);
+    if anomalies.is_empty() {
+        PackerInfo::NotPacked
+    } else {
+        PackerInfo::Suspicious(anomalies)
+    }
+}
+
+/// UPX leaves its magic right after the program headers, after a checksum, and in the trailer at
+/// the end of the file
+fn is_upx(elf: &Elf64, bytes: &[u8]) -> bool {
+    let header = &elf.elf_header;
+    let phdrs_size = usize::from(header.e_phnum) * usize::from(header.e_phentsize);
+    let start = (header.e_phoff.0 as usize).saturating_add(phdrs_size).saturating_add(4);
+    let info_magic = bytes.get(start..start.saturating_add(4));
+    let trailer = &bytes[bytes.len().saturating_sub(UPX_TRAILER_LEN)..];
+    let has_sections = elf
+        .sh_table
+        .iter()
+        .filter_map(|sh| elf.section_name(sh))
+        .any(|name| UPX_SECTIONS.contains(&name.as_str()));
+    info_magic == Some(UPX_MAGIC) || find(trailer, UPX_MAGIC).is_some() || has_sections
+}
+
+/// Looks for the signatures of other packers in the section names and in the contents of the
+/// executable loadable segments, where their stubs run from
+fn signature(elf: &Elf64) -> Option<&'static str> {
+    let names: Vec<_> = elf.sh_table.iter().filter_map(|sh| elf.section_name(sh)).collect();
+    let by_section = SECTION_SIGNATURES
+        .iter()
+        .find(|(_, section)| names.iter().any(|name| name == section))
+        .map(|(packer, _)| *packer);
+    by_section.or_else(|| {
+        let code = elf.ph_table.iter().filter(|ph| {
+            ph.p_type() == SegmentType::PtLoad && ph.p_flags().contains(SegmentFlags::EXEC)
+        });
+        let by_stub = STUB_SIGNATURES
+            .iter()
+            .find(|(_, stub)| code.clone().any(|ph| find(&ph.data, stub).is_some()));
+        by_stub.map(|(packer, _)| *packer)
+    })
+}
+
+fn anomalies(elf: &Elf64) -> Vec<PackerAnomaly> {
+    let mut anomalies = vec![];
+    let linked = matches!(elf.elf_header.e_type, FileType::EtExec | FileType::EtDyn);
+    if linked && elf.sh_table.is_empty() {
+        anomalies.push(PackerAnomaly::NoSectionHeaders);
+    }
+
+    let is_load = |(_, ph): &(usize, &ProgramHeader)| ph.p_type() == SegmentType::PtLoad;
+    let loads = elf.ph_table.iter().enumerate().filter(is_load);
+    if let Some((_, last)) = loads.clone().next_back() {
+        let (file_size, mem_size) = (last.p_filesz(), last.p_memsz());
+        let large = mem_size.0 >= MIN_UNPACK_SIZE && mem_size.0 / UNPACK_RATIO >= file_size.0;
+        if large && last.p_flags().contains(SegmentFlags::EXEC) {
+            anomalies.push(PackerAnomaly::LargeLastLoad { file_size, mem_size });
+        }
+    }
+    for (index, ph) in loads.filter(|(_, ph)| ph.p_flags().contains(SegmentFlags::EXEC)) {
+        if ph.p_flags().contains(SegmentFlags::WRITE) {
+            anomalies.push(PackerAnomaly::WritableCode(index));
+        }
+        let entropy = entropy::entropy(&ph.data);
+        if entropy >= HIGH_ENTROPY {
+            anomalies.push(PackerAnomaly::HighEntropy { index, entropy });
+        }
+    }
+    anomalies
+}
+
+fn find(bytes: &[u8], needle: &[u8]) -> Option<usize> {
+    bytes.windows(needle.len()).position(|window| window == needle)
+}
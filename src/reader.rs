@@ -1,15 +1,27 @@
-use core::{mem::size_of, ops::Range};
+use core::ops::Range;
 
-use crate::error::ParseError;
+use crate::{class::Class, endian::Endianness, error::ParseError};
+
+/// Position of a `Reader`, saved to roll back to after reading ahead
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Checkpoint(usize);
 
 pub struct Reader<'a> {
     pub bytes: &'a [u8],
     pub index: usize,
+    /// Class of the file being read, which decides the width of `read_word`
+    pub class: Class,
+    /// Byte order used when reading multi-byte integers
+    pub endianness: Endianness,
 }
 
 impl<'a> Reader<'a> {
     pub fn from_bytes(bytes: &'a [u8]) -> Self {
-        Reader {bytes, index: 0}
+        Reader {bytes, index: 0, class: Class::Elf64, endianness: Endianness::Little}
+    }
+
+    pub fn new(bytes: &'a [u8], class: Class, endianness: Endianness) -> Self {
+        Reader {bytes, index: 0, class, endianness}
     }
 
     pub fn seek(&mut self, offset: usize) -> Result<(), ParseError> {
@@ -21,48 +33,232 @@ impl<'a> Reader<'a> {
 
         Ok(())
     }
-    pub fn read_slice(&mut self, size: usize) -> Result<&[u8], ParseError> {
-        let range = Range { start: self.index, end: self.index + size };
-        self.index += size;
-        self.read_slice_from(range)
+
+    /// Returns the offset of the next byte to read
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Moves to `offset`, which unlike with `seek` may be the end of the bytes
+    pub fn set_position(&mut self, offset: usize) -> Result<(), ParseError> {
+        if offset > self.bytes.len() {
+            return Err(ParseError::OutOfBounds);
+        }
+        self.index = offset;
+        Ok(())
+    }
+
+    /// Moves forward to the next offset which is a multiple of `alignment`, unless already on
+    /// one. Fails if that offset is past the end of the bytes.
+    pub fn align_to(&mut self, alignment: usize) -> Result<(), ParseError> {
+        let offset = self
+            .index
+            .checked_next_multiple_of(alignment.max(1))
+            .ok_or(ParseError::OutOfBounds)?;
+        self.set_position(offset)
+    }
+
+    /// Saves the current position, to come back to it with `rollback`
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.index)
+    }
+
+    /// Goes back to the position saved by `checkpoint`
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.index = checkpoint.0;
+    }
+
+    /// Returns a reader over `range` of the bytes only, with the same class and byte order, which
+    /// cannot read past the end of the range whatever the lengths it reads claim
+    pub fn sub_reader(&self, range: Range<usize>) -> Result<Reader<'a>, ParseError> {
+        Ok(Reader::new(self.read_slice_from(range)?, self.class, self.endianness))
+    }
+
+    /// Returns a reader at the same position, which reads ahead without moving this one
+    fn lookahead(&self) -> Reader<'a> {
+        let mut reader = Reader::new(self.bytes, self.class, self.endianness);
+        reader.index = self.index;
+        reader
+    }
+
+    pub fn peek_slice(&self, size: usize) -> Result<&'a [u8], ParseError> {
+        self.lookahead().read_slice(size)
+    }
+
+    pub fn peek_u8(&self) -> Result<u8, ParseError> {
+        self.lookahead().read_u8()
+    }
+
+    pub fn peek_u16(&self) -> Result<u16, ParseError> {
+        self.lookahead().read_u16()
+    }
+
+    pub fn peek_u32(&self) -> Result<u32, ParseError> {
+        self.lookahead().read_u32()
+    }
+
+    pub fn peek_u64(&self) -> Result<u64, ParseError> {
+        self.lookahead().read_u64()
+    }
+
+    pub fn read_slice(&mut self, size: usize) -> Result<&'a [u8], ParseError> {
+        self.take(size)
     }
 
     pub fn read_slice_from(
         &self,
         range: Range<usize>
-    ) -> Result<&[u8], ParseError> {
-        self.bytes.get(range).ok_or(ParseError::OutOfBounds)
+    ) -> Result<&'a [u8], ParseError> {
+        self.bytes.get(range.clone()).ok_or(ParseError::BadRange(range))
     }
 
-    pub fn read_u8(&mut self) -> Result<u8, ParseError> {
-        let size = size_of::<u8>();
-        let range = Range { start: self.index, end: self.index + size };
+    /// Returns the next `size` bytes and moves past them. On failure, the position is left as it
+    /// was, so the reader can still be used.
+    fn take(&mut self, size: usize) -> Result<&'a [u8], ParseError> {
+        let bytes = self
+            .index
+            .checked_add(size)
+            .and_then(|end| self.bytes.get(self.index..end))
+            .ok_or(ParseError::ReadOutOfBounds { offset: self.index, size })?;
         self.index += size;
-        let subslice = self.read_slice_from(range)?;
-        Ok(u8::from_le_bytes(subslice.try_into().unwrap()))
-     }
+        Ok(bytes)
+    }
+
+    /// Reads a null terminated string and moves past its terminator, which is not returned
+    pub fn read_cstr(&mut self) -> Result<&'a [u8], ParseError> {
+        let string = self.read_cstr_at(self.index)?;
+        self.index += string.len() + 1;
+        Ok(string)
+    }
+
+    /// Returns the null terminated string starting at `offset`, without its terminator and
+    /// without moving
+    pub fn read_cstr_at(&self, offset: usize) -> Result<&'a [u8], ParseError> {
+        let rest = self.bytes.get(offset..).ok_or(ParseError::OutOfBounds)?;
+        let len = rest.iter().position(|&c| c == 0).ok_or(ParseError::Unterminated(offset))?;
+        Ok(&rest[..len])
+    }
+
+    /// Takes the next `N` bytes, sized for the integer they are decoded into
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], ParseError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, ParseError> {
+        Ok(u8::from_le_bytes(self.take_array()?))
+    }
 
     pub fn read_u16(&mut self) -> Result<u16, ParseError> {
-        let size = size_of::<u16>();
-        let range = Range { start: self.index, end: self.index + size };
-        self.index += size;
-        let subslice = self.read_slice_from(range)?;
-        Ok(u16::from_le_bytes(subslice.try_into().unwrap()))
-     }
+        let bytes = self.take_array()?;
+        match self.endianness {
+            Endianness::Little => Ok(u16::from_le_bytes(bytes)),
+            Endianness::Big => Ok(u16::from_be_bytes(bytes)),
+        }
+    }
 
     pub fn read_u32(&mut self) -> Result<u32, ParseError> {
-        let size = size_of::<u32>();
-        let range = Range { start: self.index, end: self.index + size };
-        self.index += size;
-        let subslice = self.read_slice_from(range)?;
-        Ok(u32::from_le_bytes(subslice.try_into().unwrap()))
-     }
+        let bytes = self.take_array()?;
+        match self.endianness {
+            Endianness::Little => Ok(u32::from_le_bytes(bytes)),
+            Endianness::Big => Ok(u32::from_be_bytes(bytes)),
+        }
+    }
 
     pub fn read_u64(&mut self) -> Result<u64, ParseError> {
-        let size = size_of::<u64>();
-        let range = Range { start: self.index, end: self.index + size };
-        self.index += size;
-        let subslice = self.read_slice_from(range)?;
-        Ok(u64::from_le_bytes(subslice.try_into().unwrap()))
-     }
+        let bytes = self.take_array()?;
+        match self.endianness {
+            Endianness::Little => Ok(u64::from_le_bytes(bytes)),
+            Endianness::Big => Ok(u64::from_be_bytes(bytes)),
+        }
+    }
+
+    pub fn read_i8(&mut self) -> Result<i8, ParseError> {
+        Ok(self.read_u8()? as i8)
+    }
+
+    pub fn read_i16(&mut self) -> Result<i16, ParseError> {
+        Ok(self.read_u16()? as i16)
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, ParseError> {
+        Ok(self.read_u32()? as i32)
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, ParseError> {
+        Ok(self.read_u64()? as i64)
+    }
+
+    /// Reads an unsigned LEB128 value: 7 bits per byte, lowest first, with the top bit set on
+    /// every byte but the last
+    pub fn read_uleb128(&mut self) -> Result<u64, ParseError> {
+        let checkpoint = self.checkpoint();
+        let value = self.read_uleb128_bytes();
+        if value.is_err() {
+            self.rollback(checkpoint);
+        }
+        value
+    }
+
+    fn read_uleb128_bytes(&mut self) -> Result<u64, ParseError> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift >= 64 || (shift == 63 && byte & 0x7F > 1) {
+                return Err(ParseError::BadLeb128);
+            }
+            value |= u64::from(byte & 0x7F) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Reads a signed LEB128 value, whose last byte carries the sign in its bit 6
+    pub fn read_sleb128(&mut self) -> Result<i64, ParseError> {
+        let checkpoint = self.checkpoint();
+        let value = self.read_sleb128_bytes();
+        if value.is_err() {
+            self.rollback(checkpoint);
+        }
+        value
+    }
+
+    fn read_sleb128_bytes(&mut self) -> Result<i64, ParseError> {
+        let mut value = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift >= 64 {
+                return Err(ParseError::BadLeb128);
+            }
+            value |= i64::from(byte & 0x7F) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Reads an address, offset or size field, whose width depends on the file class.
+    /// 32-bit values are zero-extended.
+    pub fn read_word(&mut self) -> Result<u64, ParseError> {
+        match self.class {
+            Class::Elf32 => Ok(self.read_u32()?.into()),
+            Class::Elf64 => self.read_u64(),
+        }
+    }
+
+    /// Reads a signed field whose width depends on the file class, like `r_addend`. 32-bit values
+    /// are sign-extended.
+    pub fn read_sword(&mut self) -> Result<i64, ParseError> {
+        match self.class {
+            Class::Elf32 => Ok(self.read_i32()?.into()),
+            Class::Elf64 => self.read_i64(),
+        }
+    }
 }
@@ -3,7 +3,11 @@ use thiserror::Error;
 use crate::{
     addr::Addr,
     reader::Reader,
-    error::SegmentError,
+    error::{ParseError, SegmentError},
+    class::Class,
+    endian::Endianness,
+    machine::Machine,
+    sym::Symbol,
 };
 
 /// Structure of a relocation entry. Rela entries contain an explicit addend.
@@ -21,15 +25,20 @@ pub struct Rela {
     pub r_sym: u32,
     /// This member specifies a contant addend used to compute the value to be stored
     /// into th relocatable field.
-    pub r_addend: u64,
+    pub r_addend: i64,
 }
 
 impl Rela {
-    pub fn parse(reader: &mut Reader) -> Result<Self, SegmentError> {
-        let r_offset = Addr::from(reader.read_u64()?);
-        let r_type = RelType::try_from(reader.read_u32()?)?;
-        let r_sym = reader.read_u32()?;
-        let r_addend = reader.read_u64()?;
+    /// Returns the size, in bytes, of a `Rela` entry for the given `class`
+    pub fn size(class: Class) -> usize {
+        3 * class.word_size()
+    }
+
+    /// Parses an entry of a file targeting `machine`, which the relocation type depends on
+    pub fn parse(reader: &mut Reader, machine: Machine) -> Result<Self, SegmentError> {
+        let r_offset = Addr::parse(reader)?;
+        let (r_type, r_sym) = parse_info(reader, machine)?;
+        let r_addend = reader.read_sword()?;
 
         Ok(Self {
             r_offset,
@@ -39,10 +48,369 @@ impl Rela {
         })
     }
 }
+/// Structure of a relocation entry without an addend, which is instead read from the location
+/// being relocated. Used by i386 and some other 32-bit architectures.
+#[derive(Debug)]
+pub struct Rel {
+    /// Gives the location at which to apply the relocation action
+    pub r_offset: Addr,
+    /// The type of relocation to apply
+    pub r_type: RelType,
+    /// Symbol table index, with respect to which the relocation must be made
+    pub r_sym: u32,
+}
+
+impl Rel {
+    /// Returns the size, in bytes, of a `Rel` entry for the given `class`
+    pub fn size(class: Class) -> usize {
+        2 * class.word_size()
+    }
+
+    /// Parses an entry of a file targeting `machine`, which the relocation type depends on
+    pub fn parse(reader: &mut Reader, machine: Machine) -> Result<Self, SegmentError> {
+        let r_offset = Addr::parse(reader)?;
+        let (r_type, r_sym) = parse_info(reader, machine)?;
+
+        Ok(Self { r_offset, r_type, r_sym })
+    }
+}
+
+/// Parses `r_info`, which packs the symbol index and the relocation type. 64-bit files split it
+/// into two 32-bit halves, while 32-bit files keep the type in the low byte.
+fn parse_info(reader: &mut Reader, machine: Machine) -> Result<(RelType, u32), SegmentError> {
+    let (r_type, r_sym) = match reader.class {
+        Class::Elf32 => {
+            let r_info = reader.read_u32()?;
+            (r_info & 0xFF, r_info >> 8)
+        }
+        Class::Elf64 => (reader.read_u32()?, reader.read_u32()?),
+    };
+    Ok((RelType::new(machine, r_type), r_sym))
+}
+
+/// The relocations of the procedure linkage table, `DT_JMPREL`, in the format `DT_PLTREL` gives.
+/// Each one binds a function slot, lazily unless the object is bound at load time.
+#[derive(Debug)]
+pub enum PltRelocations {
+    Rel(Vec<Rel>),
+    Rela(Vec<Rela>),
+}
+
+impl PltRelocations {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Rel(rels) => rels.len(),
+            Self::Rela(relas) => relas.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the offset, type and symbol index of every relocation, whatever its format, which
+    /// is enough to match the slots to the imported functions they bind
+    pub fn entries(&self) -> Box<dyn Iterator<Item = (Addr, RelType, u32)> + '_> {
+        match self {
+            Self::Rel(rels) => {
+                Box::new(rels.iter().map(|rel| (rel.r_offset, rel.r_type, rel.r_sym)))
+            }
+            Self::Rela(relas) => {
+                Box::new(relas.iter().map(|rela| (rela.r_offset, rela.r_type, rela.r_sym)))
+            }
+        }
+    }
+}
 
+/// Relocation table a `Relocation` comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelocationTable {
+    /// `DT_RELA`
+    Rela,
+    /// `DT_REL`
+    Rel,
+    /// `DT_RELR`, packed relative relocations
+    Relr,
+    /// `DT_JMPREL`, the relocations of the procedure linkage table
+    JmpRel,
+    /// `SHT_RELA` or `SHT_REL` section, of the given index
+    Section(u32),
+}
 
+/// A relocation of any of the dynamic relocation tables, whatever the format of its entries
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Relocation {
+    pub r_offset: Addr,
+    /// Type of the relocation, the relative one of the machine for `DT_RELR` entries
+    pub r_type: RelType,
+    /// Symbol table index, 0 for relocations without a symbol
+    pub r_sym: u32,
+    /// Explicit addend, or `None` when it is stored at the relocated location, as for `Rel` and
+    /// `Relr` entries
+    pub r_addend: Option<i64>,
+    pub table: RelocationTable,
+}
+
+impl Relocation {
+    pub fn from_rela(rela: &Rela, table: RelocationTable) -> Self {
+        let (r_offset, r_type, r_sym) = (rela.r_offset, rela.r_type, rela.r_sym);
+        Self { r_offset, r_type, r_sym, r_addend: Some(rela.r_addend), table }
+    }
+
+    pub fn from_rel(rel: &Rel, table: RelocationTable) -> Self {
+        let (r_offset, r_type, r_sym) = (rel.r_offset, rel.r_type, rel.r_sym);
+        Self { r_offset, r_type, r_sym, r_addend: None, table }
+    }
+}
+
+/// A relocation together with the dynamic symbol it refers to, the way `readelf -r` lists it
+#[derive(Debug)]
+pub struct NamedRelocation {
+    pub relocation: Relocation,
+    /// Symbol `r_sym` refers to, whose entry gives its binding and type, or `None` for
+    /// relocations without a symbol
+    pub symbol: Option<Symbol>,
+}
+
+/// A relative relocation, storing the load base plus the addend at `r_offset`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RelativeRelocation {
+    pub r_offset: Addr,
+    /// Explicit addend, or `None` when it is stored at the relocated location
+    pub r_addend: Option<i64>,
+}
+
+/// The relocations of a `SHT_RELA` or `SHT_REL` section
+#[derive(Debug)]
+pub struct RelocationSection {
+    /// Index of the relocation section
+    pub index: usize,
+    /// Index of the symbol table the relocations refer to, `sh_link`
+    pub symtab: u32,
+    /// Index of the section the relocations apply to, `sh_info`
+    pub target: u32,
+    pub relocations: Vec<NamedRelocation>,
+}
+
+/// An indirect function, `STT_GNU_IFUNC`, bound through an `R_X86_64_IRELATIVE` relocation. The
+/// loader calls the resolver and stores the address it returns in the target slot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndirectFunction {
+    /// Address of the resolver, choosing the implementation of the function
+    pub resolver: Addr,
+    /// Address of the slot receiving the implementation, usually a GOT entry
+    pub target: Addr,
+}
+
+/// Decodes a table of packed relative relocations, `SHT_RELR`, into the offsets to relocate. An
+/// even entry is the address of a relocation, after which the next word starts the range of the
+/// following odd entries: bitmaps whose bits, above the lowest one, flag which of the next words
+/// are relocated.
+pub fn decode_relr(
+    bytes: &[u8],
+    class: Class,
+    endianness: Endianness,
+) -> Result<Vec<Addr>, ParseError> {
+    let word_size = class.word_size() as u64;
+    let bitmap_bits = word_size * 8 - 1;
+    let mut reader = Reader::new(bytes, class, endianness);
+    let mut offsets = vec![];
+    // Address of the word the next bitmap starts at
+    let mut base = 0u64;
+    while reader.index < bytes.len() {
+        let entry = reader.read_word()?;
+        if entry & 1 == 0 {
+            offsets.push(Addr(entry));
+            base = entry.wrapping_add(word_size);
+            continue;
+        }
+        for bit in 0..bitmap_bits {
+            if entry >> (bit + 1) & 1 != 0 {
+                offsets.push(Addr(base.wrapping_add(bit * word_size)));
+            }
+        }
+        base = base.wrapping_add(bitmap_bits * word_size);
+    }
+    Ok(offsets)
+}
+
+/// Magic starting the Android packed relocation tables
+pub const APS2_MAGIC: &[u8] = b"APS2";
+
+// Flags of a group of Android packed relocations
+const GROUPED_BY_INFO: i64 = 0x1;
+const GROUPED_BY_OFFSET_DELTA: i64 = 0x2;
+const GROUPED_BY_ADDEND: i64 = 0x4;
+const GROUP_HAS_ADDEND: i64 = 0x8;
+
+/// A relocation decoded from an Android packed table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PackedRelocation {
+    pub r_offset: Addr,
+    pub r_type: RelType,
+    pub r_sym: u32,
+    /// Explicit addend, always 0 in `Rel` tables
+    pub r_addend: i64,
+}
+
+/// Decodes an Android packed relocation table, `APS2`, of a file targeting `machine`, holding
+/// `Rela` entries if `is_rela`, or else `Rel` ones. After the magic, every field is a SLEB128:
+/// the number of relocations and the initial offset, followed by groups of relocations which may
+/// share their info, offset delta or addend, stored once in the group header instead of for
+/// every relocation.
+/// Groups sharing all of these take no bytes per relocation, so the count the table starts with
+/// is only trusted up to `max_count` relocations.
+pub fn decode_android_packed(
+    bytes: &[u8],
+    class: Class,
+    endianness: Endianness,
+    machine: Machine,
+    is_rela: bool,
+    max_count: usize,
+) -> Result<Vec<PackedRelocation>, Error> {
+    let body = bytes.strip_prefix(APS2_MAGIC).ok_or(Error::NotPacked)?;
+    let mut reader = Reader::new(body, class, endianness);
+
+    let mut remaining = reader.read_sleb128()?;
+    if u64::try_from(remaining).is_ok_and(|count| count > max_count as u64) {
+        return Err(Error::TooManyRelocations(remaining));
+    }
+    let mut r_offset = reader.read_sleb128()? as u64;
+    let (mut r_info, mut r_addend) = (0u64, 0i64);
+    let mut relocations = vec![];
+    while remaining > 0 {
+        // Groups of 0 or less relocations would never exhaust the count
+        let group_size = reader.read_sleb128()?;
+        if group_size <= 0 {
+            return Err(Error::BadGroupSize(group_size));
+        }
+        let flags = reader.read_sleb128()?;
+        let offset_delta = if flags & GROUPED_BY_OFFSET_DELTA != 0 {
+            reader.read_sleb128()? as u64
+        } else {
+            0
+        };
+        if flags & GROUPED_BY_INFO != 0 {
+            r_info = reader.read_sleb128()? as u64;
+        }
+        let has_addend = flags & GROUP_HAS_ADDEND != 0;
+        if has_addend && !is_rela {
+            return Err(Error::AddendInRel);
+        }
+        if has_addend && flags & GROUPED_BY_ADDEND != 0 {
+            r_addend = r_addend.wrapping_add(reader.read_sleb128()?);
+        } else if !has_addend {
+            r_addend = 0;
+        }
+
+        for _ in 0..group_size.min(remaining) {
+            r_offset = r_offset.wrapping_add(if flags & GROUPED_BY_OFFSET_DELTA != 0 {
+                offset_delta
+            } else {
+                reader.read_sleb128()? as u64
+            });
+            if flags & GROUPED_BY_INFO == 0 {
+                r_info = reader.read_sleb128()? as u64;
+            }
+            if has_addend && flags & GROUPED_BY_ADDEND == 0 {
+                r_addend = r_addend.wrapping_add(reader.read_sleb128()?);
+            }
+            let (r_type, r_sym) = match class {
+                Class::Elf32 => (r_info as u32 & 0xFF, r_info as u32 >> 8),
+                Class::Elf64 => (r_info as u32, (r_info >> 32) as u32),
+            };
+            let (r_offset, r_type) = (Addr(r_offset), RelType::new(machine, r_type));
+            relocations.push(PackedRelocation { r_offset, r_type, r_sym, r_addend });
+        }
+        remaining -= group_size;
+    }
+    Ok(relocations)
+}
+
+/// Type of a relocation. Relocation types are numbered per machine, so the same value means
+/// different things depending on `e_machine`.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RelType {
+    X86_64(X64RelType),
+    I386(I386RelType),
+    AArch64(AArch64RelType),
+    Arm(ArmRelType),
+    RiscV(RiscVRelType),
+    /// Type of a machine without a table here, or missing from the table of its machine
+    Other(u32),
+    /// Relative relocation of a `DT_RELR` table, which has no type number, on a machine without
+    /// a table here to give its `R_*_RELATIVE` type
+    Relr,
+}
+
+impl RelType {
+    /// Decodes the relocation type `value` of the `machine` the file targets
+    pub fn new(machine: Machine, value: u32) -> Self {
+        let rel_type = match machine {
+            Machine::AmdX86_64 => X64RelType::try_from(value).map(Self::X86_64),
+            Machine::X86 => I386RelType::try_from(value).map(Self::I386),
+            Machine::AArch64 => AArch64RelType::try_from(value).map(Self::AArch64),
+            Machine::Arm => ArmRelType::try_from(value).map(Self::Arm),
+            Machine::RiscV => RiscVRelType::try_from(value).map(Self::RiscV),
+            _ => Err(Error::InvalidRelocationType(value)),
+        };
+        rel_type.unwrap_or(Self::Other(value))
+    }
+
+    /// Returns the `R_*_RELATIVE` type of `machine`, which machines without a table here lack
+    pub fn relative(machine: Machine) -> Option<Self> {
+        match machine {
+            Machine::AmdX86_64 => Some(Self::X86_64(X64RelType::Relative)),
+            Machine::X86 => Some(Self::I386(I386RelType::Relative)),
+            Machine::AArch64 => Some(Self::AArch64(AArch64RelType::Relative)),
+            Machine::Arm => Some(Self::Arm(ArmRelType::Relative)),
+            Machine::RiscV => Some(Self::RiscV(RiscVRelType::Relative)),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the relocation adds the load base to its addend, `R_*_RELATIVE`
+    pub fn is_relative(&self) -> bool {
+        matches!(
+            self,
+            Self::Relr
+                | Self::X86_64(X64RelType::Relative)
+                | Self::I386(I386RelType::Relative)
+                | Self::AArch64(AArch64RelType::Relative)
+                | Self::Arm(ArmRelType::Relative)
+                | Self::RiscV(RiscVRelType::Relative)
+        )
+    }
+
+    /// Returns whether the relocation stores the address an indirect function resolver returns,
+    /// `R_*_IRELATIVE`
+    pub fn is_irelative(&self) -> bool {
+        matches!(
+            self,
+            Self::X86_64(X64RelType::IRelative)
+                | Self::I386(I386RelType::IRelative)
+                | Self::AArch64(AArch64RelType::IRelative)
+                | Self::Arm(ArmRelType::IRelative)
+                | Self::RiscV(RiscVRelType::IRelative)
+        )
+    }
+
+    /// Returns whether the relocation binds a procedure linkage table slot, `R_*_JUMP_SLOT`
+    pub fn is_jump_slot(&self) -> bool {
+        matches!(
+            self,
+            Self::X86_64(X64RelType::JumpSlot)
+                | Self::I386(I386RelType::JmpSlot)
+                | Self::AArch64(AArch64RelType::JumpSlot)
+                | Self::Arm(ArmRelType::JumpSlot)
+                | Self::RiscV(RiscVRelType::JumpSlot)
+        )
+    }
+}
+
+/// Relocation types of x86-64, `R_X86_64_*`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum X64RelType {
     None,
     W64,
     Pc32,
@@ -52,11 +420,54 @@ pub enum RelType {
     GlobDat,
     JumpSlot,
     Relative,
+    GotPcRel,
+    W32,
+    /// 32-bit value, sign extended to 64 bits
+    W32S,
+    W16,
+    Pc16,
+    W8,
+    Pc8,
+    /// Module index of the thread local storage block of the symbol
+    DtpMod64,
+    /// Offset of the symbol in its thread local storage block
+    DtpOff64,
+    /// Offset of the symbol from the thread pointer, in the static TLS block
+    TpOff64,
+    /// General dynamic TLS access, through a `tls_index` pair in the GOT
+    TlsGd,
+    /// Local dynamic TLS access, through the module index in the GOT
+    TlsLd,
+    DtpOff32,
+    /// Initial exec TLS access, through a thread pointer offset in the GOT
+    GotTpOff,
+    TpOff32,
+    Pc64,
+    GotOff64,
+    GotPc32,
+    Got64,
+    GotPcRel64,
+    GotPc64,
+    GotPlt64,
+    PltOff64,
+    Size32,
+    Size64,
+    GotPc32TlsDesc,
+    TlsDescCall,
+    TlsDesc,
+    /// The addend is the address of an indirect function resolver, and the address it returns is
+    /// stored at the relocation offset
+    IRelative,
+    Relative64,
+    /// `GOTPCREL` which the linker may relax into a direct reference
+    GotPcRelX,
+    /// `GOTPCRELX` of an instruction with a REX prefix
+    RexGotPcRelX,
 }
 
-impl TryFrom<u32> for RelType {
+impl TryFrom<u32> for X64RelType {
     type Error = Error;
-    fn try_from(value: u32) -> Result<RelType, Self::Error> {
+    fn try_from(value: u32) -> Result<X64RelType, Self::Error> {
         let rel_type = match value {
             0 => Self::None,
             1 => Self::W64,
@@ -67,6 +478,240 @@ impl TryFrom<u32> for RelType {
             6 => Self::GlobDat,
             7 => Self::JumpSlot,
             8 => Self::Relative,
+            9 => Self::GotPcRel,
+            10 => Self::W32,
+            11 => Self::W32S,
+            12 => Self::W16,
+            13 => Self::Pc16,
+            14 => Self::W8,
+            15 => Self::Pc8,
+            16 => Self::DtpMod64,
+            17 => Self::DtpOff64,
+            18 => Self::TpOff64,
+            19 => Self::TlsGd,
+            20 => Self::TlsLd,
+            21 => Self::DtpOff32,
+            22 => Self::GotTpOff,
+            23 => Self::TpOff32,
+            24 => Self::Pc64,
+            25 => Self::GotOff64,
+            26 => Self::GotPc32,
+            27 => Self::Got64,
+            28 => Self::GotPcRel64,
+            29 => Self::GotPc64,
+            30 => Self::GotPlt64,
+            31 => Self::PltOff64,
+            32 => Self::Size32,
+            33 => Self::Size64,
+            34 => Self::GotPc32TlsDesc,
+            35 => Self::TlsDescCall,
+            36 => Self::TlsDesc,
+            37 => Self::IRelative,
+            38 => Self::Relative64,
+            41 => Self::GotPcRelX,
+            42 => Self::RexGotPcRelX,
+            _ => return Err(Error::InvalidRelocationType(value)),
+        };
+
+        Ok(rel_type)
+    }
+}
+
+/// Relocation types of 32-bit x86, `R_386_*`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum I386RelType {
+    None,
+    W32,
+    Pc32,
+    Got32,
+    Plt32,
+    Copy,
+    GlobDat,
+    JmpSlot,
+    Relative,
+    GotOff,
+    GotPc,
+    TlsTpOff,
+    TlsDtpMod32,
+    TlsDtpOff32,
+    TlsTpOff32,
+    IRelative,
+}
+
+impl TryFrom<u32> for I386RelType {
+    type Error = Error;
+    fn try_from(value: u32) -> Result<I386RelType, Self::Error> {
+        let rel_type = match value {
+            0 => Self::None,
+            1 => Self::W32,
+            2 => Self::Pc32,
+            3 => Self::Got32,
+            4 => Self::Plt32,
+            5 => Self::Copy,
+            6 => Self::GlobDat,
+            7 => Self::JmpSlot,
+            8 => Self::Relative,
+            9 => Self::GotOff,
+            10 => Self::GotPc,
+            14 => Self::TlsTpOff,
+            35 => Self::TlsDtpMod32,
+            36 => Self::TlsDtpOff32,
+            37 => Self::TlsTpOff32,
+            42 => Self::IRelative,
+            _ => return Err(Error::InvalidRelocationType(value)),
+        };
+
+        Ok(rel_type)
+    }
+}
+
+/// Relocation types of 64-bit ARM, `R_AARCH64_*`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AArch64RelType {
+    None,
+    Abs64,
+    Abs32,
+    Prel32,
+    AdrPrelPgHi21,
+    AddAbsLo12Nc,
+    Jump26,
+    Call26,
+    Copy,
+    GlobDat,
+    JumpSlot,
+    Relative,
+    TlsDtpMod,
+    TlsDtpRel,
+    TlsTpRel,
+    TlsDesc,
+    IRelative,
+}
+
+impl TryFrom<u32> for AArch64RelType {
+    type Error = Error;
+    fn try_from(value: u32) -> Result<AArch64RelType, Self::Error> {
+        let rel_type = match value {
+            0 => Self::None,
+            257 => Self::Abs64,
+            258 => Self::Abs32,
+            261 => Self::Prel32,
+            275 => Self::AdrPrelPgHi21,
+            277 => Self::AddAbsLo12Nc,
+            282 => Self::Jump26,
+            283 => Self::Call26,
+            1024 => Self::Copy,
+            1025 => Self::GlobDat,
+            1026 => Self::JumpSlot,
+            1027 => Self::Relative,
+            1028 => Self::TlsDtpMod,
+            1029 => Self::TlsDtpRel,
+            1030 => Self::TlsTpRel,
+            1031 => Self::TlsDesc,
+            1032 => Self::IRelative,
+            _ => return Err(Error::InvalidRelocationType(value)),
+        };
+
+        Ok(rel_type)
+    }
+}
+
+/// Relocation types of 32-bit ARM, `R_ARM_*`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArmRelType {
+    None,
+    Abs32,
+    Rel32,
+    TlsDtpMod32,
+    TlsDtpOff32,
+    TlsTpOff32,
+    Copy,
+    GlobDat,
+    JumpSlot,
+    Relative,
+    Call,
+    Jump24,
+    IRelative,
+}
+
+impl TryFrom<u32> for ArmRelType {
+    type Error = Error;
+    fn try_from(value: u32) -> Result<ArmRelType, Self::Error> {
+        let rel_type = match value {
+            0 => Self::None,
+            2 => Self::Abs32,
+            3 => Self::Rel32,
+            17 => Self::TlsDtpMod32,
+            18 => Self::TlsDtpOff32,
+            19 => Self::TlsTpOff32,
+            20 => Self::Copy,
+            21 => Self::GlobDat,
+            22 => Self::JumpSlot,
+            23 => Self::Relative,
+            28 => Self::Call,
+            29 => Self::Jump24,
+            160 => Self::IRelative,
+            _ => return Err(Error::InvalidRelocationType(value)),
+        };
+
+        Ok(rel_type)
+    }
+}
+
+/// Relocation types of RISC-V, `R_RISCV_*`, the same for both classes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RiscVRelType {
+    None,
+    W32,
+    W64,
+    Relative,
+    Copy,
+    JumpSlot,
+    TlsDtpMod32,
+    TlsDtpMod64,
+    TlsDtpRel32,
+    TlsDtpRel64,
+    TlsTpRel32,
+    TlsTpRel64,
+    TlsDesc,
+    Branch,
+    Jal,
+    Call,
+    CallPlt,
+    GotHi20,
+    PcrelHi20,
+    PcrelLo12I,
+    Hi20,
+    Lo12I,
+    IRelative,
+}
+
+impl TryFrom<u32> for RiscVRelType {
+    type Error = Error;
+    fn try_from(value: u32) -> Result<RiscVRelType, Self::Error> {
+        let rel_type = match value {
+            0 => Self::None,
+            1 => Self::W32,
+            2 => Self::W64,
+            3 => Self::Relative,
+            4 => Self::Copy,
+            5 => Self::JumpSlot,
+            6 => Self::TlsDtpMod32,
+            7 => Self::TlsDtpMod64,
+            8 => Self::TlsDtpRel32,
+            9 => Self::TlsDtpRel64,
+            10 => Self::TlsTpRel32,
+            11 => Self::TlsTpRel64,
+            12 => Self::TlsDesc,
+            16 => Self::Branch,
+            17 => Self::Jal,
+            18 => Self::Call,
+            19 => Self::CallPlt,
+            20 => Self::GotHi20,
+            23 => Self::PcrelHi20,
+            24 => Self::PcrelLo12I,
+            26 => Self::Hi20,
+            27 => Self::Lo12I,
+            58 => Self::IRelative,
             _ => return Err(Error::InvalidRelocationType(value)),
         };
 
@@ -78,4 +723,14 @@ impl TryFrom<u32> for RelType {
 pub enum Error {
     #[error("Unknown relocation type referenced by value {0}")]
     InvalidRelocationType(u32),
+    #[error("Relocation table does not start with the APS2 magic")]
+    NotPacked,
+    #[error("Packed relocation group of {0} relocations")]
+    BadGroupSize(i64),
+    #[error("Packed relocation table of {0} relocations, more than the file can hold")]
+    TooManyRelocations(i64),
+    #[error("Packed Rel table has addends")]
+    AddendInRel,
+    #[error("Packed relocation table parsing error {0}")]
+    ParseError(#[from] ParseError),
 }
\ No newline at end of file
@@ -0,0 +1,288 @@
+//! Module finding the shared libraries an object needs, and the ones they need in turn, the way
+//! the dynamic loader would, but without running anything: `ldd` as a library. Libraries can be
+//! looked up in a sysroot, the root directory of another system.
+use std::{
+    collections::{HashSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::{Class, DynamicTag, Elf64, Machine, StringError};
+
+/// Configuration file listing the directories of libraries, relative to the sysroot
+pub const LD_SO_CONF: &str = "etc/ld.so.conf";
+/// Directories the dynamic loader searches last
+pub const DEFAULT_DIRS: [&str; 4] = ["/lib64", "/usr/lib64", "/lib", "/usr/lib"];
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Dynamic table strings {0}")]
+    Strings(#[from] StringError),
+}
+
+/// Directories libraries are searched in, apart from the `DT_RPATH` and `DT_RUNPATH` of the
+/// objects needing them. Absolute directories are taken inside `sysroot`.
+#[derive(Debug, Clone, Default)]
+pub struct SearchPaths {
+    /// Root directory of the system the libraries come from, empty for this system
+    pub sysroot: PathBuf,
+    /// Directories of `LD_LIBRARY_PATH`
+    pub library_path: Vec<PathBuf>,
+    /// Directories listed by `ld.so.conf`, which the loader cache is built from
+    pub config_dirs: Vec<PathBuf>,
+    /// Directories searched last
+    pub default_dirs: Vec<PathBuf>,
+}
+
+impl SearchPaths {
+    /// Returns the directories of the system at `sysroot`: the ones its `ld.so.conf` lists, then
+    /// the default ones
+    pub fn new(sysroot: &Path) -> Self {
+        let config_dirs = read_ld_so_conf(sysroot, &sysroot.join(LD_SO_CONF), &mut HashSet::new());
+        Self {
+            sysroot: sysroot.to_path_buf(),
+            library_path: vec![],
+            config_dirs,
+            default_dirs: DEFAULT_DIRS.iter().map(PathBuf::from).collect(),
+        }
+    }
+
+    /// Sets the directories of `LD_LIBRARY_PATH` from its value, separated by colons or
+    /// semicolons
+    pub fn with_library_path(mut self, value: &str) -> Self {
+        self.library_path = split_path(value).map(PathBuf::from).collect();
+        self
+    }
+
+    /// Returns `dir` inside the sysroot
+    fn in_sysroot(&self, dir: &Path) -> PathBuf {
+        match dir.strip_prefix("/") {
+            Ok(relative) if !self.sysroot.as_os_str().is_empty() => self.sysroot.join(relative),
+            _ => dir.to_path_buf(),
+        }
+    }
+}
+
+/// An object of the dependency graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    /// Name the object is needed by, from `DT_NEEDED`, or the path of the root object
+    pub name: String,
+    /// Path the object was found at, or `None` when no directory holds a compatible one
+    pub path: Option<PathBuf>,
+    /// Objects this one needs, as indexes in the graph, in the order of its `DT_NEEDED` entries
+    pub needed: Vec<usize>,
+}
+
+/// Objects the root object needs, directly or not, in the order the dynamic loader loads them,
+/// breadth first, with the root first. Each library appears once, however many objects need it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyGraph {
+    pub objects: Vec<Dependency>,
+}
+
+impl DependencyGraph {
+    /// Returns the names of the libraries no directory holds a compatible object for
+    pub fn missing(&self) -> impl Iterator<Item = &str> {
+        self.objects.iter().filter(|object| object.path.is_none()).map(|object| &object.name[..])
+    }
+}
+
+/// What the search needs from an object which was found
+struct Found {
+    needed: Vec<String>,
+    rpath: Vec<PathBuf>,
+    runpath: Vec<PathBuf>,
+    /// Index of the object which needed this one first, whose `DT_RPATH` applies as well
+    loader: Option<usize>,
+}
+
+/// Finds the libraries `root`, the object at `root_path`, needs, directly or not, in `paths`.
+/// Libraries are searched in the `DT_RPATH` of the object needing them and of the objects which
+/// loaded it, unless it has a `DT_RUNPATH`, then in `LD_LIBRARY_PATH`, in its `DT_RUNPATH`, in
+/// the directories of `ld.so.conf` and in the default directories. Files of another class or
+/// machine than `root` are skipped, as the loader does.
+pub fn resolve(
+    root: &Elf64,
+    root_path: &Path,
+    paths: &SearchPaths,
+) -> Result<DependencyGraph, Error> {
+    let (class, machine) = (root.elf_header.e_class(), root.elf_header.e_machine);
+    let mut objects = vec![Dependency {
+        name: root_path.to_string_lossy().into_owned(),
+        path: Some(root_path.to_path_buf()),
+        needed: vec![],
+    }];
+    // Search information of each object, `None` for the ones which were not found
+    let mut found = vec![Some(search_info(root, root_path, paths, None)?)];
+
+    let mut queue = VecDeque::from([0]);
+    while let Some(index) = queue.pop_front() {
+        let needed = found[index].as_ref().map(|info| info.needed.clone()).unwrap_or_default();
+        for name in needed {
+            // Libraries already loaded are known by the name they were needed by, or by path
+            if let Some(known) = objects.iter().position(|other| other.name == name) {
+                objects[index].needed.push(known);
+                continue;
+            }
+            let library = find_library(&name, index, &found, paths, class, machine);
+            let path = library.as_ref().map(|(path, _)| path);
+            let same_path = |other: &Dependency| path.is_some() && other.path.as_ref() == path;
+            let known = objects.iter().position(same_path);
+            if let Some(known) = known {
+                objects[index].needed.push(known);
+                continue;
+            }
+
+            let next = objects.len();
+            objects[index].needed.push(next);
+            let (path, info) = library.unzip();
+            objects.push(Dependency { name, path, needed: vec![] });
+            found.push(info);
+            queue.push_back(next);
+        }
+    }
+    Ok(DependencyGraph { objects })
+}
+
+/// Looks for the library `name`, which the object at `index` needs
+fn find_library(
+    name: &str,
+    index: usize,
+    found: &[Option<Found>],
+    paths: &SearchPaths,
+    class: Class,
+    machine: Machine,
+) -> Option<(PathBuf, Found)> {
+    let load = |path: PathBuf| {
+        let bytes = fs::read(&path).ok()?;
+        let elf = Elf64::parse(&bytes).ok()?;
+        let compatible = elf.elf_header.e_class() == class && elf.elf_header.e_machine == machine;
+        let info = search_info(&elf, &path, paths, Some(index)).ok()?;
+        compatible.then_some((path, info))
+    };
+    // Names with a slash are paths
+    if name.contains('/') {
+        return load(paths.in_sysroot(Path::new(name)));
+    }
+
+    let object = found[index].as_ref()?;
+    let mut dirs = vec![];
+    if object.runpath.is_empty() {
+        // `DT_RPATH` of the object, then of the chain of objects which loaded it
+        let mut loader = Some(index);
+        while let Some(info) = loader.and_then(|loader| found[loader].as_ref()) {
+            dirs.extend(info.rpath.iter().cloned());
+            loader = info.loader;
+        }
+    }
+    dirs.extend(paths.library_path.iter().map(|dir| paths.in_sysroot(dir)));
+    dirs.extend(object.runpath.iter().cloned());
+    dirs.extend(paths.config_dirs.iter().map(|dir| paths.in_sysroot(dir)));
+    dirs.extend(paths.default_dirs.iter().map(|dir| paths.in_sysroot(dir)));
+    dirs.into_iter().find_map(|dir| load(dir.join(name)))
+}
+
+/// Reads the needed libraries and the search paths of `elf`, the object at `path`. Absolute
+/// directories are taken inside the sysroot, then `$ORIGIN` and `$LIB` are expanded.
+fn search_info(
+    elf: &Elf64,
+    path: &Path,
+    paths: &SearchPaths,
+    loader: Option<usize>,
+) -> Result<Found, Error> {
+    let origin = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let origin = origin.unwrap_or(Path::new("."));
+    let lib = match elf.elf_header.e_class() {
+        Class::Elf32 => "lib",
+        Class::Elf64 => "lib64",
+    };
+    let dirs = |tag| -> Vec<PathBuf> {
+        elf.dynamic_entry_strings(tag)
+            .flat_map(|value| split_path(&value).map(String::from).collect::<Vec<_>>())
+            .map(|dir| expand(&paths.in_sysroot(Path::new(&dir)), origin, lib))
+            .collect()
+    };
+    Ok(Found {
+        needed: elf.needed_libraries()?,
+        rpath: dirs(DynamicTag::RPath),
+        runpath: dirs(DynamicTag::RunPath),
+        loader,
+    })
+}
+
+/// Replaces the `$ORIGIN` and `$LIB` tokens of `dir`, braced or not
+fn expand(dir: &Path, origin: &Path, lib: &str) -> PathBuf {
+    let (dir, origin) = (dir.to_string_lossy(), origin.to_string_lossy());
+    let dir = dir.replace("${ORIGIN}", &origin).replace("$ORIGIN", &origin);
+    PathBuf::from(dir.replace("${LIB}", lib).replace("$LIB", lib))
+}
+
+fn split_path(value: &str) -> impl Iterator<Item = &str> {
+    value.split([':', ';']).filter(|dir| !dir.is_empty())
+}
+
+/// Reads the directories listed by the `ld.so.conf` file at `path`, one per line, following its
+/// `include` lines, whose patterns may hold a `*` in their file name. Files already in `seen`
+/// are skipped, so files including each other are read once.
+fn read_ld_so_conf(sysroot: &Path, path: &Path, seen: &mut HashSet<PathBuf>) -> Vec<PathBuf> {
+    if !seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())) {
+        return vec![];
+    }
+    let Ok(contents) = fs::read_to_string(path) else {
+        return vec![];
+    };
+    let mut dirs = vec![];
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("include") => {
+                for pattern in words {
+                    let pattern = match Path::new(pattern) {
+                        pattern if pattern.is_absolute() => sysroot_join(sysroot, pattern),
+                        pattern => path.parent().unwrap_or(Path::new("")).join(pattern),
+                    };
+                    for file in glob(&pattern) {
+                        dirs.extend(read_ld_so_conf(sysroot, &file, seen));
+                    }
+                }
+            }
+            // Hardware capabilities name subdirectories, not directories
+            Some("hwcap") | None => {}
+            Some(dir) => dirs.extend([dir].into_iter().chain(words).map(PathBuf::from)),
+        }
+    }
+    dirs
+}
+
+fn sysroot_join(sysroot: &Path, path: &Path) -> PathBuf {
+    sysroot.join(path.strip_prefix("/").unwrap_or(path))
+}
+
+/// Returns the files matching `pattern`, sorted, where only the file name may hold a `*`
+fn glob(pattern: &Path) -> Vec<PathBuf> {
+    let name = pattern.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let Some((prefix, suffix)) = name.split_once('*') else {
+        return vec![pattern.to_path_buf()];
+    };
+    let dir = pattern.parent().unwrap_or(Path::new(""));
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        })
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    files
+}
@@ -1,22 +1,207 @@
 //! Module describing the Section header table and its entries.
+use core::{fmt, ops::Range};
+use std::borrow::Cow;
+
+use bitflags::bitflags;
 use thiserror::Error;
 
-use crate::{Addr, Reader, ParseError};
+use crate::{
+    compress, entropy, write, Addr, Class, Elf64, FileOffset, Reader, ParseError, SegmentError,
+    Size, Writer,
+};
+
+/// Section index signaling that the real index does not fit and is stored elsewhere
+pub const SHN_XINDEX: u16 = 0xFFFF;
+
+// Reserved inclusive range. Operating system specific.
+const LOOS: u32 = 0x6000_0000;
+const HIOS: u32 = 0x6FFF_FFFF;
+// Reserved inclusive range. Processor specific.
+const LOPROC: u32 = 0x7000_0000;
+const HIPROC: u32 = 0x7FFF_FFFF;
+// Reserved inclusive range. Application specific.
+const LOUSER: u32 = 0x8000_0000;
+const HIUSER: u32 = 0xFFFF_FFFF;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SectionType {
+    /// Inactive entry, like the first one in the table
+    ShtNull,
+    /// Information defined by the program, like code and data
+    ShtProgbits,
+    /// Symbol table
+    ShtSymtab,
+    /// String table
+    ShtStrtab,
+    /// Relocation entries with explicit addends
+    ShtRela,
+    /// Symbol hash table
+    ShtHash,
+    /// Dynamic linking information
+    ShtDynamic,
+    /// Notes
+    ShtNote,
+    /// Occupies no space in the file, like `.bss`
+    ShtNobits,
+    /// Relocation entries without explicit addends
+    ShtRel,
+    /// Reserved
+    ShtShlib,
+    /// Dynamic linking symbol table
+    ShtDynsym,
+    /// Pointers to initialization functions
+    ShtInitArray,
+    /// Pointers to termination functions
+    ShtFiniArray,
+    /// Pointers to functions run before all other initialization functions
+    ShtPreinitArray,
+    /// Section group, used for COMDAT sections
+    ShtGroup,
+    /// Extended section indexes of a symbol table
+    ShtSymtabShndx,
+    /// Relative relocation entries in the compact RELR format
+    ShtRelr,
+    /// GNU object attributes
+    ShtGnuAttributes,
+    /// GNU style symbol hash table
+    ShtGnuHash,
+    /// Prelink library list
+    ShtGnuLiblist,
+    /// Checksum of the file contents
+    ShtChecksum,
+    /// Symbol versions defined by the file
+    ShtGnuVerdef,
+    /// Symbol versions needed by the file
+    ShtGnuVerneed,
+    /// Version of every dynamic symbol
+    ShtGnuVersym,
+    /// Value for specific OS
+    ShtOsSpecific(u32),
+    /// Value for specific processor
+    ShtProcSpecific(u32),
+    /// Value for specific application
+    ShtUserSpecific(u32),
+    /// Value not defined by any specification we know of
+    ShtUnknown(u32),
+}
+
+impl From<u32> for SectionType {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => Self::ShtNull,
+            1 => Self::ShtProgbits,
+            2 => Self::ShtSymtab,
+            3 => Self::ShtStrtab,
+            4 => Self::ShtRela,
+            5 => Self::ShtHash,
+            6 => Self::ShtDynamic,
+            7 => Self::ShtNote,
+            8 => Self::ShtNobits,
+            9 => Self::ShtRel,
+            10 => Self::ShtShlib,
+            11 => Self::ShtDynsym,
+            14 => Self::ShtInitArray,
+            15 => Self::ShtFiniArray,
+            16 => Self::ShtPreinitArray,
+            17 => Self::ShtGroup,
+            18 => Self::ShtSymtabShndx,
+            19 => Self::ShtRelr,
+            0x6FFF_FFF5 => Self::ShtGnuAttributes,
+            0x6FFF_FFF6 => Self::ShtGnuHash,
+            0x6FFF_FFF7 => Self::ShtGnuLiblist,
+            0x6FFF_FFF8 => Self::ShtChecksum,
+            0x6FFF_FFFD => Self::ShtGnuVerdef,
+            0x6FFF_FFFE => Self::ShtGnuVerneed,
+            0x6FFF_FFFF => Self::ShtGnuVersym,
+            LOOS..=HIOS => Self::ShtOsSpecific(value),
+            LOPROC..=HIPROC => Self::ShtProcSpecific(value),
+            LOUSER..=HIUSER => Self::ShtUserSpecific(value),
+            _ => Self::ShtUnknown(value),
+        }
+    }
+}
+
+impl From<SectionType> for u32 {
+    fn from(value: SectionType) -> Self {
+        match value {
+            SectionType::ShtNull => 0,
+            SectionType::ShtProgbits => 1,
+            SectionType::ShtSymtab => 2,
+            SectionType::ShtStrtab => 3,
+            SectionType::ShtRela => 4,
+            SectionType::ShtHash => 5,
+            SectionType::ShtDynamic => 6,
+            SectionType::ShtNote => 7,
+            SectionType::ShtNobits => 8,
+            SectionType::ShtRel => 9,
+            SectionType::ShtShlib => 10,
+            SectionType::ShtDynsym => 11,
+            SectionType::ShtInitArray => 14,
+            SectionType::ShtFiniArray => 15,
+            SectionType::ShtPreinitArray => 16,
+            SectionType::ShtGroup => 17,
+            SectionType::ShtSymtabShndx => 18,
+            SectionType::ShtRelr => 19,
+            SectionType::ShtGnuAttributes => 0x6FFF_FFF5,
+            SectionType::ShtGnuHash => 0x6FFF_FFF6,
+            SectionType::ShtGnuLiblist => 0x6FFF_FFF7,
+            SectionType::ShtChecksum => 0x6FFF_FFF8,
+            SectionType::ShtGnuVerdef => 0x6FFF_FFFD,
+            SectionType::ShtGnuVerneed => 0x6FFF_FFFE,
+            SectionType::ShtGnuVersym => 0x6FFF_FFFF,
+            SectionType::ShtOsSpecific(value)
+            | SectionType::ShtProcSpecific(value)
+            | SectionType::ShtUserSpecific(value)
+            | SectionType::ShtUnknown(value) => value,
+        }
+    }
+}
 
-#[derive(Debug)]
-pub struct SectionHeader {
+bitflags! {
+    /// Structure representing the `sh_flags` from the Section Header in an Elf file
+    pub struct SectionFlags: u64 {
+        /// Writable at run time
+        const WRITE = 0x1;
+        /// Occupies memory at run time
+        const ALLOC = 0x2;
+        /// Holds machine instructions
+        const EXECINSTR = 0x4;
+        /// Elements may be merged to remove duplicates
+        const MERGE = 0x10;
+        /// Holds null terminated strings
+        const STRINGS = 0x20;
+        /// `sh_info` holds a section header table index
+        const INFO_LINK = 0x40;
+        /// Must keep its order relative to the section `sh_link` points to
+        const LINK_ORDER = 0x80;
+        /// Needs OS specific processing to avoid incorrect behavior
+        const OS_NONCONFORMING = 0x100;
+        /// Member of a section group
+        const GROUP = 0x200;
+        /// Holds thread-local storage
+        const TLS = 0x400;
+        /// Holds compressed data, behind a compression header
+        const COMPRESSED = 0x800;
+        /// Bits reserved for operating system specific semantics
+        const MASKOS = 0x0FF0_0000;
+        /// Bits reserved for processor specific semantics
+        const MASKPROC = 0xF000_0000;
+    }
+}
+
+pub struct SectionHeader<'a> {
     /// An offset to a string in the .shstrtab section that represents the name of this section.
     sh_name: u32,
-    /// Identifies the type of this header. TODO define section header types enum
-    sh_type: u32,
-    /// Identifies the attributes of the section. TODO define section header attributes enum
-    sh_flags: u64,
+    /// Identifies the type of this header.
+    sh_type: SectionType,
+    /// Identifies the attributes of the section.
+    sh_flags: SectionFlags,
     /// Virtual address of the section in memory, for sections that are loaded.
     sh_addr: Addr,
     /// Offset of the section in the file image.
-    sh_offset: u64,
+    sh_offset: FileOffset,
     /// Size in bytes of the section in the file image. May be 0.
-    sh_size: u64,
+    sh_size: Size,
     /// Contains the section index of an associated section.
     /// This field is used for several purposes, depending on the type of section.
     sh_link: u32,
@@ -28,20 +213,44 @@ pub struct SectionHeader {
     /// Contains the size, in bytes, of each entry, for sections that contain fixed-size entries.
     /// Otherwise, this field contains zero.
     sh_entsize: u64,
+    /// Name of the section, resolved through the section name string table once its contents are
+    /// loaded
+    pub(crate) name: Option<String>,
+    /// The contents of the section, borrowed from the parsed bytes. Empty for `ShtNobits`
+    /// sections.
+    pub data: Cow<'a, [u8]>,
 }
 
-impl SectionHeader {
-    pub fn parse(reader: &mut Reader) -> Result<SectionHeader, SectionError> {
+impl<'a> SectionHeader<'a> {
+    /// Returns the size, in bytes, of a section header for the given `class`
+    pub fn size(class: Class) -> usize {
+        match class {
+            Class::Elf32 => 40,
+            Class::Elf64 => 64,
+        }
+    }
+
+    /// Parses a section header. Fields that are word sized are read according to the class of
+    /// the `reader` and widened to 64-bit.
+    pub fn parse(reader: &mut Reader<'a>) -> Result<Self, SectionError> {
+        let mut sh = Self::parse_header(reader)?;
+        sh.load_data(reader)?;
+        Ok(sh)
+    }
+
+    /// Parses a section header, leaving the contents of its section empty
+    pub fn parse_header(reader: &mut Reader) -> Result<Self, SectionError> {
         let sh_name = reader.read_u32()?;
-        let sh_type = reader.read_u32()?;
-        let sh_flags = reader.read_u64()?;
-        let sh_addr = Addr::from(reader.read_u64()?);
-        let sh_offset = reader.read_u64()?;
-        let sh_size = reader.read_u64()?;
+        let sh_type = SectionType::from(reader.read_u32()?);
+        // Bits without a meaning we know of are dropped, OS and processor specific ones are kept
+        let sh_flags = SectionFlags::from_bits_truncate(reader.read_word()?);
+        let sh_addr = Addr::parse(reader)?;
+        let sh_offset = FileOffset::parse(reader)?;
+        let sh_size = Size::parse(reader)?;
         let sh_link = reader.read_u32()?;
         let sh_info = reader.read_u32()?;
-        let sh_addralign = reader.read_u64()?;
-        let sh_entsize = reader.read_u64()?;
+        let sh_addralign = reader.read_word()?;
+        let sh_entsize = reader.read_word()?;
 
         Ok(Self {
             sh_name,
@@ -54,16 +263,229 @@ impl SectionHeader {
             sh_info,
             sh_addralign,
             sh_entsize,
+            name: None,
+            data: Cow::Borrowed(&[]),
         })
     }
 
+    /// Returns a section of `sh_type` holding `data` at `sh_offset` in the file, which is not
+    /// loaded in memory
+    pub(crate) fn new(
+        sh_name: u32,
+        sh_type: SectionType,
+        sh_offset: FileOffset,
+        data: Cow<'a, [u8]>,
+    ) -> Self {
+        Self {
+            sh_name,
+            sh_type,
+            sh_flags: SectionFlags::empty(),
+            sh_addr: Addr(0),
+            sh_offset,
+            sh_size: Size(data.len() as u64),
+            sh_link: 0,
+            sh_info: 0,
+            sh_addralign: 0,
+            sh_entsize: 0,
+            name: None,
+            data,
+        }
+    }
+
+    /// Moves the section to `sh_offset` in the file, with `data` as its contents
+    pub(crate) fn set_contents(&mut self, sh_offset: FileOffset, data: Cow<'a, [u8]>) {
+        self.sh_offset = sh_offset;
+        self.sh_size = Size(data.len() as u64);
+        self.data = data;
+    }
+
+    pub(crate) fn set_sh_name(&mut self, sh_name: u32) {
+        self.sh_name = sh_name;
+    }
+
+    pub(crate) fn set_sh_link(&mut self, sh_link: u32) {
+        self.sh_link = sh_link;
+    }
+
+    pub(crate) fn set_sh_info(&mut self, sh_info: u32) {
+        self.sh_info = sh_info;
+    }
+
+    pub(crate) fn set_sh_addr(&mut self, sh_addr: Addr) {
+        self.sh_addr = sh_addr;
+    }
+
+    /// Sets the size, which the first section header uses to count the sections of files with
+    /// too many of them for `e_shnum`
+    pub(crate) fn set_sh_size(&mut self, sh_size: Size) {
+        self.sh_size = sh_size;
+    }
+
+    /// Writes the section header, without the contents of its section
+    pub fn write_header(&self, writer: &mut Writer) -> Result<(), write::Error> {
+        writer.write_u32(self.sh_name);
+        writer.write_u32(self.sh_type.into());
+        writer.write_word(self.sh_flags.bits())?;
+        writer.write_word(self.sh_addr.into())?;
+        writer.write_word(self.sh_offset.into())?;
+        writer.write_word(self.sh_size.into())?;
+        writer.write_u32(self.sh_link);
+        writer.write_u32(self.sh_info);
+        writer.write_word(self.sh_addralign)?;
+        writer.write_word(self.sh_entsize)
+    }
+
+    /// Borrows the contents of the section from the file `reader` points to
+    pub fn load_data(&mut self, reader: &Reader<'a>) -> Result<(), SectionError> {
+        if let Some(range) = self.file_range() {
+            self.data = Cow::Borrowed(reader.read_slice_from(range)?);
+        }
+        Ok(())
+    }
+
+    /// Returns the entropy of the contents of the section as they are stored in the file,
+    /// compressed or not, in bits per byte
+    pub fn entropy(&self) -> f64 {
+        entropy::entropy(&self.data)
+    }
+
+    /// Returns the range where the section is stored in the file, or `None` for sections which
+    /// take no space in the file. Ranges overflowing the address space end at `usize::MAX`.
+    pub fn file_range(&self) -> Option<Range<usize>> {
+        if matches!(self.sh_type, SectionType::ShtNull | SectionType::ShtNobits) {
+            return None;
+        }
+
+        let start: usize = self.sh_offset.into();
+        Some(start..start.saturating_add(self.sh_size.into()))
+    }
+
+    /// Returns the range the section occupies in memory. Only meaningful for sections with the
+    /// `ALLOC` flag.
+    pub fn mem_range(&self) -> Range<Addr> {
+        self.sh_addr..self.sh_addr + self.sh_size
+    }
+
+    /// Returns the name of the section, or `None` until the section name string table is loaded
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn sh_name(&self) -> u32 {
+        self.sh_name
+    }
+
+    pub fn sh_type(&self) -> SectionType {
+        self.sh_type
+    }
+
+    pub fn sh_flags(&self) -> SectionFlags {
+        self.sh_flags
+    }
+
     pub fn sh_addr(&self) -> Addr {
         self.sh_addr
     }
+
+    pub fn sh_offset(&self) -> FileOffset {
+        self.sh_offset
+    }
+
+    pub fn sh_size(&self) -> Size {
+        self.sh_size
+    }
+
+    pub fn sh_link(&self) -> u32 {
+        self.sh_link
+    }
+
+    pub fn sh_info(&self) -> u32 {
+        self.sh_info
+    }
+
+    pub fn sh_addralign(&self) -> u64 {
+        self.sh_addralign
+    }
+
+    pub fn sh_entsize(&self) -> u64 {
+        self.sh_entsize
+    }
+}
+
+impl fmt::Debug for SectionHeader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SectionHeader")
+            .field("name", &self.name)
+            .field("sh_name", &self.sh_name)
+            .field("sh_type", &self.sh_type)
+            .field("sh_flags", &self.sh_flags)
+            .field("sh_addr", &self.sh_addr)
+            .field("sh_offset", &self.sh_offset)
+            .field("sh_size", &self.sh_size)
+            .field("sh_link", &self.sh_link)
+            .field("sh_info", &self.sh_info)
+            .field("sh_addralign", &self.sh_addralign)
+            .field("sh_entsize", &self.sh_entsize)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A view of a section, bundling its header with its index and name, as handed out by
+/// `Elf64::sections`
+#[derive(Clone, Copy)]
+pub struct Section<'e, 'a> {
+    elf: &'e Elf64<'a>,
+    index: usize,
+    header: &'e SectionHeader<'a>,
+}
+
+impl<'e, 'a> Section<'e, 'a> {
+    pub(crate) fn new(elf: &'e Elf64<'a>, index: usize) -> Option<Self> {
+        let header = elf.sh_table.get(index)?;
+        Some(Self { elf, index, header })
+    }
+
+    /// Returns the index of the section in the section header table
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn header(&self) -> &'e SectionHeader<'a> {
+        self.header
+    }
+
+    pub fn name(&self) -> Option<&'e str> {
+        self.header.name()
+    }
+
+    pub fn section_type(&self) -> SectionType {
+        self.header.sh_type()
+    }
+
+    pub fn flags(&self) -> SectionFlags {
+        self.header.sh_flags()
+    }
+
+    /// Returns the contents of the section, decompressed on every call if the section is
+    /// compressed
+    pub fn data(&self) -> Result<Cow<'e, [u8]>, compress::Error> {
+        self.elf.section_data(self.header)
+    }
+}
+
+impl fmt::Debug for Section<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Section")
+            .field("index", &self.index)
+            .field("header", self.header)
+            .finish()
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum SectionError {
     #[error("Error parsing the section table {0}")]
     ParseError(#[from] ParseError),
+    #[error("Error parsing the dynamic section {0}")]
+    DynamicError(#[from] SegmentError),
 }
\ No newline at end of file
@@ -7,7 +7,7 @@ use bitflags::bitflags;
 use crate::{
     error::SegmentError,
     reader::{Reader},
-    addr::Addr, DynamicError,
+    addr::{Addr, FileOffset}, write, DynamicError, StringError, StringTable, Writer,
 };
 
 // Reserved inclusive range. Operating system specific.
@@ -24,6 +24,17 @@ const HIOS64: u64 = 0x6FFF_FFFF;
 const LOPROC64: u64 = 0x7000_0000;
 const HIPROC64: u64 = 0x7FFF_FFFF;
 
+/// GNU segment holding the exception handling frame header
+pub const PT_GNU_EH_FRAME: u32 = 0x6474_E550;
+/// GNU segment whose flags give the permissions of the stack
+pub const PT_GNU_STACK: u32 = 0x6474_E551;
+/// GNU segment made read-only after relocation
+pub const PT_GNU_RELRO: u32 = 0x6474_E552;
+/// GNU segment holding the `.note.gnu.property` section
+pub const PT_GNU_PROPERTY: u32 = 0x6474_E553;
+/// GNU segment holding the stack trace format data
+pub const PT_GNU_SFRAME: u32 = 0x6474_E554;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SegmentType {
     /// Program header table entry unused.
@@ -42,6 +53,14 @@ pub enum SegmentType {
     PtPhdr,
     /// Thread-Local storage template.
     PtTls,
+    /// Exception handling frame header, `.eh_frame_hdr`
+    PtGnuEhFrame,
+    /// Permissions of the stack, given by the flags
+    PtGnuStack,
+    /// Range made read-only once relocations are applied
+    PtGnuRelro,
+    /// GNU properties of the program, like the features of the CPU it needs
+    PtGnuProperty,
     /// Value for specific OS
     PtOsSpecific(u32),
     /// Value for specific processor
@@ -68,6 +87,10 @@ impl TryFrom<u32> for SegmentType {
             0x5 => Ok(Self::PtShlib),
             0x6 => Ok(Self::PtPhdr),
             0x7 => Ok(Self::PtTls),
+            PT_GNU_EH_FRAME => Ok(Self::PtGnuEhFrame),
+            PT_GNU_STACK => Ok(Self::PtGnuStack),
+            PT_GNU_RELRO => Ok(Self::PtGnuRelro),
+            PT_GNU_PROPERTY => Ok(Self::PtGnuProperty),
             LOOS..=HIOS => Ok(Self::PtOsSpecific(value)),
             LOPROC..=HIPROC => Ok(Self::PtProcSpecific(value)),
             _ => Err(SegmentError::TypeUnknown(value)),
@@ -75,6 +98,26 @@ impl TryFrom<u32> for SegmentType {
     }
 }
 
+impl From<SegmentType> for u32 {
+    fn from(value: SegmentType) -> Self {
+        match value {
+            SegmentType::PtNull => 0x0,
+            SegmentType::PtLoad => 0x1,
+            SegmentType::PtDynamic => 0x2,
+            SegmentType::PtInterp => 0x3,
+            SegmentType::PtNote => 0x4,
+            SegmentType::PtShlib => 0x5,
+            SegmentType::PtPhdr => 0x6,
+            SegmentType::PtTls => 0x7,
+            SegmentType::PtGnuEhFrame => PT_GNU_EH_FRAME,
+            SegmentType::PtGnuStack => PT_GNU_STACK,
+            SegmentType::PtGnuRelro => PT_GNU_RELRO,
+            SegmentType::PtGnuProperty => PT_GNU_PROPERTY,
+            SegmentType::PtOsSpecific(value) | SegmentType::PtProcSpecific(value) => value,
+        }
+    }
+}
+
 bitflags! {
     /// Structure representing the `p_flags` from the Program Header in an Elf file
     pub struct SegmentFlags: u32 {
@@ -87,14 +130,100 @@ bitflags! {
 impl SegmentFlags {
     pub fn parse(reader: &mut Reader) -> Result<Self, SegmentError> {
         let value = reader.read_u32()?;
-        Ok( SegmentFlags::from_bits(value).ok_or(SegmentError::SegmentFlagsParseFailed(value))? )
+        SegmentFlags::from_bits(value).ok_or(SegmentError::SegmentFlagsParseFailed(value))
+    }
+}
+
+bitflags! {
+    /// Flags of the `DT_FLAGS` dynamic entry
+    pub struct DynamicFlags: u64 {
+        /// The object may use `$ORIGIN` in the paths it loads
+        const ORIGIN = 0x1;
+        /// Symbol resolution starts from the object itself
+        const SYMBOLIC = 0x2;
+        /// Relocations may write to non-writable segments
+        const TEXTREL = 0x4;
+        /// Every relocation is processed before control is given to the program
+        const BIND_NOW = 0x8;
+        /// The object uses the static thread-local storage model
+        const STATIC_TLS = 0x10;
     }
 }
 
+bitflags! {
+    /// Flags of the `DT_FLAGS_1` dynamic entry
+    pub struct DynamicFlags1: u64 {
+        const NOW = 0x1;
+        const GLOBAL = 0x2;
+        const GROUP = 0x4;
+        /// The object cannot be unloaded
+        const NODELETE = 0x8;
+        const LOADFLTR = 0x10;
+        const INITFIRST = 0x20;
+        /// The object cannot be loaded with `dlopen`
+        const NOOPEN = 0x40;
+        const ORIGIN = 0x80;
+        const DIRECT = 0x100;
+        const TRANS = 0x200;
+        const INTERPOSE = 0x400;
+        /// The default library search path is ignored
+        const NODEFLIB = 0x800;
+        const NODUMP = 0x1000;
+        const CONFALT = 0x2000;
+        const ENDFILTEE = 0x4000;
+        const DISPRELDNE = 0x8000;
+        const DISPRELPND = 0x10000;
+        const NODIRECT = 0x20000;
+        const IGNMULDEF = 0x40000;
+        const NOKSYMS = 0x80000;
+        const NOHDR = 0x100000;
+        const EDITED = 0x200000;
+        const NORELOC = 0x400000;
+        const SYMINTPOSE = 0x800000;
+        const GLOBAUDIT = 0x1000000;
+        const SINGLETON = 0x2000000;
+        const STUB = 0x4000000;
+        /// The object is a position independent executable
+        const PIE = 0x8000000;
+    }
+}
+
+/// How much of the data written by relocations is made read-only once they are applied
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelroLevel {
+    /// There is no `PtGnuRelro` segment
+    None,
+    /// The `PtGnuRelro` range is read-only, but the procedure linkage table slots of the GOT are
+    /// bound lazily, so they stay writable
+    Partial,
+    /// Every symbol is bound at load time, so the whole GOT is in the read-only range
+    Full,
+}
+
+/// A problem with the layout of a segment
+#[derive(Debug, Clone, PartialEq)]
+pub enum SegmentIssue {
+    /// The memory range of the loadable segment overlaps that of the loadable segment `other`
+    OverlappingLoad { other: usize },
+    /// The file range of the segment ends at `end`, past the end of the file
+    PastEndOfFile { end: FileOffset },
+    /// The loadable segment cannot be mapped, as `p_vaddr` and `p_offset` differ modulo `p_align`
+    Misaligned { vaddr: Addr, offset: FileOffset, align: u64 },
+}
+
+/// An issue of the segment at `index` in the program header table
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentDiagnostic {
+    pub index: usize,
+    pub issue: SegmentIssue,
+}
+
 #[derive(Debug)]
 pub enum SegmentContents {
     /// Contents for a Dynamic table reffered by `PtDynamic` `ProgramHeader` p_type
     Dynamic(DynamicTable),
+    /// Path of the program interpreter held by a `PtInterp` segment, without its terminating NUL
+    Interp(String),
     Unknown,
 }
 
@@ -102,19 +231,19 @@ pub enum SegmentContents {
 pub struct DynamicTable(Vec<DynamicEntry>);
 
 impl DynamicTable {
-    pub fn parse(bytes: &[u8]) -> Result<Self, SegmentError> {
-        let mut reader = Reader::from_bytes(bytes);
+    /// Parses the dynamic table from the segment contents `reader` points to. The table ends with
+    /// a null entry, which must come before the end of the contents.
+    pub fn parse(reader: &mut Reader) -> Result<Self, SegmentError> {
+        let entry_size = 2 * reader.class.word_size();
         let mut table = vec![];
-        // Flags if we reached the null entry or not
-        let mut still_got_entries = true;
-        while still_got_entries {
-            let dynamic_entry = DynamicEntry::parse(&mut reader)?;
+        while reader.bytes.len().saturating_sub(reader.index) >= entry_size {
+            let dynamic_entry = DynamicEntry::parse(reader)?;
             table.push(dynamic_entry);
             if dynamic_entry.d_tag == DynamicTag::Null {
-                still_got_entries = false;
+                return Ok(Self(table));
             }
         }
-        Ok(Self(table))
+        Err(DynamicError::Truncated(table.len()).into())
     }
 
     pub fn entries(&self) -> &Vec<DynamicEntry> {
@@ -136,7 +265,7 @@ pub struct DynamicEntry {
 
 impl DynamicEntry {
     pub fn parse(reader: &mut Reader) -> Result<Self, SegmentError> {
-        let d_tag = DynamicTag::try_from(reader.read_u64()?)?;
+        let d_tag = DynamicTag::try_from(reader.read_word()?)?;
         let d_un = Addr::parse(reader)?;
         
         Ok(Self {
@@ -144,6 +273,11 @@ impl DynamicEntry {
             d_un
         })
     }
+
+    pub fn write(&self, writer: &mut Writer) -> Result<(), write::Error> {
+        writer.write_word(self.d_tag.into())?;
+        writer.write_word(self.d_un.into())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -220,6 +354,45 @@ pub enum DynamicTag {
     RunPath,
     /// Flag values specific to this object.
     Flags,
+    /// Pointer to an array of pointers to pre-initialization functions, run before the
+    /// initialization functions of every object
+    PreinitArray,
+    /// Size, in bytes, of the array of pre-initialization functions.
+    PreinitArraySz,
+    /// Address of the table of extended section indexes of the dynamic symbols
+    SymTabShndx,
+    /// Total size, in bytes, of the Relr relocation table.
+    RelrSz,
+    /// Address of a table of compressed relative relocations
+    Relr,
+    /// Size, in bytes, of each Relr relocation entry
+    RelrEnt,
+    /// Address of the Android packed Rel relocation table
+    AndroidRel,
+    /// Total size, in bytes, of the Android packed Rel relocation table
+    AndroidRelSz,
+    /// Address of the Android packed Rela relocation table
+    AndroidRela,
+    /// Total size, in bytes, of the Android packed Rela relocation table
+    AndroidRelaSz,
+    /// Address of the GNU style symbol hash table
+    GnuHash,
+    /// Address of the symbol version table, `.gnu.version`
+    VerSym,
+    /// Number of relative relocations, which come first in the Rela table
+    RelaCount,
+    /// Number of relative relocations, which come first in the Rel table
+    RelCount,
+    /// GNU flag values specific to this object
+    Flags1,
+    /// Address of the table of versions defined by this object
+    VerDef,
+    /// Number of entries in the version definition table
+    VerDefNum,
+    /// Address of the table of versions needed from other objects
+    VerNeed,
+    /// Number of entries in the version requirement table
+    VerNeedNum,
     /// A range between LoOs and HiOs reserved for environment-specific use.
     OsSpecific(u64),
     /// A range between LoProc and HiProc reserved for processor-specific use.
@@ -261,6 +434,25 @@ impl TryFrom<u64> for DynamicTag {
             28 => Self::FiniArraySz,
             29 => Self::RunPath,
             30 => Self::Flags,
+            32 => Self::PreinitArray,
+            33 => Self::PreinitArraySz,
+            34 => Self::SymTabShndx,
+            35 => Self::RelrSz,
+            36 => Self::Relr,
+            37 => Self::RelrEnt,
+            0x6000_000F => Self::AndroidRel,
+            0x6000_0010 => Self::AndroidRelSz,
+            0x6000_0011 => Self::AndroidRela,
+            0x6000_0012 => Self::AndroidRelaSz,
+            0x6FFF_FEF5 => Self::GnuHash,
+            0x6FFF_FFF0 => Self::VerSym,
+            0x6FFF_FFF9 => Self::RelaCount,
+            0x6FFF_FFFA => Self::RelCount,
+            0x6FFF_FFFB => Self::Flags1,
+            0x6FFF_FFFC => Self::VerDef,
+            0x6FFF_FFFD => Self::VerDefNum,
+            0x6FFF_FFFE => Self::VerNeed,
+            0x6FFF_FFFF => Self::VerNeedNum,
             LOOS64..=HIOS64 => Self::OsSpecific(value),
             LOPROC64..=HIPROC64 => Self::ProcSpecific(value),
             _ => return Err(DynamicError::EntryUnknown(value)),
@@ -268,4 +460,104 @@ impl TryFrom<u64> for DynamicTag {
 
         Ok(dynamic_tag)
     }
-}
\ No newline at end of file
+}
+
+impl From<DynamicTag> for u64 {
+    fn from(value: DynamicTag) -> u64 {
+        match value {
+            DynamicTag::Null => 0,
+            DynamicTag::Needed => 1,
+            DynamicTag::PltRelSz => 2,
+            DynamicTag::PltGot => 3,
+            DynamicTag::Hash => 4,
+            DynamicTag::StrTab => 5,
+            DynamicTag::SymTab => 6,
+            DynamicTag::RelA => 7,
+            DynamicTag::RelASz => 8,
+            DynamicTag::RelAEnt => 9,
+            DynamicTag::StrSz => 10,
+            DynamicTag::SymEnt => 11,
+            DynamicTag::Init => 12,
+            DynamicTag::Fini => 13,
+            DynamicTag::SoName => 14,
+            DynamicTag::RPath => 15,
+            DynamicTag::Symbolic => 16,
+            DynamicTag::Rel => 17,
+            DynamicTag::RelSz => 18,
+            DynamicTag::RelEnt => 19,
+            DynamicTag::PltRel => 20,
+            DynamicTag::Debug => 21,
+            DynamicTag::TextRel => 22,
+            DynamicTag::JmpRel => 23,
+            DynamicTag::BindNow => 24,
+            DynamicTag::InitArray => 25,
+            DynamicTag::FiniArray => 26,
+            DynamicTag::InitArraySz => 27,
+            DynamicTag::FiniArraySz => 28,
+            DynamicTag::RunPath => 29,
+            DynamicTag::Flags => 30,
+            DynamicTag::PreinitArray => 32,
+            DynamicTag::PreinitArraySz => 33,
+            DynamicTag::SymTabShndx => 34,
+            DynamicTag::RelrSz => 35,
+            DynamicTag::Relr => 36,
+            DynamicTag::RelrEnt => 37,
+            DynamicTag::AndroidRel => 0x6000_000F,
+            DynamicTag::AndroidRelSz => 0x6000_0010,
+            DynamicTag::AndroidRela => 0x6000_0011,
+            DynamicTag::AndroidRelaSz => 0x6000_0012,
+            DynamicTag::GnuHash => 0x6FFF_FEF5,
+            DynamicTag::VerSym => 0x6FFF_FFF0,
+            DynamicTag::RelaCount => 0x6FFF_FFF9,
+            DynamicTag::RelCount => 0x6FFF_FFFA,
+            DynamicTag::Flags1 => 0x6FFF_FFFB,
+            DynamicTag::VerDef => 0x6FFF_FFFC,
+            DynamicTag::VerDefNum => 0x6FFF_FFFD,
+            DynamicTag::VerNeed => 0x6FFF_FFFE,
+            DynamicTag::VerNeedNum => 0x6FFF_FFFF,
+            DynamicTag::OsSpecific(value) | DynamicTag::ProcSpecific(value) => value,
+        }
+    }
+}
+
+/// Value of a dynamic entry, interpreted according to its tag
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    /// Virtual address of a table or function
+    Address(Addr),
+    /// Size, in bytes, of a table or of its entries
+    Bytes(u64),
+    /// Number of entries
+    Count(u64),
+    /// String read from the dynamic string table, like the name of a needed library
+    String(String),
+    Flags(DynamicFlags),
+    Flags1(DynamicFlags1),
+    /// The value is ignored, only the presence of the entry matters
+    Ignored,
+    /// OS, processor specific or otherwise uninterpreted value, like the tag `DT_PLTREL` holds
+    Other(u64),
+}
+
+impl DynamicValue {
+    /// Interprets the value `d_un` of an entry tagged `tag`, reading strings from `strtab`
+    pub fn new(tag: DynamicTag, d_un: Addr, strtab: &StringTable) -> Result<Self, StringError> {
+        use DynamicTag::*;
+        let value = match tag {
+            PltGot | Hash | StrTab | SymTab | RelA | Init | Fini | Rel | Debug | JmpRel
+            | InitArray | FiniArray | PreinitArray | SymTabShndx | Relr | AndroidRel | AndroidRela
+            | GnuHash | VerSym | VerDef | VerNeed => Self::Address(d_un),
+            PltRelSz | RelASz | RelAEnt | StrSz | SymEnt | RelSz | RelEnt | InitArraySz
+            | FiniArraySz | PreinitArraySz | RelrSz | RelrEnt | AndroidRelSz | AndroidRelaSz => {
+                Self::Bytes(d_un.0)
+            }
+            RelaCount | RelCount | VerDefNum | VerNeedNum => Self::Count(d_un.0),
+            Needed | SoName | RPath | RunPath => Self::String(strtab.get(d_un.into())?.into()),
+            Flags => Self::Flags(DynamicFlags::from_bits_truncate(d_un.0)),
+            Flags1 => Self::Flags1(DynamicFlags1::from_bits_truncate(d_un.0)),
+            Null | Symbolic | TextRel | BindNow => Self::Ignored,
+            PltRel | OsSpecific(_) | ProcSpecific(_) => Self::Other(d_un.0),
+        };
+        Ok(value)
+    }
+}
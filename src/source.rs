@@ -0,0 +1,128 @@
+//! Module describing the sources a file can be parsed from, other than a slice already in memory
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    ops::Range,
+};
+
+use crate::{ElfError, ParseError};
+
+/// A source of bytes which can be read at any offset, like a file or a block device
+pub trait ReadAt {
+    /// Returns the total size, in bytes, of the source
+    fn size(&self) -> io::Result<u64>;
+    /// Fills `buf` with the bytes found at `offset` in the source
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()>;
+}
+
+impl ReadAt for [u8] {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let start = usize::try_from(offset).map_err(|_| io::ErrorKind::UnexpectedEof)?;
+        let bytes = start
+            .checked_add(buf.len())
+            .and_then(|end| self.get(start..end))
+            .ok_or(io::ErrorKind::UnexpectedEof)?;
+        buf.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl ReadAt for File {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        // `Read` and `Seek` are implemented for `&File`, which lets us read through a shared
+        // reference. The cursor of the file is moved as a consequence.
+        let mut file = self;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buf)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ReadAt for memmap2::Mmap {
+    fn size(&self) -> io::Result<u64> {
+        self[..].size()
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        self[..].read_at(offset, buf)
+    }
+}
+
+/// Adapter reading from any `Read + Seek` source, such as a `BufReader` or a custom storage
+/// backend
+pub struct SeekSource<R> {
+    inner: RefCell<R>,
+}
+
+impl<R: Read + Seek> SeekSource<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner: RefCell::new(inner) }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<R: Read + Seek> ReadAt for SeekSource<R> {
+    fn size(&self) -> io::Result<u64> {
+        self.inner.borrow_mut().seek(SeekFrom::End(0))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        inner.seek(SeekFrom::Start(offset))?;
+        inner.read_exact(buf)
+    }
+}
+
+/// Hands out the bytes of a file range, either borrowed from memory or read from a source
+pub(crate) trait Fetch<'a> {
+    fn fetch(&self, offset: usize, len: usize) -> Result<Cow<'a, [u8]>, ElfError>;
+    /// Returns the size of the whole file
+    fn size(&self) -> Result<u64, ElfError>;
+}
+
+impl<'a> Fetch<'a> for &'a [u8] {
+    fn fetch(&self, offset: usize, len: usize) -> Result<Cow<'a, [u8]>, ElfError> {
+        let end = offset.checked_add(len).ok_or(ParseError::OutOfBounds)?;
+        let range = Range { start: offset, end };
+        let bytes: &'a [u8] = self;
+        Ok(Cow::Borrowed(bytes.get(range.clone()).ok_or(ParseError::BadRange(range))?))
+    }
+
+    fn size(&self) -> Result<u64, ElfError> {
+        Ok(self.len() as u64)
+    }
+}
+
+/// Reads every requested range from a `ReadAt` source into an owned buffer
+pub(crate) struct FromSource<'s, S: ?Sized>(pub &'s S);
+
+impl<S: ReadAt + ?Sized> Fetch<'static> for FromSource<'_, S> {
+    fn fetch(&self, offset: usize, len: usize) -> Result<Cow<'static, [u8]>, ElfError> {
+        // Check the range against the size of the source before trusting `len` with an allocation
+        let end = offset.checked_add(len).ok_or(ParseError::OutOfBounds)?;
+        if end as u64 > self.0.size()? {
+            return Err(ParseError::BadRange(Range { start: offset, end }).into());
+        }
+
+        let mut buf = vec![0; len];
+        self.0.read_at(offset as u64, &mut buf)?;
+        Ok(Cow::Owned(buf))
+    }
+
+    fn size(&self) -> Result<u64, ElfError> {
+        Ok(self.0.size()?)
+    }
+}
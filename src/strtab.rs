@@ -0,0 +1,46 @@
+//! Module describing string tables, like `.shstrtab`, `.strtab` and `.dynstr`, which hold the null
+//! terminated strings other structures refer to by offset
+use std::borrow::Cow;
+
+use crate::{Reader, StringError};
+
+/// A string table, borrowing its bytes from the section or segment holding it
+#[derive(Debug, Clone, Copy)]
+pub struct StringTable<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> StringTable<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Returns the raw bytes of the table
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the string starting at `offset`. Offsets may point in the middle of a string, which
+    /// linkers use to share suffixes between names. Invalid UTF-8 is replaced, without copying
+    /// valid strings.
+    pub fn get(&self, offset: usize) -> Result<Cow<'a, str>, StringError> {
+        if offset >= self.bytes.len() {
+            return Err(StringError::OffsetOutOfBounds(offset));
+        }
+        let string = Reader::from_bytes(self.bytes)
+            .read_cstr_at(offset)
+            .map_err(|_| StringError::Unterminated(offset))?;
+        Ok(String::from_utf8_lossy(string))
+    }
+
+    /// Returns an iterator over every string in the table, with its offset. Bytes after the last
+    /// null terminator are not a string and are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Cow<'a, str>)> + 'a {
+        let mut reader = Reader::from_bytes(self.bytes);
+        std::iter::from_fn(move || {
+            let offset = reader.position();
+            let string = reader.read_cstr().ok()?;
+            Some((offset, String::from_utf8_lossy(string)))
+        })
+    }
+}
@@ -1,8 +1,13 @@
 //! Module describing and parsing the symbol table of Elf files
+use core::fmt;
+
 use thiserror::Error;
 
 use crate::{
     Addr,
+    Class,
+    ParseError,
+    Reader,
 };
 
 /// Lower bound for OS specific use
@@ -14,23 +19,31 @@ const LOPROC: u8 = 13;
 /// Higher bound for processor specific use
 const HIPROC: u8 = 15;
 
+/// GNU indirect function symbol type, the first OS specific one
+pub const STT_GNU_IFUNC: u8 = 10;
+/// GNU unique symbol binding, the first OS specific one
+pub const STB_GNU_UNIQUE: u8 = 10;
+
 /// Section index used to mark an undefined or meaningless section reference
-const SHN_UNDEF: u16 = 0;
+pub const SHN_UNDEF: u16 = 0;
+/// Lowest section index reserved for special meanings, like `SHN_ABS`, rather than sections
+pub const SHN_LORESERVE: u16 = 0xFF00;
 /// Section index used to indicate that the corresponding reference is an absolute value
-const SHN_ABS: u16 = 0xFFF1;
+pub const SHN_ABS: u16 = 0xFFF1;
 /// Section index used to indicate a symbol that has been declared a common block
 /// (Fortran COMMON or C tentatic declaration)
-const SHN_COMMON: u16 = 0xFFF2;
+pub const SHN_COMMON: u16 = 0xFFF2;
 
 /// The first sybol table entry is reserved and must be all zeroes.
 /// The symbolic constant STN_UNDEF is used to refer to this entry.
+#[derive(Debug, Clone, PartialEq)]
 pub struct SymbolEntry {
     /// Contains the offset, in bytes, to the symbol name, relatice to the start of the symbol
     /// string table. If this field contains zero, the symbol has no name.
     st_name: u32,
     /// Contains the symbol type and its binding attributes
     st_info: SymbolInfo,
-    /// Reserved for future use; must be zero
+    /// Holds the visibility of the symbol in its lowest two bits
     st_other: u8,
     /// Section table index of the section in which the symbol is defined. For undefined symbols,
     /// this field contains `SHN_UNDEF`; For absolute symbols, it contains `SHN_ABS`; and for
@@ -44,13 +57,36 @@ pub struct SymbolEntry {
 }
 
 impl SymbolEntry {
+    /// Returns the size, in bytes, of a symbol table entry for the given `class`
+    pub fn size(class: Class) -> usize {
+        match class {
+            Class::Elf32 => 16,
+            Class::Elf64 => 24,
+        }
+    }
+
     pub fn parse(reader: &mut Reader) -> Result<Self, SymbolError> {
         let st_name = reader.read_u32()?;
-        let st_info = SymbolInfo::try_from(reader.read_u8())?;
-        let st_other = reader.read_u8()?;
-        let st_shndx = reader.read_u16()?;
-        let st_value = Addr::from(reader.read_u64()?);
-        let st_size = reader.read_u64()?;
+        // 32-bit entries keep the value and size right after the name, while 64-bit entries move
+        // them at the end, to keep them aligned.
+        let (st_info, st_other, st_shndx, st_value, st_size) = match reader.class {
+            Class::Elf32 => {
+                let st_value = Addr::parse(reader)?;
+                let st_size = reader.read_word()?;
+                let st_info = SymbolInfo::try_from(reader.read_u8()?)?;
+                let st_other = reader.read_u8()?;
+                let st_shndx = reader.read_u16()?;
+                (st_info, st_other, st_shndx, st_value, st_size)
+            }
+            Class::Elf64 => {
+                let st_info = SymbolInfo::try_from(reader.read_u8()?)?;
+                let st_other = reader.read_u8()?;
+                let st_shndx = reader.read_u16()?;
+                let st_value = Addr::parse(reader)?;
+                let st_size = reader.read_word()?;
+                (st_info, st_other, st_shndx, st_value, st_size)
+            }
+        };
         Ok(Self {
             st_name,
             st_info,
@@ -60,9 +96,138 @@ impl SymbolEntry {
             st_size,
         })
     }
+
+    pub fn st_name(&self) -> u32 {
+        self.st_name
+    }
+
+    pub fn st_info(&self) -> SymbolInfo {
+        self.st_info
+    }
+
+    pub fn st_other(&self) -> u8 {
+        self.st_other
+    }
+
+    /// Returns the visibility of the symbol, decoded from `st_other`
+    pub fn visibility(&self) -> SymbolVisibility {
+        SymbolVisibility::from(self.st_other)
+    }
+
+    pub fn st_shndx(&self) -> u16 {
+        self.st_shndx
+    }
+
+    pub fn st_value(&self) -> Addr {
+        self.st_value
+    }
+
+    pub fn st_size(&self) -> u64 {
+        self.st_size
+    }
+}
+
+/// Prints the entry the way `readelf --syms` does, without the index and name columns
+impl fmt::Display for SymbolEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shndx = match self.st_shndx {
+            SHN_UNDEF => "UND".to_string(),
+            SHN_ABS => "ABS".to_string(),
+            SHN_COMMON => "COM".to_string(),
+            shndx => shndx.to_string(),
+        };
+        write!(
+            f,
+            "{:016x} {:5} {:7} {:6} {:8}{:>4}",
+            self.st_value.0,
+            self.st_size,
+            self.st_info.st_type().to_string(),
+            self.st_info.st_binding().to_string(),
+            self.visibility().to_string(),
+            shndx,
+        )
+    }
+}
+
+/// A symbol table entry together with its resolved name
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    /// Name of the symbol, read from the string table linked to the symbol table
+    pub name: String,
+    /// Section index of the symbol. Same as `st_shndx`, unless it is `SHN_XINDEX`, in which case
+    /// the real index is read from the `.symtab_shndx` section.
+    pub shndx: u32,
+    /// The symbol table entry
+    pub entry: SymbolEntry,
+}
+
+#[cfg(feature = "demangle")]
+impl Symbol {
+    /// Returns the name of the symbol demangled, or the name itself when it is not a mangled Rust
+    /// or C++ name
+    pub fn demangled_name(&self) -> String {
+        demangle(&self.name)
+    }
+}
+
+/// Demangles `name` as a Rust symbol, without its hash, or else as a C++ symbol. Names which are
+/// neither are returned unchanged.
+#[cfg(feature = "demangle")]
+pub fn demangle(name: &str) -> String {
+    if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+        return format!("{:#}", demangled);
+    }
+    cpp_demangle::Symbol::new(name)
+        .ok()
+        .and_then(|symbol| symbol.demangle(&Default::default()).ok())
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// A problem found while validating a symbol table entry
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolIssue {
+    /// The name offset is not the start of a string in the string table
+    BadNameOffset(u32),
+    /// The section index is neither a section nor a reserved index
+    BadSectionIndex(u32),
+    /// The section index is `SHN_XINDEX`, but there is no extended index for the symbol
+    MissingExtendedIndex,
+    /// The value falls outside of the section the symbol is defined in
+    ValueOutsideSection { value: Addr, shndx: u32 },
+}
+
+/// An issue of the symbol at `index` in the symbol table section `table`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolDiagnostic {
+    /// Section index of the symbol table
+    pub table: usize,
+    /// Index of the symbol in its table
+    pub index: usize,
+    pub issue: SymbolIssue,
+}
+
+/// An undefined dynamic symbol, which the object imports from the objects it depends on
+#[derive(Debug)]
+pub struct Import {
+    pub name: String,
+    /// Version the symbol is needed with, like `GLIBC_2.14`
+    pub version: Option<String>,
+    /// Object the version is needed from, hinting at the library providing the symbol
+    pub library: Option<String>,
+    pub entry: SymbolEntry,
+}
+
+/// A defined dynamic symbol other objects can bind to
+#[derive(Debug)]
+pub struct Export {
+    pub name: String,
+    /// Version the symbol is defined with
+    pub version: Option<String>,
+    pub entry: SymbolEntry,
 }
 
 /// Information regarding a symbol table entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct SymbolInfo {
     /// Type attributes contained in the low-order four bits.
     st_type: SymbolType,
@@ -81,50 +246,148 @@ impl TryFrom<u8> for SymbolInfo {
     }
 }
 
+impl SymbolInfo {
+    pub fn st_type(&self) -> SymbolType {
+        self.st_type
+    }
+
+    pub fn st_binding(&self) -> SymbolBinding {
+        self.st_binding
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SymbolType {
     NoType,
     Object,
     Func,
     Section,
     File,
+    /// Uninitialised common block
+    Common,
+    /// Thread-local storage entity
+    Tls,
+    /// GNU indirect function, whose value is a resolver returning the address of the function
+    GnuIfunc,
     OsSpecific(u8),
     ProcSpecific(u8),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SymbolBinding {
     Local,
     Global,
     Weak,
+    /// GNU unique global symbol, of which the dynamic linker uses a single definition in the whole
+    /// process
+    GnuUnique,
     OsSpecific(u8),
     ProcSpecific(u8),
 }
 
 impl TryFrom<u8> for SymbolType {
     type Error = SymbolError;
+    // The GNU extension is matched before the rest of the OS specific range
+    #[allow(clippy::match_overlapping_arm)]
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
-            0 => Self::NoType,
-            1 => Self::Object,
-            2 => Self::Func,
-            3 => Self::Section,
-            4 => Self::File,
-            LOOS..=HIOS => OsSpecific(value),
-            LOPROC..=HIPROC => ProcSpecific(value),
-            _ => return Err(SymbolError::UnknownSymbolType(value))
+            0 => Ok(Self::NoType),
+            1 => Ok(Self::Object),
+            2 => Ok(Self::Func),
+            3 => Ok(Self::Section),
+            4 => Ok(Self::File),
+            5 => Ok(Self::Common),
+            6 => Ok(Self::Tls),
+            STT_GNU_IFUNC => Ok(Self::GnuIfunc),
+            LOOS..=HIOS => Ok(Self::OsSpecific(value)),
+            LOPROC..=HIPROC => Ok(Self::ProcSpecific(value)),
+            _ => Err(SymbolError::UnknownSymbolType(value))
         }
     }
 }
 
 impl TryFrom<u8> for SymbolBinding {
     type Error = SymbolError;
+    // The GNU extension is matched before the rest of the OS specific range
+    #[allow(clippy::match_overlapping_arm)]
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
-            0 => Self::Local,
-            1 => Self::Global,
-            2 => Self::Weak,
-            LOOS..=HIOS => OsSpecific(value),
-            LOPROC..=HIPROC => ProcSpecific(value),
-            _ => return Err(SymbolError::UnknownSymbolBinding(value))
+            0 => Ok(Self::Local),
+            1 => Ok(Self::Global),
+            2 => Ok(Self::Weak),
+            STB_GNU_UNIQUE => Ok(Self::GnuUnique),
+            LOOS..=HIOS => Ok(Self::OsSpecific(value)),
+            LOPROC..=HIPROC => Ok(Self::ProcSpecific(value)),
+            _ => Err(SymbolError::UnknownSymbolBinding(value))
+        }
+    }
+}
+
+/// Visibility of a symbol outside the component defining it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolVisibility {
+    /// Visibility given by the binding of the symbol
+    Default,
+    /// Processor specific hidden visibility
+    Internal,
+    /// Not visible to other components
+    Hidden,
+    /// Visible to other components, but references from the defining component always bind to
+    /// its own definition
+    Protected,
+}
+
+/// Prints the visibility the way `readelf` does
+impl fmt::Display for SymbolVisibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "DEFAULT"),
+            Self::Internal => write!(f, "INTERNAL"),
+            Self::Hidden => write!(f, "HIDDEN"),
+            Self::Protected => write!(f, "PROTECTED"),
+        }
+    }
+}
+
+impl From<u8> for SymbolVisibility {
+    fn from(st_other: u8) -> Self {
+        match st_other & 0x3 {
+            0 => Self::Default,
+            1 => Self::Internal,
+            2 => Self::Hidden,
+            _ => Self::Protected,
+        }
+    }
+}
+
+/// Prints the type the way `readelf` does
+impl fmt::Display for SymbolType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoType => write!(f, "NOTYPE"),
+            Self::Object => write!(f, "OBJECT"),
+            Self::Func => write!(f, "FUNC"),
+            Self::Section => write!(f, "SECTION"),
+            Self::File => write!(f, "FILE"),
+            Self::Common => write!(f, "COMMON"),
+            Self::Tls => write!(f, "TLS"),
+            Self::GnuIfunc => write!(f, "IFUNC"),
+            Self::OsSpecific(value) => write!(f, "<OS specific>: {}", value),
+            Self::ProcSpecific(value) => write!(f, "<processor specific>: {}", value),
+        }
+    }
+}
+
+/// Prints the binding the way `readelf` does
+impl fmt::Display for SymbolBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local => write!(f, "LOCAL"),
+            Self::Global => write!(f, "GLOBAL"),
+            Self::Weak => write!(f, "WEAK"),
+            Self::GnuUnique => write!(f, "UNIQUE"),
+            Self::OsSpecific(value) => write!(f, "<OS specific>: {}", value),
+            Self::ProcSpecific(value) => write!(f, "<processor specific>: {}", value),
         }
     }
 }
@@ -135,4 +398,18 @@ pub enum SymbolError {
     UnknownSymbolType(u8),
     #[error("Symbol binding referenced by value {0} is unknown")]
     UnknownSymbolBinding(u8),
+    #[error("Cannot find the number of entries in the symbol table")]
+    UnknownSymbolCount,
+    #[error("Section index {0} of the string table is out of bounds")]
+    BadStringTableIndex(u32),
+    #[error("Section index {0} of the symbol table is out of bounds")]
+    BadSymbolTableIndex(u32),
+    #[error("Symbol name at offset {0} is not in the string table")]
+    BadNameOffset(u32),
+    #[error("Symbol {0} has an extended section index, but there is no index for it")]
+    BadExtendedIndex(usize),
+    #[error("Symbol index {0} is past the end of the symbol table")]
+    BadSymbolIndex(u32),
+    #[error("Symbol parsing error {0}")]
+    ParseError(#[from] ParseError),
 }
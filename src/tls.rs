@@ -0,0 +1,81 @@
+//! Module describing thread local storage, whose initial contents the `PtTls` segment holds. Each
+//! thread gets its own copy of them, the TLS block, at a fixed distance from its thread pointer
+//! for the executable and the libraries loaded with it.
+use crate::{Addr, Machine};
+
+/// Initialization image of the TLS block, `.tdata`, followed by zeros up to `mem_size`, `.tbss`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TlsTemplate<'a> {
+    /// Initial contents of the block, `file_size` bytes long
+    pub image: &'a [u8],
+    /// Virtual address of the image, which only matters modulo `align`
+    pub vaddr: Addr,
+    pub file_size: u64,
+    /// Size of the whole block, including the zeroed part
+    pub mem_size: u64,
+    /// Alignment of the block, at least 1
+    pub align: u64,
+}
+
+/// Way a machine arranges the TLS blocks around the thread pointer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TlsVariant {
+    /// The thread pointer points to the thread control block, with the blocks after it
+    I {
+        /// Size reserved for the thread control block between the thread pointer and the first
+        /// block
+        tcb_size: u64,
+    },
+    /// The thread pointer points right past the blocks, which come before it
+    II,
+}
+
+impl TlsVariant {
+    /// Returns the variant `machine` uses, as its psABI and glibc define it, for the machines
+    /// known here
+    pub fn of(machine: Machine) -> Option<Self> {
+        match machine {
+            Machine::X86 | Machine::AmdX86_64 => Some(Self::II),
+            Machine::AArch64 => Some(Self::I { tcb_size: 16 }),
+            Machine::Arm => Some(Self::I { tcb_size: 8 }),
+            // The control block comes before the thread pointer
+            Machine::RiscV => Some(Self::I { tcb_size: 0 }),
+            _ => None,
+        }
+    }
+}
+
+/// Place of the TLS block of the executable, the first in the static TLS area
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StaticTlsLayout {
+    pub variant: TlsVariant,
+    /// Offset of the start of the block from the thread pointer, negative for variant II
+    pub offset: i64,
+}
+
+impl TlsTemplate<'_> {
+    /// Lays out the block in the static TLS area of `machine`, as the first one, which is where
+    /// the block of the executable goes. The block is placed at an address congruent to `vaddr`
+    /// modulo `align`, given a thread pointer aligned to `align`, like glibc does.
+    pub fn static_layout(&self, machine: Machine) -> Option<StaticTlsLayout> {
+        let variant = TlsVariant::of(machine)?;
+        let align = self.align.max(1);
+        // How far the block starts past an aligned address
+        let firstbyte = self.vaddr.0 % align;
+        let offset = match variant {
+            TlsVariant::I { tcb_size } => {
+                (align_up(tcb_size.saturating_sub(firstbyte), align) + firstbyte) as i64
+            }
+            TlsVariant::II => {
+                // The gap past the end of the block must keep the start congruent to `vaddr`
+                let size = align_up(self.mem_size.saturating_add(firstbyte), align);
+                firstbyte as i64 - size as i64
+            }
+        };
+        Some(StaticTlsLayout { variant, offset })
+    }
+}
+
+fn align_up(value: u64, align: u64) -> u64 {
+    value.saturating_add(align - 1) / align * align
+}
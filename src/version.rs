@@ -0,0 +1,245 @@
+//! Module describing GNU symbol versioning: the versions an object defines (`.gnu.version_d`), the
+//! versions it needs from other objects (`.gnu.version_r`) and the version of every dynamic symbol
+//! (`.gnu.version`).
+use thiserror::Error;
+
+use crate::{Class, Endianness, ParseError, Reader, SegmentError, StringError, StringTable};
+
+/// Version index of local symbols
+pub const VER_NDX_LOCAL: u16 = 0;
+/// Version index of global symbols which are not versioned
+pub const VER_NDX_GLOBAL: u16 = 1;
+/// Bit of a version index marking a version which is not the default one of the symbol
+pub const VERSYM_HIDDEN: u16 = 0x8000;
+/// Flag of the version definition naming the object itself, rather than a version
+pub const VER_FLG_BASE: u16 = 0x1;
+
+/// A version defined by the object, `Elf64_Verdef` with its auxiliary entries
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionDefinition {
+    pub vd_flags: u16,
+    /// Version index symbols refer to this version with
+    pub vd_ndx: u16,
+    /// Hash of the version name
+    pub vd_hash: u32,
+    /// Name of the version, like `GLIBC_2.34`
+    pub name: String,
+    /// Names of the versions this one inherits from
+    pub parents: Vec<String>,
+}
+
+/// The versions needed from one object, `Elf64_Verneed`
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionRequirement {
+    /// Name of the object defining the versions, like `libc.so.6`
+    pub file: String,
+    pub versions: Vec<VersionNeeded>,
+}
+
+/// A version needed from another object, `Elf64_Vernaux`
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionNeeded {
+    /// Hash of the version name
+    pub vna_hash: u32,
+    pub vna_flags: u16,
+    /// Version index symbols refer to this version with
+    pub vna_other: u16,
+    /// Name of the version, like `GLIBC_2.14`
+    pub name: String,
+}
+
+/// The version of a dynamic symbol, resolved from its version index
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolVersion {
+    /// Name of the version
+    pub name: String,
+    /// Whether the version is not the default one, which only explicit references bind to
+    pub hidden: bool,
+    /// Object the version is needed from, or `None` for versions defined by this object
+    pub file: Option<String>,
+}
+
+impl SymbolVersion {
+    /// Resolves the version index `versym` against the version tables of the object. Local and
+    /// unversioned global symbols have no version.
+    pub fn resolve(
+        versym: u16,
+        definitions: &[VersionDefinition],
+        requirements: &[VersionRequirement],
+    ) -> Option<Self> {
+        let hidden = versym & VERSYM_HIDDEN != 0;
+        let index = versym & !VERSYM_HIDDEN;
+        if index == VER_NDX_LOCAL || index == VER_NDX_GLOBAL {
+            return None;
+        }
+
+        let defined = definitions
+            .iter()
+            .find(|def| def.vd_ndx == index)
+            .map(|def| Self { name: def.name.clone(), hidden, file: None });
+        defined.or_else(|| {
+            requirements.iter().find_map(|req| {
+                let needed = req.versions.iter().find(|needed| needed.vna_other == index)?;
+                Some(Self { name: needed.name.clone(), hidden, file: Some(req.file.clone()) })
+            })
+        })
+    }
+}
+
+/// Parses `count` version definitions from the table `bytes` holds, resolving names through
+/// `strtab`. Entries are chained by offsets relative to each entry.
+pub fn parse_definitions(
+    bytes: &[u8],
+    count: usize,
+    strtab: &StringTable,
+    class: Class,
+    endianness: Endianness,
+) -> Result<Vec<VersionDefinition>, Error> {
+    let table = Reader::new(bytes, class, endianness);
+    // Every entry is read through a reader starting at its offset, bounded by the table
+    let reader = |offset| table.sub_reader(offset..bytes.len());
+    let mut definitions = vec![];
+    let mut offset = 0;
+    for _ in 0..count {
+        let mut verdef = reader(offset)?;
+        let _vd_version = verdef.read_u16()?;
+        let vd_flags = verdef.read_u16()?;
+        let vd_ndx = verdef.read_u16()?;
+        let vd_cnt = verdef.read_u16()?;
+        let vd_hash = verdef.read_u32()?;
+        let vd_aux = verdef.read_u32()? as usize;
+        let vd_next = verdef.read_u32()? as usize;
+
+        // The first auxiliary entry names the version, the following ones its parents
+        let mut names = vec![];
+        let mut aux_offset = offset.checked_add(vd_aux).ok_or(ParseError::OutOfBounds)?;
+        for _ in 0..vd_cnt {
+            let mut verdaux = reader(aux_offset)?;
+            let vda_name = verdaux.read_u32()?;
+            let vda_next = verdaux.read_u32()? as usize;
+            names.push(String::from(strtab.get(vda_name as usize)?));
+            if vda_next == 0 {
+                break;
+            }
+            aux_offset = aux_offset.checked_add(vda_next).ok_or(ParseError::OutOfBounds)?;
+        }
+        if names.is_empty() {
+            return Err(Error::Unnamed(vd_ndx));
+        }
+        let name = names.remove(0);
+        definitions.push(VersionDefinition { vd_flags, vd_ndx, vd_hash, name, parents: names });
+
+        if vd_next == 0 {
+            break;
+        }
+        offset = offset.checked_add(vd_next).ok_or(ParseError::OutOfBounds)?;
+    }
+    Ok(definitions)
+}
+
+/// Parses `count` version requirements from the table `bytes` holds, resolving names through
+/// `strtab`. Entries are chained by offsets relative to each entry.
+pub fn parse_requirements(
+    bytes: &[u8],
+    count: usize,
+    strtab: &StringTable,
+    class: Class,
+    endianness: Endianness,
+) -> Result<Vec<VersionRequirement>, Error> {
+    let table = Reader::new(bytes, class, endianness);
+    // Every entry is read through a reader starting at its offset, bounded by the table
+    let reader = |offset| table.sub_reader(offset..bytes.len());
+    let mut requirements = vec![];
+    let mut offset = 0;
+    for _ in 0..count {
+        let mut verneed = reader(offset)?;
+        let _vn_version = verneed.read_u16()?;
+        let vn_cnt = verneed.read_u16()?;
+        let vn_file = verneed.read_u32()?;
+        let vn_aux = verneed.read_u32()? as usize;
+        let vn_next = verneed.read_u32()? as usize;
+
+        let mut versions = vec![];
+        let mut aux_offset = offset.checked_add(vn_aux).ok_or(ParseError::OutOfBounds)?;
+        for _ in 0..vn_cnt {
+            let mut vernaux = reader(aux_offset)?;
+            let vna_hash = vernaux.read_u32()?;
+            let vna_flags = vernaux.read_u16()?;
+            let vna_other = vernaux.read_u16()?;
+            let vna_name = vernaux.read_u32()?;
+            let vna_next = vernaux.read_u32()? as usize;
+            let name = strtab.get(vna_name as usize)?.into();
+            versions.push(VersionNeeded { vna_hash, vna_flags, vna_other, name });
+            if vna_next == 0 {
+                break;
+            }
+            aux_offset = aux_offset.checked_add(vna_next).ok_or(ParseError::OutOfBounds)?;
+        }
+        let file = strtab.get(vn_file as usize)?.into();
+        requirements.push(VersionRequirement { file, versions });
+
+        if vn_next == 0 {
+            break;
+        }
+        offset = offset.checked_add(vn_next).ok_or(ParseError::OutOfBounds)?;
+    }
+    Ok(requirements)
+}
+
+/// Highest version of an interface the object needs, which the system running it must provide
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequiredVersion {
+    /// Name of the interface, the part of the version name before its number, like `GLIBC`
+    pub interface: String,
+    /// Numbers of the version, like `[2, 34]`
+    pub numbers: Vec<u32>,
+    /// Name of the version, like `GLIBC_2.34`
+    pub name: String,
+    /// Object the version is needed from, like `libc.so.6`
+    pub file: String,
+}
+
+/// Splits a version name like `GLIBC_2.34` in its interface and the numbers of its version.
+/// Names without a number, like `GLIBC_PRIVATE`, return `None`.
+pub fn split_version_name(name: &str) -> Option<(&str, Vec<u32>)> {
+    let (interface, version) = name.rsplit_once('_')?;
+    let numbers = version.split('.').map(|number| number.parse().ok()).collect::<Option<_>>()?;
+    Some((interface, numbers))
+}
+
+/// Keeps the highest version of every interface `requirements` name, in the order the interfaces
+/// first appear
+pub fn highest_versions(requirements: &[VersionRequirement]) -> Vec<RequiredVersion> {
+    let mut highest: Vec<RequiredVersion> = vec![];
+    for requirement in requirements {
+        for version in &requirement.versions {
+            let Some((interface, numbers)) = split_version_name(&version.name) else {
+                continue;
+            };
+            let required = RequiredVersion {
+                interface: interface.to_string(),
+                numbers,
+                name: version.name.clone(),
+                file: requirement.file.clone(),
+            };
+            match highest.iter_mut().find(|known| known.interface == interface) {
+                Some(known) if known.numbers < required.numbers => *known = required,
+                Some(_) => {}
+                None => highest.push(required),
+            }
+        }
+    }
+    highest
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Version definition {0} has no name")]
+    Unnamed(u16),
+    #[error("Version table parsing error {0}")]
+    ParseError(#[from] ParseError),
+    #[error("Version table lookup error {0}")]
+    SegmentError(#[from] SegmentError),
+    #[error("Version name error {0}")]
+    StringError(#[from] StringError),
+}
@@ -0,0 +1,137 @@
+//! Module for writing Elf files back to bytes, the counterpart of `Reader`
+use thiserror::Error;
+
+use crate::{class::Class, endian::Endianness, Addr, FileOffset, ProgramHeaderError, Size};
+
+/// Growable buffer that encodes fields with the class and byte order of the file being written
+pub struct Writer {
+    bytes: Vec<u8>,
+    index: usize,
+    /// Class of the file being written, which decides the width of `write_word`
+    pub class: Class,
+    /// Byte order used when writing multi-byte integers
+    pub endianness: Endianness,
+}
+
+impl Writer {
+    pub fn new(class: Class, endianness: Endianness) -> Self {
+        Writer { bytes: vec![], index: 0, class, endianness }
+    }
+
+    /// Returns the bytes written so far
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the offset of the next byte to write
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Moves to `offset`. Writing past the end of the bytes fills the gap with zeros.
+    pub fn set_position(&mut self, offset: usize) {
+        self.index = offset;
+    }
+
+    /// Writes `bytes` at the current position, over whatever was there before
+    pub fn write_slice(&mut self, bytes: &[u8]) {
+        let end = self.index + bytes.len();
+        if end > self.bytes.len() {
+            self.bytes.resize(end, 0);
+        }
+        self.bytes[self.index..end].copy_from_slice(bytes);
+        self.index = end;
+    }
+
+    /// Writes `count` zero bytes
+    pub fn write_zeros(&mut self, count: usize) {
+        self.write_slice(&vec![0; count]);
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.write_slice(&[value]);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        match self.endianness {
+            Endianness::Little => self.write_slice(&value.to_le_bytes()),
+            Endianness::Big => self.write_slice(&value.to_be_bytes()),
+        }
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        match self.endianness {
+            Endianness::Little => self.write_slice(&value.to_le_bytes()),
+            Endianness::Big => self.write_slice(&value.to_be_bytes()),
+        }
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        match self.endianness {
+            Endianness::Little => self.write_slice(&value.to_le_bytes()),
+            Endianness::Big => self.write_slice(&value.to_be_bytes()),
+        }
+    }
+
+    /// Writes a 4 bytes word for 32-bit files and an 8 bytes word for 64-bit files. Fails when
+    /// `value` does not fit in a 32-bit word, leaving the position alone.
+    pub fn write_word(&mut self, value: u64) -> Result<(), Error> {
+        match self.class {
+            Class::Elf32 => {
+                let value = u32::try_from(value).map_err(|_| Error::WordTooLarge(value))?;
+                self.write_u32(value)
+            }
+            Class::Elf64 => self.write_u64(value),
+        }
+        Ok(())
+    }
+}
+
+/// Copies `patch` over `bytes` at `offset`, leaving every other byte alone. Fails, changing
+/// nothing, when the patch does not fit.
+pub fn patch(bytes: &mut [u8], offset: usize, patch: &[u8]) -> Result<(), Error> {
+    let size = patch.len();
+    let range = offset
+        .checked_add(size)
+        .filter(|end| *end <= bytes.len())
+        .map(|end| offset..end)
+        .ok_or(Error::PatchOutOfBounds { offset, size })?;
+    bytes[range].copy_from_slice(patch);
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Value {0:#x} does not fit in a 32-bit word")]
+    WordTooLarge(u64),
+    #[error("Contents at offset {offset} hold {len} bytes, but the header says {size}")]
+    SizeMismatch { offset: FileOffset, size: Size, len: usize },
+    #[error("Patching {size} bytes at offset {offset} goes past the end")]
+    PatchOutOfBounds { offset: usize, size: usize },
+    #[error("Segment at {0} does not come after the pages of the loadable segments")]
+    BadSegmentAddr(Addr),
+    #[error("There is no program header at index {0}")]
+    NoSegment(usize),
+    #[error("Segment {inner} would have permissions its loadable segment {outer} does not give")]
+    SegmentFlagsConflict { inner: usize, outer: usize },
+    #[error("{0} program headers do not fit in e_phnum")]
+    TooManySegments(usize),
+    #[error("Section name string table index {0} does not fit in e_shstrndx")]
+    TooManySections(usize),
+    #[error("Relocatable files need their symbol table and cannot be stripped")]
+    StripRelocatable,
+    #[error("The file has no program interpreter to replace")]
+    NoInterpreter,
+    #[error("The file has no dynamic table to edit")]
+    NoDynamicTable,
+    #[error("The dynamic table has no dynamic string table")]
+    NoDynamicStringTable,
+    #[error("The file has no section named {0}")]
+    NoSection(String),
+    #[error("I/O error {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Address {0} is not backed by the contents of the file")]
+    NotInFile(Addr),
+    #[error("Edited dynamic table does not parse back {0}")]
+    BadDynamicTable(#[from] ProgramHeaderError),
+}